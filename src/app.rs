@@ -0,0 +1,1309 @@
+// 標準ライブラリのインポート
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+// サードパーティクレートのインポート
+use camera_app::burst::{MAX_BURST_COUNT, MAX_BURST_INTERVAL_MS};
+use camera_app::capture::FpsCounter;
+use camera_app::codec::{codec_label, VideoContainer, VideoFormat, VIDEO_CODECS};
+use camera_app::devices::{DeviceInfo, DeviceProbe};
+use camera_app::error::CameraError;
+use camera_app::photo::{
+    ChromaSubsampling, PhotoFormat, MAX_BEST_OF_FRAMES, MAX_JPEG_QUALITY, MAX_PNG_COMPRESSION,
+    MAX_WEBP_QUALITY, MIN_WEBP_QUALITY,
+};
+use camera_app::prerecord::MAX_PRE_RECORD_SECS;
+use camera_app::segment::{MAX_SEGMENT_MINUTES, MAX_SEGMENT_SIZE_MB};
+use camera_app::session::CameraSession;
+use camera_app::settings::{SettingsStore, MAX_SELF_TIMER_SECS};
+use camera_app::source::{
+    candidate_modes, CameraDevice, PlaybackConfig, PlaybackMode, SourceKind, TestPatternConfig,
+    VideoMode,
+};
+use camera_app::storage;
+use camera_app::timelapse::{
+    TimelapseLimit, UnfinishedTimelapse, MAX_PLAYBACK_FPS, MAX_TIMELAPSE_DURATION_MINS,
+    MAX_TIMELAPSE_FRAMES, MAX_TIMELAPSE_INTERVAL_SECS,
+};
+use camera_app::{CameraPosition, CaptureMode};
+use eframe::egui;
+
+use crate::cli::Cli;
+use crate::countdown::{Countdown, SELF_TIMER_PRESETS};
+use crate::toast::Toasts;
+
+/// 空き容量を調べ直す間隔 (毎フレーム調べるとディスクアクセスが増えるため)
+const DISK_SPACE_INTERVAL: Duration = Duration::from_secs(5);
+/// 空き容量がこの値を下回ったら警告する (バイト)
+const LOW_DISK_SPACE: u64 = 500 * 1024 * 1024;
+/// 連写の「最速」を外したときの撮影間隔 (ミリ秒)
+const DEFAULT_BURST_INTERVAL_MS: u32 = 100;
+/// タイムラプスの進捗バーの幅 (ピクセル)
+const TIMELAPSE_PROGRESS_WIDTH: f32 = 200.0;
+/// 1時間の秒数 (残り時間の表示用)
+const SECS_PER_HOUR: u64 = 3600;
+/// 1分の秒数 (残り時間の表示用)
+const SECS_PER_MINUTE: u64 = 60;
+
+/// カメラアプリケーションのメイン構造体
+///
+/// カメラセッション (撮影・録画のロジック) の上に、eGuiを使用したUI表示を載せる。
+/// フレームの取得と録画はバックグラウンドのスレッドで行われる。
+pub struct CameraApp {
+    /// カメラセッション (フレームソース・撮影・録画)
+    session: CameraSession,
+    /// 設定ファイルの読み書き
+    settings_store: SettingsStore,
+    /// 検出済みのカメラデバイス一覧 (ピッカー表示用)
+    devices: Vec<DeviceInfo>,
+    /// 実行中のデバイス検出 (検出中のみSome)
+    device_probe: Option<DeviceProbe>,
+    /// UIの描画FPSの計測 (キャプチャFPSとの比較用)
+    ui_fps: FpsCounter,
+    /// 再生する動画ファイル/画像フォルダ、またはデバイスパスの入力 (UI入力用)
+    playback_path: String,
+    /// ファイル再生をループするかどうか (UI入力用)
+    playback_loop: bool,
+    /// 画面に表示する通知 (保存先・録画の開始停止・エラー)
+    toasts: Toasts,
+    /// 保存先のディスクの空き容量 (取得できない場合はNone)
+    disk_space: Option<u64>,
+    /// 空き容量を最後に調べた時刻
+    disk_checked_at: Option<Instant>,
+    /// セルフタイマーのカウントダウン (カウントダウン中のみSome)
+    countdown: Option<Countdown>,
+    /// セルフタイマーで任意の秒数を入力中かどうか (UI入力用)
+    self_timer_custom: bool,
+    /// 前回の起動で終わらなかったタイムラプス (再開・仕上げの選択肢を表示する)
+    unfinished_timelapses: Vec<UnfinishedTimelapse>,
+}
+
+impl CameraApp {
+    /// eframe起動時に呼ばれる初期化関数
+    ///
+    /// 設定ファイル → 環境変数 → コマンドライン引数の順に反映したセッションを構築し、
+    /// カメラを開く。日本語フォント(Meiryo UI)を設定して文字化けを防ぐ。
+    pub fn new(cc: &eframe::CreationContext<'_>, cli: &Cli) -> Self {
+        // 日本語フォントの設定
+        let mut fonts = egui::FontDefinitions::default();
+
+        // Windowsの標準日本語フォント "Meiryo UI" を使用
+        // システムフォントパスからフォントを読み込む
+        if let Ok(font_data) = std::fs::read("C:\\Windows\\Fonts\\meiryo.ttc") {
+            fonts
+                .font_data
+                .insert("meiryo".to_owned(), egui::FontData::from_owned(font_data));
+
+            // ProportionalとMonospaceの両方に設定
+            fonts
+                .families
+                .entry(egui::FontFamily::Proportional)
+                .or_default()
+                .insert(0, "meiryo".to_owned());
+            fonts
+                .families
+                .entry(egui::FontFamily::Monospace)
+                .or_default()
+                .insert(0, "meiryo".to_owned());
+        }
+
+        cc.egui_ctx.set_fonts(fonts);
+
+        let (session, settings_store) = crate::load_session(cli);
+        // 接続されているカメラをバックグラウンドで検出 (UIを止めないため)
+        let device_probe = Some(DeviceProbe::spawn(session.camera_backends().to_vec()));
+
+        let self_timer_secs = session.self_timer_secs();
+        let mut app = Self {
+            session,
+            settings_store,
+            devices: Vec::new(),
+            device_probe,
+            ui_fps: FpsCounter::default(),
+            playback_path: String::new(),
+            playback_loop: true,
+            toasts: Toasts::default(),
+            disk_space: None,
+            disk_checked_at: None,
+            countdown: None,
+            self_timer_custom: self_timer_secs > 0
+                && !SELF_TIMER_PRESETS.contains(&self_timer_secs),
+            unfinished_timelapses: Vec::new(),
+        };
+        app.unfinished_timelapses = app.session.unfinished_timelapses();
+        if !app.unfinished_timelapses.is_empty() {
+            app.toasts
+                .info("前回の起動で終わらなかったタイムラプスがあります");
+        }
+
+        // 初回起動時は使える録画形式をバックグラウンドで検出 (結果は設定ファイルに記録する)
+        if app.session.video_support().is_empty() {
+            app.session.probe_video_formats();
+        }
+
+        if let Err(e) = app.session.open() {
+            app.report_error(e);
+        }
+        // 起動後すぐに録画を開始 (キオスク端末・テストスクリプト用)
+        if cli.record {
+            app.start_recording();
+        }
+        app
+    }
+
+    /// エラーを出力し、通知として表示する
+    fn report_error(&mut self, error: impl Display) {
+        log::error!("{}", error);
+        self.toasts.error(error.to_string());
+    }
+
+    /// 写真の撮影を開始 (候補の選択と保存は保存スレッドで行い、完了時に通知する)
+    fn capture_photo(&mut self) {
+        if let Err(e) = self.session.start_photo() {
+            self.report_error(e);
+        }
+    }
+
+    /// 写真の保存が終わっていれば保存先を通知する
+    fn poll_photo(&mut self) {
+        let Some(result) = self.session.poll_photo() else {
+            return;
+        };
+        match result {
+            Ok(path) => self
+                .toasts
+                .success(format!("写真を保存しました: {}", path.display())),
+            Err(e) => self.report_error(e),
+        }
+        // 保存した分の空き容量をすぐに反映
+        self.disk_checked_at = None;
+    }
+
+    /// 連写を開始 (保存は書き込みスレッドで行い、完了時に通知する)
+    fn start_burst(&mut self) {
+        if let Err(e) = self.session.start_burst() {
+            self.report_error(e);
+        }
+    }
+
+    /// 連写の保存が終わっていれば結果を通知する
+    fn poll_burst(&mut self) {
+        let Some(summary) = self.session.poll_burst() else {
+            return;
+        };
+        if !summary.paths.is_empty() {
+            self.toasts.success(format!(
+                "{}枚連写しました: {}",
+                summary.paths.len(),
+                self.session.output_dir().display()
+            ));
+        }
+        // 失敗した枚数は成功した枚数と分けて警告し、最初のエラーの内容を表示する
+        let failed = summary.errors.len();
+        if let Some(error) = summary.errors.into_iter().next() {
+            self.toasts
+                .warning(format!("連写のうち{}枚を保存できませんでした", failed));
+            self.report_error(error);
+        }
+        // 保存した分の空き容量をすぐに反映
+        self.disk_checked_at = None;
+    }
+
+    /// 複数のエラーのうち最初の1件を通知し、残りは件数だけ警告する (通知が画面を埋めないように)
+    fn report_errors(&mut self, errors: Vec<CameraError>) {
+        let count = errors.len();
+        if let Some(error) = errors.into_iter().next() {
+            self.report_error(error);
+            if count > 1 {
+                self.toasts
+                    .warning(format!("ほかに{}件の保存に失敗しました", count - 1));
+            }
+        }
+    }
+
+    /// タイムラプスを開始
+    fn start_timelapse(&mut self) {
+        if let Err(e) = self.session.start_timelapse() {
+            self.report_error(e);
+        }
+    }
+
+    /// 前回の起動で終わらなかったタイムラプスを再開、または撮影済みのフレームで仕上げる
+    fn continue_unfinished_timelapse(&mut self, dir: PathBuf, finish_now: bool) {
+        let result = if finish_now {
+            self.session.finish_unfinished_timelapse(&dir)
+        } else {
+            self.session.set_capture_mode(CaptureMode::Timelapse);
+            self.session.resume_timelapse(&dir)
+        };
+        match result {
+            Ok(()) => self
+                .unfinished_timelapses
+                .retain(|unfinished| unfinished.dir != dir),
+            Err(e) => self.report_error(e),
+        }
+    }
+
+    /// タイムラプスの書き込みスレッドが終わっていれば結果を通知する
+    fn poll_timelapse(&mut self) {
+        let Some(summary) = self.session.poll_timelapse() else {
+            return;
+        };
+        if summary.finished {
+            let saved = summary.video.as_ref().unwrap_or(&summary.dir);
+            self.toasts.success(format!(
+                "タイムラプスを保存しました ({}枚): {}",
+                summary.captured,
+                saved.display()
+            ));
+        } else {
+            self.toasts.warning(format!(
+                "タイムラプスを中断しました ({}枚、続きから再開できます): {}",
+                summary.captured,
+                summary.dir.display()
+            ));
+            self.unfinished_timelapses = self.session.unfinished_timelapses();
+        }
+        self.report_errors(summary.errors);
+        self.disk_checked_at = None;
+    }
+
+    /// 録画を開始し、保存先を通知する
+    fn start_recording(&mut self) {
+        match self.session.start_recording() {
+            Ok(path) => self
+                .toasts
+                .success(format!("録画を開始しました: {}", path.display())),
+            Err(e) => self.report_error(e),
+        }
+    }
+
+    /// 写真撮影・連写・タイムラプス・録画開始を要求 (セルフタイマーが有効な場合はカウントダウンを開始)
+    fn request_capture(&mut self, action: CaptureMode) {
+        let seconds = self.session.self_timer_secs();
+        if seconds == 0 {
+            self.run_capture(action);
+        } else {
+            log::info!("セルフタイマーを開始しました ({}秒)", seconds);
+            self.countdown = Some(Countdown::start(action, seconds));
+        }
+    }
+
+    /// 写真撮影・連写・タイムラプス・録画開始のいずれかを実行
+    fn run_capture(&mut self, action: CaptureMode) {
+        match action {
+            CaptureMode::Photo => self.capture_photo(),
+            CaptureMode::Burst => self.start_burst(),
+            CaptureMode::Timelapse => self.start_timelapse(),
+            CaptureMode::Video => self.start_recording(),
+        }
+    }
+
+    /// カウントダウンが終わっていれば撮影・録画を開始
+    fn poll_countdown(&mut self) {
+        if self.countdown.as_ref().is_some_and(|c| c.is_finished()) {
+            if let Some(countdown) = self.countdown.take() {
+                self.run_capture(countdown.action());
+            }
+        }
+    }
+
+    /// カウントダウンを取り消す
+    fn cancel_countdown(&mut self) {
+        if self.countdown.take().is_some() {
+            log::info!("セルフタイマーをキャンセルしました");
+            self.toasts.info("セルフタイマーをキャンセルしました");
+        }
+    }
+
+    /// 録画を停止し、結果を通知する (取りこぼしがあった場合は警告)
+    fn stop_recording(&mut self) {
+        if let Some(summary) = self.session.stop_recording() {
+            // 取得時刻に合わせて複製・破棄したフレーム数も表示する
+            let mut details = format!(
+                "{}フレーム, 複製{}・破棄{}",
+                summary.frames_written, summary.frames_duplicated, summary.frames_dropped
+            );
+            if summary.segments.len() > 1 {
+                details.push_str(&format!(", {}ファイルに分割", summary.segments.len()));
+            }
+            self.toasts
+                .success(format!("録画を停止しました ({})", details));
+            if summary.frames_missed > 0 {
+                self.toasts.warning(format!(
+                    "録画の書き込みが追いつかず{}フレームを取りこぼしました",
+                    summary.frames_missed
+                ));
+            }
+        }
+        self.disk_checked_at = None;
+    }
+
+    /// 一定間隔で保存先の空き容量を調べ、少なくなった場合は警告する
+    fn refresh_disk_space(&mut self) {
+        if self
+            .disk_checked_at
+            .is_some_and(|checked| checked.elapsed() < DISK_SPACE_INTERVAL)
+        {
+            return;
+        }
+        self.disk_checked_at = Some(Instant::now());
+
+        let previous = self.disk_space;
+        self.disk_space = storage::available_space(self.session.output_dir());
+        // 閾値を下回った時だけ警告 (下回っている間は繰り返さない)
+        if let Some(space) = self.disk_space {
+            let was_low = previous.is_some_and(|previous| previous < LOW_DISK_SPACE);
+            if space < LOW_DISK_SPACE && !was_low {
+                self.toasts.warning(format!(
+                    "保存先の空き容量が少なくなっています: 残り{}",
+                    storage::format_bytes(space)
+                ));
+            }
+        }
+    }
+
+    /// 画面下部のステータスバー (カメラ名・解像度・FPS・空き容量)
+    fn status_bar(&self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if self.session.is_recording() {
+                ui.colored_label(egui::Color32::RED, "🔴 録画中");
+                ui.separator();
+            }
+            if let Some(progress) = self.session.timelapse_progress() {
+                if self.session.is_timelapse_suspended() {
+                    ui.colored_label(
+                        egui::Color32::YELLOW,
+                        format!(
+                            "⏸ タイムラプス {}/{} (ソースを開くと再開)",
+                            progress.captured, progress.total
+                        ),
+                    );
+                } else {
+                    ui.colored_label(
+                        egui::Color32::RED,
+                        format!("⏳ タイムラプス {}/{}", progress.captured, progress.total),
+                    );
+                }
+                ui.separator();
+            }
+
+            // カメラ名 (開いていない場合は未接続)
+            if self.session.is_open() {
+                ui.label(format!("📷 {}", self.session.source_name()));
+            } else {
+                ui.colored_label(egui::Color32::GRAY, "📷 未接続");
+            }
+            ui.separator();
+
+            // 実際に適用された解像度とピクセルフォーマット
+            let props = self.session.source_properties();
+            let fourcc = props
+                .fourcc
+                .map(|fourcc| format!(" {}", fourcc))
+                .unwrap_or_default();
+            ui.label(format!("{}x{}{}", props.width, props.height, fourcc));
+            ui.separator();
+
+            // キャプチャFPSとUI描画FPS (カメラ読み取りとUIは独立して動作)
+            ui.label(format!(
+                "{:.1} fps (UI {:.1} fps)",
+                self.session.capture_fps(),
+                self.ui_fps.fps()
+            ));
+            ui.separator();
+
+            // 保存先の空き容量 (少ない場合は赤で表示)
+            match self.disk_space {
+                Some(space) if space < LOW_DISK_SPACE => {
+                    ui.colored_label(
+                        egui::Color32::RED,
+                        format!("💾 空き {}", storage::format_bytes(space)),
+                    );
+                }
+                Some(space) => {
+                    ui.label(format!("💾 空き {}", storage::format_bytes(space)));
+                }
+                None => {
+                    ui.label("💾 空き容量不明");
+                }
+            }
+        });
+    }
+
+    /// セルフタイマーの秒数の選択 (オフ・3秒・5秒・10秒・任意の秒数)
+    fn self_timer_ui(&mut self, ui: &mut egui::Ui) {
+        let mut seconds = self.session.self_timer_secs();
+        let selected_text = if seconds == 0 {
+            "オフ".to_string()
+        } else {
+            format!("{}秒", seconds)
+        };
+
+        ui.label("⏱ タイマー:");
+        egui::ComboBox::from_id_salt("self_timer")
+            .selected_text(selected_text)
+            .show_ui(ui, |ui| {
+                if ui
+                    .selectable_label(seconds == 0 && !self.self_timer_custom, "オフ")
+                    .clicked()
+                {
+                    seconds = 0;
+                    self.self_timer_custom = false;
+                }
+                for preset in SELF_TIMER_PRESETS {
+                    let selected = seconds == preset && !self.self_timer_custom;
+                    if ui
+                        .selectable_label(selected, format!("{}秒", preset))
+                        .clicked()
+                    {
+                        seconds = preset;
+                        self.self_timer_custom = false;
+                    }
+                }
+                if ui
+                    .selectable_label(self.self_timer_custom, "カスタム")
+                    .clicked()
+                {
+                    self.self_timer_custom = true;
+                }
+            });
+        if self.self_timer_custom {
+            ui.add(
+                egui::DragValue::new(&mut seconds)
+                    .range(1..=MAX_SELF_TIMER_SECS)
+                    .suffix("秒"),
+            );
+        }
+
+        if seconds != self.session.self_timer_secs() {
+            self.session.set_self_timer_secs(seconds);
+        }
+    }
+
+    /// 写真の保存形式と、形式ごとの画質設定のコントロール
+    fn photo_options_ui(&mut self, ui: &mut egui::Ui) {
+        let mut options = *self.session.photo_options();
+
+        ui.label("形式:");
+        egui::ComboBox::from_id_salt("photo_format")
+            .selected_text(options.format.label())
+            .show_ui(ui, |ui| {
+                for format in PhotoFormat::ALL {
+                    ui.selectable_value(&mut options.format, format, format.label());
+                }
+            });
+
+        match options.format {
+            PhotoFormat::Jpeg => {
+                ui.add(
+                    egui::Slider::new(&mut options.jpeg_quality, 0..=MAX_JPEG_QUALITY).text("品質"),
+                );
+                ui.checkbox(&mut options.jpeg_progressive, "プログレッシブ");
+                egui::ComboBox::from_id_salt("jpeg_subsampling")
+                    .selected_text(options.jpeg_subsampling.label())
+                    .show_ui(ui, |ui| {
+                        for subsampling in ChromaSubsampling::ALL {
+                            ui.selectable_value(
+                                &mut options.jpeg_subsampling,
+                                subsampling,
+                                subsampling.label(),
+                            );
+                        }
+                    })
+                    .response
+                    .on_hover_text("色差サブサンプリング (4:4:4は色がにじまない、4:2:0は小さい)");
+            }
+            PhotoFormat::Png => {
+                ui.add(
+                    egui::Slider::new(&mut options.png_compression, 0..=MAX_PNG_COMPRESSION)
+                        .text("圧縮レベル"),
+                )
+                .on_hover_text("可逆圧縮のため画質は変わらない (大きいほど小さく・遅くなる)");
+            }
+            PhotoFormat::Webp => {
+                ui.add(
+                    egui::Slider::new(
+                        &mut options.webp_quality,
+                        MIN_WEBP_QUALITY..=MAX_WEBP_QUALITY,
+                    )
+                    .text("品質"),
+                );
+            }
+            PhotoFormat::Tiff => {
+                ui.label("16bit・可逆圧縮");
+            }
+        }
+
+        // ベストショット選択 (手ぶれ・ピンぼけの少ないフレームを保存、連写では使わない)
+        if self.session.capture_mode() == CaptureMode::Photo {
+            ui.separator();
+            ui.label("ベストショット:");
+            ui.add(egui::Slider::new(&mut options.best_of, 1..=MAX_BEST_OF_FRAMES).text("枚"))
+                .on_hover_text(
+                    "表示中のフレームと続くフレームから、最もシャープなものを保存 (1は表示中のフレームのみ)",
+                );
+        }
+
+        if options != *self.session.photo_options() {
+            self.session.set_photo_options(options);
+        }
+    }
+
+    /// 録画形式 (コーデック・コンテナ) のピッカーと対応形式の検出ボタン
+    fn video_format_ui(&mut self, ui: &mut egui::Ui) {
+        let mut format = self.session.video_format();
+        let support = self.session.video_support();
+
+        ui.label("録画形式:");
+        egui::ComboBox::from_id_salt("video_codec")
+            .selected_text(codec_label(format.codec))
+            .show_ui(ui, |ui| {
+                for codec in VIDEO_CODECS {
+                    let candidate = VideoFormat {
+                        codec,
+                        container: format.container,
+                    };
+                    let text = format!(
+                        "{}{}",
+                        codec_label(codec),
+                        support_mark(support.status(candidate))
+                    );
+                    ui.selectable_value(&mut format.codec, codec, text);
+                }
+            });
+        egui::ComboBox::from_id_salt("video_container")
+            .selected_text(format!(".{}", format.container.extension()))
+            .show_ui(ui, |ui| {
+                for container in VideoContainer::ALL {
+                    let candidate = VideoFormat {
+                        codec: format.codec,
+                        container,
+                    };
+                    let text = format!(
+                        ".{}{}",
+                        container.extension(),
+                        support_mark(support.status(candidate))
+                    );
+                    ui.selectable_value(&mut format.container, container, text);
+                }
+            });
+
+        if format != self.session.video_format() {
+            self.session.set_video_format(format);
+        }
+
+        // 書き込めない形式を選んでいる場合は、実際に使う形式を表示
+        let resolved = self.session.resolved_video_format();
+        if resolved != format {
+            ui.colored_label(egui::Color32::YELLOW, format!("⚠ {} で録画", resolved))
+                .on_hover_text("選択した形式はこの環境で書き込めないため、代わりの形式を使います");
+        }
+
+        if self.session.is_probing_video_formats() {
+            ui.spinner();
+            ui.label("検出中...");
+        } else if ui.button("🔍 対応形式を検出").clicked() {
+            self.session.probe_video_formats();
+        }
+    }
+
+    /// 録画形式の検出が終わっていれば、選択中の形式が使えない場合に警告する
+    fn poll_format_probe(&mut self) {
+        if !self.session.poll_format_probe() {
+            return;
+        }
+        let selected = self.session.video_format();
+        let resolved = self.session.resolved_video_format();
+        if resolved != selected {
+            self.toasts.warning(format!(
+                "録画形式 {} はこの環境で書き込めないため、{} で録画します",
+                selected, resolved
+            ));
+        }
+    }
+
+    /// 録画開始前の何秒間を録画に含めるかのコントロール
+    fn pre_record_ui(&mut self, ui: &mut egui::Ui) {
+        let mut seconds = self.session.pre_record_secs();
+
+        ui.label("録画前:");
+        ui.add(
+            egui::DragValue::new(&mut seconds)
+                .range(0..=MAX_PRE_RECORD_SECS)
+                .suffix("秒"),
+        )
+        .on_hover_text("録画ボタンを押す前の数秒間も録画に含める (0で無効)");
+
+        if seconds != self.session.pre_record_secs() {
+            self.session.set_pre_record_secs(seconds);
+        }
+    }
+
+    /// 分割録画の時間・サイズと一覧ファイルのコントロール
+    fn segment_options_ui(&mut self, ui: &mut egui::Ui) {
+        let mut options = *self.session.segment_options();
+
+        ui.label("分割:");
+        ui.add(
+            egui::DragValue::new(&mut options.max_minutes)
+                .range(0..=MAX_SEGMENT_MINUTES)
+                .suffix("分"),
+        )
+        .on_hover_text("1ファイルの録画時間 (0で時間では分割しない)");
+        ui.add(
+            egui::DragValue::new(&mut options.max_size_mb)
+                .range(0..=MAX_SEGMENT_SIZE_MB)
+                .suffix("MB"),
+        )
+        .on_hover_text("1ファイルのサイズの目安 (0でサイズでは分割しない)");
+        if options.is_enabled() {
+            ui.checkbox(&mut options.manifest, "一覧")
+                .on_hover_text("分割したファイルの一覧 (M3Uプレイリスト) を書き出す");
+        }
+
+        if options != *self.session.segment_options() {
+            self.session.set_segment_options(options);
+        }
+    }
+
+    /// 連写の枚数と撮影間隔のコントロール
+    fn burst_options_ui(&mut self, ui: &mut egui::Ui) {
+        let mut options = *self.session.burst_options();
+
+        ui.label("枚数:");
+        ui.add(egui::DragValue::new(&mut options.count).range(1..=MAX_BURST_COUNT));
+
+        // 最速: カメラのフレームレートで連続撮影
+        let mut fastest = options.interval_ms == 0;
+        if ui.checkbox(&mut fastest, "最速").changed() {
+            options.interval_ms = if fastest {
+                0
+            } else {
+                DEFAULT_BURST_INTERVAL_MS
+            };
+        }
+        if !fastest {
+            ui.label("間隔:");
+            ui.add(
+                egui::DragValue::new(&mut options.interval_ms)
+                    .range(1..=MAX_BURST_INTERVAL_MS)
+                    .suffix("ms"),
+            );
+        }
+
+        if options != *self.session.burst_options() {
+            self.session.set_burst_options(options);
+        }
+    }
+
+    /// タイムラプスの撮影間隔・終了条件・保存内容のコントロール
+    fn timelapse_options_ui(&mut self, ui: &mut egui::Ui) {
+        let mut options = *self.session.timelapse_options();
+
+        ui.label("間隔:");
+        ui.add(
+            egui::DragValue::new(&mut options.interval_secs)
+                .range(1..=MAX_TIMELAPSE_INTERVAL_SECS)
+                .suffix("秒"),
+        );
+
+        egui::ComboBox::from_id_salt("timelapse_limit")
+            .selected_text(options.limit.label())
+            .show_ui(ui, |ui| {
+                for limit in TimelapseLimit::ALL {
+                    ui.selectable_value(&mut options.limit, limit, limit.label());
+                }
+            });
+        match options.limit {
+            TimelapseLimit::Count => {
+                ui.add(
+                    egui::DragValue::new(&mut options.count)
+                        .range(1..=MAX_TIMELAPSE_FRAMES)
+                        .suffix("枚"),
+                );
+            }
+            TimelapseLimit::Duration => {
+                ui.add(
+                    egui::DragValue::new(&mut options.duration_mins)
+                        .range(1..=MAX_TIMELAPSE_DURATION_MINS)
+                        .suffix("分"),
+                );
+            }
+        }
+
+        // 写真と動画のどちらも残さない設定にはできない
+        ui.add_enabled(
+            options.make_video,
+            egui::Checkbox::new(&mut options.save_photos, "写真を残す"),
+        );
+        ui.add_enabled(
+            options.save_photos,
+            egui::Checkbox::new(&mut options.make_video, "動画を作成"),
+        );
+        if options.make_video {
+            ui.label("再生:");
+            ui.add(
+                egui::DragValue::new(&mut options.playback_fps)
+                    .range(1..=MAX_PLAYBACK_FPS)
+                    .suffix("fps"),
+            );
+            ui.label(format!(
+                "({}枚 → 動画 {})",
+                options.total_frames(),
+                format_duration(options.video_length())
+            ));
+        } else {
+            ui.label(format!("({}枚)", options.total_frames()));
+        }
+
+        if options != *self.session.timelapse_options() {
+            self.session.set_timelapse_options(options);
+        }
+    }
+
+    /// 前回の起動で終わらなかったタイムラプスの再開・仕上げの選択肢
+    fn unfinished_timelapse_ui(&mut self, ui: &mut egui::Ui) {
+        let Some(unfinished) = self.unfinished_timelapses.first() else {
+            return;
+        };
+        let dir = unfinished.dir.clone();
+        let description = format!(
+            "⏸ 終わらなかったタイムラプス: {} ({}/{}枚)",
+            unfinished.state.stem, unfinished.state.captured, unfinished.state.total
+        );
+        let busy = self.session.timelapse_progress().is_some();
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label(description);
+            if ui.add_enabled(!busy, egui::Button::new("▶ 再開")).clicked() {
+                self.continue_unfinished_timelapse(dir.clone(), false);
+            }
+            if ui
+                .add_enabled(!busy, egui::Button::new("🎬 ここまでで仕上げる"))
+                .on_hover_text("撮影済みのフレームで動画を作成し、途中経過を削除します")
+                .clicked()
+            {
+                self.continue_unfinished_timelapse(dir.clone(), true);
+            }
+            if ui.button("✖").on_hover_text("今回は表示しない").clicked() {
+                self.unfinished_timelapses
+                    .retain(|unfinished| unfinished.dir != dir);
+            }
+        });
+        ui.separator();
+    }
+
+    /// 設定に変化があれば保存 (変化が無ければ何もしないため毎フレーム呼んでよい)
+    fn save_settings(&mut self) {
+        let settings = self.session.current_settings();
+        if let Err(e) = self.settings_store.save(&settings) {
+            self.report_error(e);
+        }
+    }
+
+    /// デバイス検出が終わっていれば結果を反映
+    ///
+    /// 使用中のデバイスは検出時に開けないことがあるため、
+    /// 現在開いているカメラが一覧に無い場合は現在の情報で補完する。
+    fn poll_device_probe(&mut self) {
+        let Some(mut devices) = self.device_probe.as_mut().and_then(|p| p.try_take()) else {
+            return;
+        };
+        self.device_probe = None;
+
+        let camera_index = self.session.camera_index();
+        let in_use = *self.session.source_kind() == SourceKind::Camera && self.session.is_open();
+        if in_use && !devices.iter().any(|d| d.index == camera_index) {
+            let (width, height) = self.session.frame_size();
+            devices.push(DeviceInfo {
+                index: camera_index,
+                backend: 0,
+                backend_name: "使用中".to_string(),
+                width,
+                height,
+            });
+            devices.sort_by_key(|d| d.index);
+        }
+
+        log::info!("カメラを{}台検出しました", devices.len());
+        self.toasts
+            .info(format!("カメラを{}台検出しました", devices.len()));
+        self.devices = devices;
+    }
+
+    /// フレームソースを切り替え、失敗した場合はエラーを表示
+    fn switch_source(&mut self, kind: SourceKind) {
+        if let Err(e) = self.session.switch_source(kind) {
+            self.report_switch_error(e);
+        }
+    }
+
+    /// ソースの切り替えに失敗したことを表示 (タイムラプスを一時停止した場合はその旨も通知)
+    fn report_switch_error(&mut self, error: CameraError) {
+        self.report_error(error);
+        if self.session.is_timelapse_suspended() {
+            self.toasts.warning(
+                "ソースを開けないため、タイムラプスを一時停止しました (ソースを開くと再開します)",
+            );
+        }
+    }
+}
+
+/// eframe::Appトレイトの実装
+///
+/// eGuiのメインループで呼ばれるupdate関数を実装し、UIを描画する。
+impl eframe::App for CameraApp {
+    /// UIの更新と描画 (eGuiのメインループで毎フレーム呼ばれる)
+    ///
+    /// キャプチャスレッドが格納した最新フレームとUI要素
+    /// (プレビュー、モード切り替え、撮影ボタン等) を描画。
+    /// ctx.request_repaint()で継続的に再描画を要求し、リアルタイム更新を実現。
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // UIの描画FPSを計測
+        self.ui_fps.tick();
+        // バックグラウンドのデバイス検出結果を反映
+        self.poll_device_probe();
+        // モード変更・モード検出の結果を反映
+        self.session.poll_mode_requests();
+        // 録画形式の検出結果を反映
+        self.poll_format_probe();
+        // バックグラウンドで発生したエラーを表示
+        for error in self.session.take_errors() {
+            self.report_error(error);
+        }
+        // 保存先の空き容量を更新
+        self.refresh_disk_space();
+        // セルフタイマーが終わっていれば撮影・録画を開始
+        self.poll_countdown();
+        // 写真・連写の保存が終わっていれば結果を表示
+        self.poll_photo();
+        self.poll_burst();
+        // タイムラプスが終わっていれば結果を表示
+        self.poll_timelapse();
+
+        // ステータスバー (中央パネルより先に配置して下端に固定)
+        egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+            self.status_bar(ui);
+        });
+
+        // 中央パネルを作成 (メインUI領域)
+        egui::CentralPanel::default().show(ctx, |ui| {
+            // アプリケーションタイトル
+            ui.heading("Surface Go 4 カメラアプリ (OpenCV)");
+
+            ui.separator();
+
+            // カメラプレビュー表示
+            if let Some(frame) = self.session.preview_frame() {
+                // フレームをテクスチャとしてGPUにアップロード
+                // 同じ名前 ("camera_frame") で上書きすることで自動的に更新される
+                let texture = ctx.load_texture("camera_frame", frame, Default::default());
+
+                // 利用可能な画面サイズを取得
+                let available_size = ui.available_size();
+                // 画像表示サイズを計算 (最大800px幅、下部コントロール用に150px確保)
+                let image_size = [available_size.x.min(800.0), available_size.y - 150.0];
+
+                // 画像を表示 (指定サイズにフィット)
+                let preview = ui.add(
+                    egui::Image::new(&texture)
+                        .fit_to_exact_size(egui::vec2(image_size[0], image_size[1])),
+                );
+
+                // セルフタイマーの残り秒数をプレビューに重ねて表示
+                let cancelled = self
+                    .countdown
+                    .as_ref()
+                    .is_some_and(|countdown| countdown.show_overlay(ui, preview.rect));
+                if cancelled {
+                    self.cancel_countdown();
+                }
+            } else {
+                // カメラ初期化中はメッセージを表示
+                ui.label("カメラを初期化中...");
+            }
+
+            ui.separator();
+
+            // コントロールパネル (モード切り替えとカメラ切り替え)
+            ui.horizontal(|ui| {
+                // キャプチャモード切り替えトグル (写真・連写・タイムラプス・動画)
+                ui.label("モード:");
+                let capture_mode = self.session.capture_mode();
+                // 写真モードボタン (選択中の場合ハイライト表示)
+                // 録画中の場合は停止してから写真モードに切り替え
+                if ui
+                    .selectable_label(capture_mode == CaptureMode::Photo, "📷 写真")
+                    .clicked()
+                {
+                    if self.session.is_recording() {
+                        self.stop_recording();
+                    }
+                    self.cancel_countdown();
+                    self.session.set_capture_mode(CaptureMode::Photo);
+                }
+
+                // 連写モードボタン (録画中の場合は停止してから切り替え)
+                if ui
+                    .selectable_label(capture_mode == CaptureMode::Burst, "⚡ 連写")
+                    .clicked()
+                {
+                    if self.session.is_recording() {
+                        self.stop_recording();
+                    }
+                    self.cancel_countdown();
+                    self.session.set_capture_mode(CaptureMode::Burst);
+                }
+
+                // タイムラプスモードボタン (録画中の場合は停止してから切り替え)
+                if ui
+                    .selectable_label(capture_mode == CaptureMode::Timelapse, "⏳ タイムラプス")
+                    .clicked()
+                {
+                    if self.session.is_recording() {
+                        self.stop_recording();
+                    }
+                    self.cancel_countdown();
+                    self.session.set_capture_mode(CaptureMode::Timelapse);
+                }
+
+                // 動画モードボタン (選択中の場合ハイライト表示)
+                if ui
+                    .selectable_label(capture_mode == CaptureMode::Video, "🎥 動画")
+                    .clicked()
+                {
+                    self.cancel_countdown();
+                    self.session.set_capture_mode(CaptureMode::Video);
+                }
+
+                ui.separator();
+
+                // カメラ位置切り替えトグル (リア/フロント/外部)
+                ui.label("カメラ:");
+                // テストパターン使用中はどのカメラもハイライトしない
+                let is_camera = *self.session.source_kind() == SourceKind::Camera;
+                for position in CameraPosition::ALL {
+                    // 選択中の場合ハイライト表示
+                    let selected = is_camera && self.session.camera_position() == position;
+                    if ui.selectable_label(selected, position.label()).clicked() && !selected {
+                        if let Err(e) = self.session.switch_camera(position) {
+                            self.report_switch_error(e);
+                        }
+                    }
+                }
+
+                // テストパターンボタン (カメラ無しで動作確認するための合成映像)
+                let is_test_pattern =
+                    matches!(self.session.source_kind(), SourceKind::TestPattern(_));
+                if ui.selectable_label(is_test_pattern, "🧪 テスト").clicked() && !is_test_pattern
+                {
+                    // 現在の解像度でテストパターンを生成
+                    let (width, height) = self.session.frame_size();
+                    self.switch_source(SourceKind::TestPattern(TestPatternConfig {
+                        width,
+                        height,
+                        ..TestPatternConfig::default()
+                    }));
+                }
+            });
+
+            // デバイスピッカー (選択したデバイスを現在の役割に割り当てる)
+            ui.horizontal(|ui| {
+                ui.label("デバイス:");
+                let camera_index = self.session.camera_index();
+                let selected_text = self
+                    .devices
+                    .iter()
+                    .find(|d| d.index == camera_index)
+                    .map(|d| d.label())
+                    .unwrap_or_else(|| format!("カメラ {}", camera_index));
+
+                let mut chosen = None;
+                egui::ComboBox::from_id_salt("camera_device")
+                    .selected_text(selected_text)
+                    .show_ui(ui, |ui| {
+                        for device in &self.devices {
+                            if ui
+                                .selectable_label(camera_index == device.index, device.label())
+                                .clicked()
+                            {
+                                chosen = Some(device.index);
+                            }
+                        }
+                    });
+                if let Some(index) = chosen {
+                    if let Err(e) = self.session.select_device(index) {
+                        self.report_switch_error(e);
+                    }
+                }
+
+                if self.device_probe.is_some() {
+                    ui.spinner();
+                    ui.label("検出中...");
+                } else if ui.button("🔍 再検出").clicked() {
+                    self.device_probe =
+                        Some(DeviceProbe::spawn(self.session.camera_backends().to_vec()));
+                }
+            });
+
+            // 録画形式ピッカー (コーデック・コンテナ、検出済みの場合は使えるかどうかを表示)
+            ui.horizontal(|ui| self.video_format_ui(ui));
+
+            // 動作モードピッカー (解像度・FPS・ピクセルフォーマットを開いたまま変更)
+            ui.horizontal(|ui| {
+                ui.label("解像度:");
+                let props = self.session.source_properties();
+
+                let mut chosen = None;
+                egui::ComboBox::from_id_salt("video_mode")
+                    .selected_text(VideoMode::from(props).label())
+                    .show_ui(ui, |ui| {
+                        if self.session.mode_probes().is_empty() {
+                            // 未検出の場合は候補をそのまま表示
+                            for mode in candidate_modes() {
+                                if ui.selectable_label(false, mode.label()).clicked() {
+                                    chosen = Some(mode);
+                                }
+                            }
+                        } else {
+                            // 検出済みの場合は要求どおりに適用されたかを表示
+                            for probe in self.session.mode_probes() {
+                                let selected = probe.requested.is_satisfied_by(&props);
+                                if ui.selectable_label(selected, probe.label()).clicked() {
+                                    chosen = Some(probe.requested);
+                                }
+                            }
+                        }
+                    });
+                if let Some(mode) = chosen {
+                    self.session.change_mode(mode);
+                }
+
+                if self.session.is_probing_modes() {
+                    ui.spinner();
+                    ui.label("検出中...");
+                } else if self.session.is_changing_mode() {
+                    ui.spinner();
+                    ui.label("変更中...");
+                } else if ui.button("🔍 対応モードを検出").clicked() {
+                    self.session.probe_modes();
+                }
+            });
+
+            // パス指定でソースを開く
+            // 動画ファイル・画像フォルダはカメラの代わりに再生し、
+            // デバイスパス (/dev/videoN) やGStreamerパイプラインはカメラとして開く
+            ui.horizontal(|ui| {
+                ui.label("パス:");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.playback_path).hint_text(
+                        "動画ファイル / 画像フォルダ / /dev/videoN / GStreamerパイプライン",
+                    ),
+                );
+                ui.checkbox(&mut self.playback_loop, "ループ");
+                let path = self.playback_path.trim().to_string();
+                if ui.button("▶ 開く").clicked() && !path.is_empty() {
+                    if CameraDevice::looks_like_device(&path) {
+                        self.switch_source(SourceKind::Device(path));
+                    } else {
+                        let mode = if self.playback_loop {
+                            PlaybackMode::Loop
+                        } else {
+                            PlaybackMode::Once
+                        };
+                        self.switch_source(SourceKind::Playback(PlaybackConfig {
+                            path: PathBuf::from(path),
+                            mode,
+                            fps: None,
+                        }));
+                    }
+                }
+            });
+
+            ui.separator();
+
+            // 前回の起動で終わらなかったタイムラプス
+            self.unfinished_timelapse_ui(ui);
+
+            // 撮影・録画ボタン (モードに応じて表示を切り替え、幅が足りない場合は折り返す)
+            ui.horizontal_wrapped(|ui| {
+                // カウントダウン中は撮影・録画開始ボタンを押せないようにする
+                let idle = self.countdown.is_none();
+                match self.session.capture_mode() {
+                    CaptureMode::Photo => {
+                        // 写真モード: 保存中は進捗、それ以外は撮影ボタンを表示
+                        if self.session.is_saving_photo() {
+                            ui.spinner();
+                            ui.label("写真を保存中...");
+                        } else if ui
+                            .add_enabled(idle, egui::Button::new("📸 写真を撮る"))
+                            .clicked()
+                        {
+                            self.request_capture(CaptureMode::Photo);
+                        }
+                        self.self_timer_ui(ui);
+                        ui.separator();
+                        self.photo_options_ui(ui);
+                    }
+                    CaptureMode::Burst => {
+                        // 連写モード: 保存中は進捗、それ以外は連写ボタンを表示
+                        if let Some(progress) = self.session.burst_progress() {
+                            ui.spinner();
+                            ui.label(format!(
+                                "連写を保存中... {}/{}",
+                                progress.saved, progress.total
+                            ));
+                            if progress.failed > 0 {
+                                ui.colored_label(
+                                    egui::Color32::YELLOW,
+                                    format!("⚠ {}枚失敗", progress.failed),
+                                );
+                            }
+                        } else {
+                            let count = self.session.burst_options().count;
+                            if ui
+                                .add_enabled(
+                                    idle,
+                                    egui::Button::new(format!("📸 連写 ({}枚)", count)),
+                                )
+                                .clicked()
+                            {
+                                self.request_capture(CaptureMode::Burst);
+                            }
+                            self.self_timer_ui(ui);
+                        }
+                        ui.separator();
+                        self.burst_options_ui(ui);
+                        ui.separator();
+                        self.photo_options_ui(ui);
+                    }
+                    CaptureMode::Timelapse => {
+                        // タイムラプスモード: 撮影中は進捗と停止ボタン、それ以外は開始ボタンを表示
+                        match self.session.timelapse_progress() {
+                            Some(progress) => {
+                                if let Some(compiled) = progress.compiled {
+                                    ui.spinner();
+                                    ui.label(format!(
+                                        "動画を作成中... {}/{}",
+                                        compiled, progress.captured
+                                    ));
+                                } else {
+                                    if ui.button("⏹ タイムラプス停止").clicked() {
+                                        self.session.stop_timelapse();
+                                    }
+                                    ui.add(
+                                        egui::ProgressBar::new(
+                                            progress.captured as f32 / progress.total.max(1) as f32,
+                                        )
+                                        .desired_width(TIMELAPSE_PROGRESS_WIDTH)
+                                        .text(format!(
+                                            "{}/{}枚",
+                                            progress.captured, progress.total
+                                        )),
+                                    );
+                                    ui.label(format!(
+                                        "残り約 {}",
+                                        format_duration(progress.remaining)
+                                    ));
+                                }
+                            }
+                            None => {
+                                if ui
+                                    .add_enabled(idle, egui::Button::new("⏺ タイムラプス開始"))
+                                    .clicked()
+                                {
+                                    self.request_capture(CaptureMode::Timelapse);
+                                }
+                                self.self_timer_ui(ui);
+                                ui.separator();
+                                self.timelapse_options_ui(ui);
+                                ui.separator();
+                                self.photo_options_ui(ui);
+                            }
+                        }
+                    }
+                    CaptureMode::Video => {
+                        // 動画モード: 録画中かどうかで表示を切り替え
+                        if !self.session.is_recording() {
+                            // 録画停止中: 録画開始ボタンを表示
+                            if ui
+                                .add_enabled(idle, egui::Button::new("⏺ 録画開始"))
+                                .clicked()
+                            {
+                                self.request_capture(CaptureMode::Video);
+                            }
+                            self.self_timer_ui(ui);
+                            ui.separator();
+                            self.pre_record_ui(ui);
+                            ui.separator();
+                            self.segment_options_ui(ui);
+                        } else {
+                            // 録画中: 録画停止ボタンとステータス表示
+                            if ui.button("⏹ 録画停止").clicked() {
+                                self.stop_recording();
+                            }
+                            match self.session.recording_segment() {
+                                Some(index) if self.session.segment_options().is_enabled() => {
+                                    ui.label(format!("🔴 録画中... ({}ファイル目)", index));
+                                }
+                                _ => {
+                                    ui.label("🔴 録画中...");
+                                }
+                            }
+                        }
+                    }
+                }
+            });
+
+            ui.separator();
+            // 保存先ディレクトリを表示
+            ui.label(format!("保存先: {}", self.session.output_dir().display()));
+        });
+
+        // 通知を前面に表示
+        self.toasts.show(ctx);
+
+        // 設定の変更を保存 (変化があった場合のみ書き込む)
+        self.save_settings();
+
+        // 継続的に再描画を要求 (リアルタイム更新のため)
+        ctx.request_repaint();
+    }
+}
+
+/// Dropトレイトの実装
+///
+/// アプリケーション終了時に設定を保存する。
+/// 録画中の場合は、セッションの破棄時に自動的に停止される。
+impl Drop for CameraApp {
+    fn drop(&mut self) {
+        // 終了時の設定を保存
+        self.save_settings();
+    }
+}
+
+/// 録画形式の選択肢に付ける印 (検出済みの場合のみ)
+fn support_mark(status: Option<bool>) -> &'static str {
+    match status {
+        Some(true) => " ✔",
+        Some(false) => " ✖ (非対応)",
+        None => "",
+    }
+}
+
+/// 時間を`H:MM:SS`形式 (1時間未満は`M:SS`形式) の文字列に変換
+fn format_duration(duration: Duration) -> String {
+    let total = duration.as_secs();
+    let (hours, minutes, seconds) = (
+        total / SECS_PER_HOUR,
+        total % SECS_PER_HOUR / SECS_PER_MINUTE,
+        total % SECS_PER_MINUTE,
+    );
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
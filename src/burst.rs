@@ -0,0 +1,201 @@
+// 標準ライブラリのインポート
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+// サードパーティクレートのインポート
+use serde::{Deserialize, Serialize};
+
+use crate::capture::{IntervalTap, TimedFrame};
+use crate::error::CameraError;
+use crate::filename;
+use crate::metadata::PhotoMetadata;
+use crate::photo::{self, PhotoOptions};
+
+/// 連写枚数のデフォルト値
+pub const DEFAULT_BURST_COUNT: u32 = 10;
+/// 連写枚数の上限 (書き込み待ちのフレームはすべてメモリに載るため抑える)
+pub const MAX_BURST_COUNT: u32 = 50;
+/// 連写間隔の上限 (ミリ秒)
+pub const MAX_BURST_INTERVAL_MS: u32 = 10_000;
+/// 連番の桁数 (ゼロ埋め)
+const BURST_INDEX_DIGITS: usize = 3;
+
+/// 連写の設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BurstOptions {
+    /// 撮影する枚数
+    #[serde(rename = "burst_count")]
+    pub count: u32,
+    /// 撮影間隔 (ミリ秒、0はカメラのフレームレートで連続撮影)
+    #[serde(rename = "burst_interval_ms")]
+    pub interval_ms: u32,
+}
+
+impl Default for BurstOptions {
+    fn default() -> Self {
+        Self {
+            count: DEFAULT_BURST_COUNT,
+            interval_ms: 0,
+        }
+    }
+}
+
+impl BurstOptions {
+    /// 範囲外の値を上下限に収める
+    pub fn clamp(&mut self) {
+        self.count = self.count.clamp(1, MAX_BURST_COUNT);
+        self.interval_ms = self.interval_ms.min(MAX_BURST_INTERVAL_MS);
+    }
+
+    /// 撮影間隔
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(u64::from(self.interval_ms))
+    }
+}
+
+/// 連写の進捗
+#[derive(Debug, Clone, Copy)]
+pub struct BurstProgress {
+    /// 保存できた枚数
+    pub saved: u32,
+    /// 保存に失敗した枚数
+    pub failed: u32,
+    /// 撮影する枚数
+    pub total: u32,
+}
+
+/// 連写の集計結果
+#[derive(Debug, Default)]
+pub struct BurstSummary {
+    /// 保存したファイル (撮影順)
+    pub paths: Vec<PathBuf>,
+    /// 保存に失敗したフレームのエラー
+    pub errors: Vec<CameraError>,
+}
+
+/// 連写セッション: 専用スレッドでフレームを保存する
+///
+/// エンコード・書き込みはキャプチャスレッドとUIスレッドのどちらも止めない。
+/// ファイル名は`{stem}_001.jpg`, `{stem}_002.jpg` ... の連番にする。
+pub struct BurstSession {
+    /// 書き込みスレッドのハンドル (終了時に集計結果を返す)
+    handle: Option<JoinHandle<BurstSummary>>,
+    /// 保存できた枚数 (進捗表示用)
+    saved: Arc<AtomicU32>,
+    /// 保存に失敗した枚数 (進捗表示用)
+    failed: Arc<AtomicU32>,
+    /// 撮影する枚数
+    total: u32,
+}
+
+impl BurstSession {
+    /// 書き込みスレッドを起動
+    ///
+    /// # 引数
+    /// * `dir` - 保存先ディレクトリ (作成済みであること)
+    /// * `stem` - ファイル名の共通部分 (連番と拡張子は自動で付く)
+    /// * `burst` - 枚数と撮影間隔
+    /// * `photo` - 保存形式と画質設定
+    /// * `metadata` - 埋め込むメタデータ (撮影日時は各フレームの取得時刻で置き換える)
+    ///
+    /// # 戻り値
+    /// 連写セッションと、キャプチャスレッドに渡すフレームの選択器
+    pub fn start(
+        dir: &Path,
+        stem: String,
+        burst: BurstOptions,
+        photo: PhotoOptions,
+        metadata: Option<PhotoMetadata>,
+    ) -> (Self, IntervalTap) {
+        let (sender, receiver) = mpsc::sync_channel(burst.count as usize);
+        let saved = Arc::new(AtomicU32::new(0));
+        let failed = Arc::new(AtomicU32::new(0));
+
+        let handle = {
+            let dir = dir.to_path_buf();
+            let counts = (saved.clone(), failed.clone());
+            thread::spawn(move || {
+                let (saved, failed) = counts;
+                burst_loop(receiver, &dir, &stem, &photo, metadata, &saved, &failed)
+            })
+        };
+
+        (
+            Self {
+                handle: Some(handle),
+                saved,
+                failed,
+                total: burst.count,
+            },
+            IntervalTap::new(sender, burst.interval(), burst.count),
+        )
+    }
+
+    /// 進捗 (保存できた枚数・保存に失敗した枚数・撮影する枚数)
+    pub fn progress(&self) -> BurstProgress {
+        BurstProgress {
+            saved: self.saved.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+            total: self.total,
+        }
+    }
+
+    /// すべてのフレームを保存し終えたかどうか
+    pub fn is_finished(&self) -> bool {
+        self.handle
+            .as_ref()
+            .is_none_or(|handle| handle.is_finished())
+    }
+
+    /// 書き込みスレッドの終了を待ち、集計結果を返す
+    ///
+    /// 選択器 (`IntervalTap`) が破棄されてから呼ぶこと (キューに残ったフレームは保存される)。
+    pub fn finish(mut self) -> Option<BurstSummary> {
+        self.handle.take().and_then(|handle| handle.join().ok())
+    }
+}
+
+/// 書き込みスレッドの本体
+///
+/// 送信口が破棄されるまでフレームを受け取り、連番のファイル名で保存する。
+/// 保存できた枚数と失敗した枚数は別々に数える。
+fn burst_loop(
+    receiver: Receiver<TimedFrame>,
+    dir: &Path,
+    stem: &str,
+    options: &PhotoOptions,
+    metadata: Option<PhotoMetadata>,
+    saved: &AtomicU32,
+    failed: &AtomicU32,
+) -> BurstSummary {
+    let mut summary = BurstSummary::default();
+    let extension = options.format.extension();
+
+    for (index, timed) in receiver.into_iter().enumerate() {
+        let name = format!("{}_{:0width$}", stem, index + 1, width = BURST_INDEX_DIGITS);
+        let path = filename::unique_path(dir, &name, extension);
+
+        // 撮影日時は保存した時刻ではなくフレームを取得した時刻にする
+        let metadata = metadata
+            .clone()
+            .map(|metadata| metadata.taken_at_instant(timed.captured_at));
+
+        match photo::save_photo(&path, &timed.frame, options, metadata.as_ref()) {
+            Ok(()) => {
+                summary.paths.push(path);
+                saved.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(e) => {
+                log::warn!("連写の{}枚目を保存できませんでした: {}", index + 1, e);
+                summary.errors.push(e);
+                failed.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+    summary
+}
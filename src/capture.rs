@@ -0,0 +1,543 @@
+// 標準ライブラリのインポート
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+// サードパーティクレートのインポート
+use opencv::{core::Mat, prelude::*};
+
+use crate::convert::mat_to_color_image;
+use crate::error::CameraError;
+use crate::prerecord::{EncodedFrame, PreRecordBuffer};
+use crate::source::{FrameSource, ModeProbe, SourceProperties, VideoMode};
+
+/// リングバッファに保持するフレーム数 (メモリ使用量とのバランス)
+const FRAME_RING_CAPACITY: usize = 4;
+/// ソースからフレームを取得できなかった場合の待機時間 (ビジーループ防止)
+const READ_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+/// 読み取りの失敗がこの回数続いたらUIに報告する
+const READ_FAILURE_LIMIT: u32 = 30;
+/// 読み取りの失敗がこの時間続いたら、回数に満たなくてもUIに報告する
+const READ_FAILURE_TIMEOUT: Duration = Duration::from_secs(2);
+/// FPS計測に使用する時間窓
+const FPS_WINDOW: Duration = Duration::from_secs(1);
+
+/// キャプチャスレッドで取得したフレーム (取得時刻・連番付き)
+pub struct TimedFrame {
+    /// フレーム本体 (BGR形式)
+    pub frame: Mat,
+    /// ソースから取得した時刻
+    pub captured_at: Instant,
+    /// キャプチャ開始からの連番 (1始まり)
+    pub sequence: u64,
+}
+
+impl TimedFrame {
+    /// フレームを複製 (各コンシューマーへ個別に渡すため)
+    pub fn try_clone(&self) -> opencv::Result<Self> {
+        Ok(Self {
+            frame: self.frame.try_clone()?,
+            captured_at: self.captured_at,
+            sequence: self.sequence,
+        })
+    }
+}
+
+/// キャプチャスレッド側で一定間隔ごとにフレームを選び、別スレッドへ送る
+///
+/// 写真のベストショット選択・連写・タイムラプスで使う。キューが満杯の場合はそのフレームを見送り、
+/// キャプチャスレッドは書き込みを待たない。
+pub struct IntervalTap {
+    /// 書き込みスレッドへの送信口
+    sender: SyncSender<TimedFrame>,
+    /// 撮影間隔
+    interval: Duration,
+    /// 次のフレームを選ぶ時刻 (最初のフレームはすぐに選ぶ)
+    next_due: Option<Instant>,
+    /// 残りの枚数
+    remaining: u32,
+}
+
+impl IntervalTap {
+    /// 指定した間隔・枚数でフレームを送る選択器を作成
+    pub fn new(sender: SyncSender<TimedFrame>, interval: Duration, count: u32) -> Self {
+        Self {
+            sender,
+            interval,
+            next_due: None,
+            remaining: count,
+        }
+    }
+
+    /// 取得したフレームを必要に応じて書き込みスレッドへ送る
+    ///
+    /// # 戻り値
+    /// まだ続ける場合はtrue、必要な枚数を送り終えたか書き込みスレッドが終了した場合はfalse
+    fn offer(&mut self, timed: &TimedFrame) -> bool {
+        if self.remaining == 0 {
+            return false;
+        }
+        let due = self.next_due.unwrap_or(timed.captured_at);
+        if timed.captured_at < due {
+            return true;
+        }
+
+        let Ok(copy) = timed.try_clone() else {
+            return true;
+        };
+        match self.sender.try_send(copy) {
+            Ok(()) => {
+                self.remaining -= 1;
+                // 予定の時刻を基準に次を決める (遅れている場合は今回の時刻を基準にする)
+                let next = due + self.interval;
+                self.next_due = Some(if next < timed.captured_at {
+                    timed.captured_at + self.interval
+                } else {
+                    next
+                });
+            }
+            // 書き込みが追いついていない: 次のフレームで再試行
+            Err(TrySendError::Full(_)) => {}
+            Err(TrySendError::Disconnected(_)) => return false,
+        }
+        self.remaining > 0
+    }
+}
+
+/// 選択器にフレームを渡す (必要な枚数を送り終えたら選択器を破棄)
+fn offer_to_tap(slot: &Mutex<Option<IntervalTap>>, timed: &TimedFrame) {
+    if let Ok(mut tap_lock) = slot.lock() {
+        if let Some(tap) = tap_lock.as_mut() {
+            if !tap.offer(timed) {
+                *tap_lock = None;
+            }
+        }
+    }
+}
+
+/// UIスレッドからキャプチャスレッドへの要求
+///
+/// ソースはキャプチャスレッドが所有しているため、設定の変更はこの要求を介して行い、
+/// 結果は要求ごとのチャネルで返す。
+enum CaptureCommand {
+    /// 動作モードを変更し、実際に適用された値を返す
+    ApplyMode(VideoMode, Sender<Result<SourceProperties, CameraError>>),
+    /// 候補のモードを順に試し、検出結果を返す
+    ProbeModes(Vec<VideoMode>, Sender<Vec<ModeProbe>>),
+}
+
+/// 直近のフレームを保持する固定長のリングバッファ
+///
+/// 容量を超えた場合は古いフレームから破棄する。
+struct FrameRing {
+    /// 保持しているフレーム (古い順)
+    frames: VecDeque<TimedFrame>,
+    /// 最大保持数
+    capacity: usize,
+}
+
+impl FrameRing {
+    /// 指定した容量のリングバッファを作成
+    fn new(capacity: usize) -> Self {
+        Self {
+            frames: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// フレームを追加 (容量を超えた場合は最古のフレームを破棄)
+    fn push(&mut self, frame: TimedFrame) {
+        if self.frames.len() >= self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame);
+    }
+
+    /// 最新フレーム
+    fn latest(&self) -> Option<&TimedFrame> {
+        self.frames.back()
+    }
+}
+
+/// 直近の時間窓でフレームレートを計測するカウンター
+#[derive(Default)]
+pub struct FpsCounter {
+    /// 時間窓内のフレーム時刻
+    ticks: VecDeque<Instant>,
+}
+
+impl FpsCounter {
+    /// 1フレーム分を記録
+    pub fn tick(&mut self) {
+        let now = Instant::now();
+        self.ticks.push_back(now);
+        // 時間窓から外れた記録を破棄
+        while let Some(&oldest) = self.ticks.front() {
+            if now.duration_since(oldest) > FPS_WINDOW {
+                self.ticks.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// 直近の時間窓でのフレームレート
+    pub fn fps(&self) -> f64 {
+        match (self.ticks.front(), self.ticks.back()) {
+            (Some(first), Some(last)) if self.ticks.len() > 1 => {
+                let elapsed = last.duration_since(*first).as_secs_f64();
+                if elapsed > 0.0 {
+                    (self.ticks.len() - 1) as f64 / elapsed
+                } else {
+                    0.0
+                }
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+/// キャプチャスレッドとUIスレッドで共有する状態
+struct SharedState {
+    /// 直近のフレーム (写真撮影用)
+    ring: Mutex<FrameRing>,
+    /// 録画スレッドへの送信口 (録画中のみSome)
+    recorder: Mutex<Option<SyncSender<TimedFrame>>>,
+    /// 録画開始前のフレームを保持するバッファ (有効な場合のみSome、録画中はフレームを渡さない)
+    pre_record: Mutex<Option<PreRecordBuffer>>,
+    /// 写真のベストショット選択に使うフレームの選択器 (候補を集めている間のみSome)
+    photo: Mutex<Option<IntervalTap>>,
+    /// 連写に使うフレームの選択器 (連写中のみSome)
+    burst: Mutex<Option<IntervalTap>>,
+    /// タイムラプスに使うフレームの選択器 (タイムラプス撮影中のみSome)
+    timelapse: Mutex<Option<IntervalTap>>,
+    /// キャプチャFPSの計測
+    capture_fps: Mutex<FpsCounter>,
+    /// キャプチャスレッドで発生し、まだUIに渡していないエラー
+    errors: Mutex<Vec<CameraError>>,
+}
+
+impl SharedState {
+    /// エラーを記録 (UIスレッドが`take_errors`で取り出す)
+    fn report(&self, error: CameraError) {
+        if let Ok(mut errors) = self.errors.lock() {
+            errors.push(error);
+        }
+    }
+}
+
+/// フレームソースを所有し、バックグラウンドでフレームを取得し続けるスレッド
+///
+/// UIの描画ループとは独立してフレームを取得し、以下の各コンシューマーへ供給する:
+/// - プレビュー: eGui用に変換した画像を`current_frame`に格納
+/// - 録画: 録画スレッドへチャネル経由で送信 (詰まっている場合は破棄)、
+///   録画していない間は録画開始前のバッファへ送信
+/// - 連写・タイムラプス: 撮影間隔ごとにそれぞれの書き込みスレッドへ送信
+/// - 写真撮影: 表示中のフレームをリングバッファから取得し、ベストショットの候補は写真の保存スレッドへ送信
+pub struct CaptureThread {
+    /// スレッドの実行フラグ (falseで停止)
+    running: Arc<AtomicBool>,
+    /// スレッドハンドル (終了時にソースを返す)
+    handle: Option<JoinHandle<Box<dyn FrameSource>>>,
+    /// スレッドと共有する状態
+    shared: Arc<SharedState>,
+    /// キャプチャスレッドへの要求の送信口
+    commands: Sender<CaptureCommand>,
+}
+
+impl CaptureThread {
+    /// 開いたフレームソースを受け取り、キャプチャスレッドを起動
+    ///
+    /// # 引数
+    /// * `source` - 開いた状態のフレームソース (スレッドが所有する)
+    /// * `preview` - プレビュー画像の格納先 (UIスレッドと共有)
+    pub fn spawn(
+        source: Box<dyn FrameSource>,
+        preview: Arc<Mutex<Option<egui::ColorImage>>>,
+    ) -> Self {
+        let running = Arc::new(AtomicBool::new(true));
+        let shared = Arc::new(SharedState {
+            ring: Mutex::new(FrameRing::new(FRAME_RING_CAPACITY)),
+            recorder: Mutex::new(None),
+            pre_record: Mutex::new(None),
+            photo: Mutex::new(None),
+            burst: Mutex::new(None),
+            timelapse: Mutex::new(None),
+            capture_fps: Mutex::new(FpsCounter::default()),
+            errors: Mutex::new(Vec::new()),
+        });
+
+        let (commands, command_receiver) = mpsc::channel();
+
+        let handle = {
+            let running = running.clone();
+            let shared = shared.clone();
+            thread::spawn(move || capture_loop(source, running, shared, preview, command_receiver))
+        };
+
+        Self {
+            running,
+            handle: Some(handle),
+            shared,
+            commands,
+        }
+    }
+
+    /// 動作モードの変更を要求
+    ///
+    /// 変更はキャプチャスレッドで次のフレームの取得前に行われる。
+    ///
+    /// # 戻り値
+    /// 実際に適用されたプロパティを受け取るための受信口
+    pub fn apply_mode(&self, mode: VideoMode) -> Receiver<Result<SourceProperties, CameraError>> {
+        let (sender, receiver) = mpsc::channel();
+        // スレッドが終了している場合は送信口が破棄され、受信側で検知できる
+        let _ = self.commands.send(CaptureCommand::ApplyMode(mode, sender));
+        receiver
+    }
+
+    /// 候補のモードの検出を要求 (検出中はプレビューが止まる)
+    ///
+    /// # 戻り値
+    /// 検出結果を受け取るための受信口
+    pub fn probe_modes(&self, candidates: Vec<VideoMode>) -> Receiver<Vec<ModeProbe>> {
+        let (sender, receiver) = mpsc::channel();
+        let _ = self
+            .commands
+            .send(CaptureCommand::ProbeModes(candidates, sender));
+        receiver
+    }
+
+    /// 録画スレッドへの送信口を設定 (Noneで録画への供給を停止)
+    pub fn set_recorder(&self, sender: Option<SyncSender<TimedFrame>>) {
+        if let Ok(mut recorder_lock) = self.shared.recorder.lock() {
+            *recorder_lock = sender;
+        }
+    }
+
+    /// 録画開始前のフレームを保持する時間を設定 (Noneで保持しない、変更すると保持中のフレームは破棄)
+    pub fn set_pre_record(&self, duration: Option<Duration>) {
+        if let Ok(mut pre_record_lock) = self.shared.pre_record.lock() {
+            if pre_record_lock.as_ref().map(|buffer| buffer.duration()) != duration {
+                *pre_record_lock = duration.map(PreRecordBuffer::spawn);
+            }
+        }
+    }
+
+    /// 保持している録画開始前のフレームを取り出すよう要求
+    ///
+    /// `set_recorder`で録画スレッドへの供給を始めてから呼ぶこと。
+    /// それまでにバッファへ渡したフレームが古い順に`reply`へ送られる
+    /// (バッファが無い場合は`reply`を破棄する)。
+    pub fn drain_pre_record(&self, reply: Sender<Vec<EncodedFrame>>) {
+        if let Ok(pre_record_lock) = self.shared.pre_record.lock() {
+            if let Some(buffer) = pre_record_lock.as_ref() {
+                buffer.drain(reply);
+            }
+        }
+    }
+
+    /// 最新のフレーム (プレビューに表示中のフレーム) の複製
+    ///
+    /// # 戻り値
+    /// まだフレームを取得していない場合はNone
+    pub fn latest_frame(&self) -> Option<TimedFrame> {
+        let ring = self.shared.ring.lock().ok()?;
+        ring.latest().and_then(|latest| latest.try_clone().ok())
+    }
+
+    /// 写真のベストショット選択に使うフレームの選択器を設定 (Noneで供給を停止)
+    pub fn set_photo(&self, tap: Option<IntervalTap>) {
+        if let Ok(mut photo_lock) = self.shared.photo.lock() {
+            *photo_lock = tap;
+        }
+    }
+
+    /// 連写に使うフレームの選択器を設定 (Noneで連写への供給を停止)
+    pub fn set_burst(&self, tap: Option<IntervalTap>) {
+        if let Ok(mut burst_lock) = self.shared.burst.lock() {
+            *burst_lock = tap;
+        }
+    }
+
+    /// タイムラプスに使うフレームの選択器を設定 (Noneでタイムラプスへの供給を停止)
+    pub fn set_timelapse(&self, tap: Option<IntervalTap>) {
+        if let Ok(mut timelapse_lock) = self.shared.timelapse.lock() {
+            *timelapse_lock = tap;
+        }
+    }
+
+    /// タイムラプスに使うフレームの選択器を取り出す (ソースを切り替えても撮影を続けるため)
+    pub fn take_timelapse(&self) -> Option<IntervalTap> {
+        self.shared
+            .timelapse
+            .lock()
+            .ok()
+            .and_then(|mut timelapse_lock| timelapse_lock.take())
+    }
+
+    /// 計測したキャプチャFPS
+    pub fn capture_fps(&self) -> f64 {
+        self.shared
+            .capture_fps
+            .lock()
+            .map(|counter| counter.fps())
+            .unwrap_or(0.0)
+    }
+
+    /// キャプチャスレッドで発生したエラーを取り出す (取り出したエラーは消える)
+    pub fn take_errors(&self) -> Vec<CameraError> {
+        self.shared
+            .errors
+            .lock()
+            .map(|mut errors| std::mem::take(&mut *errors))
+            .unwrap_or_default()
+    }
+
+    /// スレッドを停止し、フレームソースを閉じる
+    pub fn stop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            match handle.join() {
+                Ok(mut source) => source.close(),
+                Err(_) => log::error!("キャプチャスレッドが異常終了しました"),
+            }
+        }
+    }
+}
+
+impl Drop for CaptureThread {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// キャプチャスレッドの本体
+///
+/// 実行フラグが下ろされるまでフレームを取得し続け、終了時にソースを返す。
+fn capture_loop(
+    mut source: Box<dyn FrameSource>,
+    running: Arc<AtomicBool>,
+    shared: Arc<SharedState>,
+    preview: Arc<Mutex<Option<egui::ColorImage>>>,
+    commands: Receiver<CaptureCommand>,
+) -> Box<dyn FrameSource> {
+    let mut sequence = 0;
+    // 変換の失敗が続いている間は同じエラーを繰り返し報告しない
+    let mut conversion_failing = false;
+    // 読み取りの失敗が続いている回数・開始時刻 (報告はこの間に1回だけ)
+    let mut read_failures: u32 = 0;
+    let mut failing_since: Option<Instant> = None;
+    let mut read_failure_reported = false;
+
+    while running.load(Ordering::Relaxed) {
+        // UIスレッドからの要求を処理 (結果の受信側が破棄されていても継続)
+        while let Ok(command) = commands.try_recv() {
+            match command {
+                CaptureCommand::ApplyMode(mode, reply) => {
+                    let _ = reply.send(source.apply_mode(&mode));
+                }
+                CaptureCommand::ProbeModes(candidates, reply) => {
+                    let _ = reply.send(source.probe_modes(&candidates));
+                }
+            }
+        }
+
+        let mut frame = Mat::default();
+        match source.read(&mut frame) {
+            Ok(true) => {
+                if read_failure_reported {
+                    log::info!("フレームの読み取りが回復しました: {}", source.name());
+                }
+                read_failures = 0;
+                failing_since = None;
+                read_failure_reported = false;
+            }
+            Ok(false) => {
+                thread::sleep(READ_RETRY_INTERVAL);
+                continue;
+            }
+            Err(e) => {
+                // 一時的な失敗は報告せず、続いている場合 (カメラの切断等) だけUIに伝える
+                read_failures += 1;
+                let since = *failing_since.get_or_insert_with(Instant::now);
+                if !read_failure_reported
+                    && (read_failures >= READ_FAILURE_LIMIT
+                        || since.elapsed() >= READ_FAILURE_TIMEOUT)
+                {
+                    log::error!("{} ({}回連続)", e, read_failures);
+                    shared.report(e);
+                    read_failure_reported = true;
+                } else {
+                    log::debug!("{}", e);
+                }
+                thread::sleep(READ_RETRY_INTERVAL);
+                continue;
+            }
+        }
+
+        sequence += 1;
+        let timed = TimedFrame {
+            frame,
+            captured_at: Instant::now(),
+            sequence,
+        };
+
+        if let Ok(mut fps_lock) = shared.capture_fps.lock() {
+            fps_lock.tick();
+        }
+
+        // 録画: 録画スレッドへ送信 (キューが満杯の場合は破棄してキャプチャを止めない)
+        // 録画していない間は録画開始前のバッファへ送信する。録画の送信口を確認してから
+        // 送り終えるまでロックを保持するため、録画開始時にバッファと録画の両方へ送られるフレームは無い
+        if let Ok(mut recorder_lock) = shared.recorder.lock() {
+            if let Some(sender) = recorder_lock.as_ref() {
+                if let Ok(copy) = timed.try_clone() {
+                    if let Err(TrySendError::Disconnected(_)) = sender.try_send(copy) {
+                        // 録画スレッドが終了している場合は送信口を破棄
+                        *recorder_lock = None;
+                    }
+                }
+            } else if let Ok(mut pre_record_lock) = shared.pre_record.lock() {
+                if pre_record_lock
+                    .as_ref()
+                    .is_some_and(|buffer| !buffer.offer(&timed))
+                {
+                    // 圧縮スレッドが終了している場合はバッファを破棄
+                    *pre_record_lock = None;
+                }
+            }
+        }
+
+        // 写真・連写・タイムラプス: 撮影間隔ごとに書き込みスレッドへ送信
+        offer_to_tap(&shared.photo, &timed);
+        offer_to_tap(&shared.burst, &timed);
+        offer_to_tap(&shared.timelapse, &timed);
+
+        // プレビュー: eGui用に変換して格納
+        match mat_to_color_image(&timed.frame) {
+            Ok(color_image) => {
+                conversion_failing = false;
+                if let Ok(mut frame_lock) = preview.lock() {
+                    *frame_lock = Some(color_image);
+                }
+            }
+            Err(e) => {
+                if !conversion_failing {
+                    shared.report(e);
+                }
+                conversion_failing = true;
+            }
+        }
+
+        // 写真撮影: リングバッファに追加
+        if let Ok(mut ring) = shared.ring.lock() {
+            ring.push(timed);
+        }
+    }
+
+    source
+}
@@ -0,0 +1,399 @@
+// 標準ライブラリのインポート
+use std::path::PathBuf;
+use std::time::Duration;
+
+// サードパーティクレートのインポート
+use camera_app::burst::MAX_BURST_COUNT;
+use camera_app::logging;
+use camera_app::session::{CameraSession, MAX_FPS, MIN_FPS};
+use camera_app::source::{
+    CameraDevice, PlaybackConfig, PlaybackMode, SourceKind, TestPatternConfig,
+};
+use camera_app::CaptureMode;
+use clap::{Args, Parser, Subcommand};
+use log::LevelFilter;
+
+/// `--device`でテストパターンを指定する際の接頭辞 (例: `test`, `test:1280x720@60`)
+const TEST_PATTERN_PREFIX: &str = "test";
+
+/// コマンドライン引数
+///
+/// 指定した項目は設定ファイルの内容より優先される (設定ファイルには保存しない)。
+#[derive(Debug, Parser)]
+#[command(
+    name = "camera_app",
+    version,
+    about = "Surface Go 4向けカメラアプリケーション",
+    long_about = None
+)]
+pub struct Cli {
+    /// ウィンドウを開かずに実行するサブコマンド (省略時はGUIを起動)
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// 使用するソース: カメラインデックス (0, 1...)、デバイスパス (/dev/videoN)、
+    /// GStreamerパイプライン、動画ファイル/画像フォルダ、または test[:WxH@FPS]
+    #[arg(
+        short,
+        long,
+        global = true,
+        value_name = "SOURCE",
+        value_parser = parse_source,
+        allow_negative_numbers = true
+    )]
+    pub device: Option<SourceArg>,
+
+    /// 要求する解像度 (例: 1280x720)
+    #[arg(short, long, global = true, value_name = "WxH", value_parser = parse_size)]
+    pub resolution: Option<(i32, i32)>,
+
+    /// 要求するフレームレート
+    #[arg(
+        short,
+        long,
+        global = true,
+        value_parser = parse_fps,
+        allow_negative_numbers = true
+    )]
+    pub fps: Option<f64>,
+
+    /// 写真・動画の保存先ディレクトリ
+    #[arg(short, long, global = true, value_name = "DIR")]
+    pub output_dir: Option<PathBuf>,
+
+    /// 起動時のキャプチャモード (photo / burst / timelapse / video)
+    #[arg(short, long, value_name = "MODE", value_parser = parse_capture_mode)]
+    pub mode: Option<CaptureMode>,
+
+    /// 起動後すぐに録画を開始する (動画モードで起動)
+    #[arg(long)]
+    pub record: bool,
+
+    /// ウィンドウサイズ (例: 1024x768)
+    #[arg(long, value_name = "WxH", value_parser = parse_size)]
+    pub window_size: Option<(i32, i32)>,
+
+    /// フルスクリーンで起動
+    #[arg(long)]
+    pub fullscreen: bool,
+
+    /// ログレベル (off / error / warn / info / debug / trace)
+    #[arg(long, global = true, value_name = "LEVEL", value_parser = logging::parse_level)]
+    pub log_level: Option<LevelFilter>,
+}
+
+impl Cli {
+    /// 設定ファイルの内容を上書きする項目が指定されているか
+    pub fn overrides_settings(&self) -> bool {
+        self.device.is_some()
+            || self.resolution.is_some()
+            || self.fps.is_some()
+            || self.output_dir.is_some()
+            || self.mode.is_some()
+            || self.record
+    }
+
+    /// 指定された項目をセッションに反映 (カメラを開く前に呼ぶ)
+    pub fn apply_to(&self, session: &mut CameraSession) {
+        if let Some((width, height)) = self.resolution {
+            session.set_resolution(width, height);
+        }
+        if let Some(fps) = self.fps {
+            session.set_requested_fps(Some(fps));
+        }
+        if let Some(dir) = self.output_dir.as_ref() {
+            session.set_output_dir(dir.clone());
+        }
+        if let Some(mode) = self.mode {
+            session.set_capture_mode(mode);
+        }
+        if self.record {
+            session.set_capture_mode(CaptureMode::Video);
+        }
+
+        match self.device.clone() {
+            Some(SourceArg::Camera(index)) => session.set_camera_index(index),
+            // テストパターン・ファイル再生にも解像度・FPSの指定を反映
+            Some(SourceArg::Source(SourceKind::TestPattern(mut config))) => {
+                if let Some((width, height)) = self.resolution {
+                    config.width = width;
+                    config.height = height;
+                }
+                if let Some(fps) = self.fps {
+                    config.fps = fps;
+                }
+                session.set_source_kind(SourceKind::TestPattern(config));
+            }
+            Some(SourceArg::Source(SourceKind::Playback(mut config))) => {
+                config.fps = self.fps;
+                session.set_source_kind(SourceKind::Playback(config));
+            }
+            Some(SourceArg::Source(kind)) => session.set_source_kind(kind),
+            None => {}
+        }
+    }
+}
+
+/// ウィンドウを開かずに実行するサブコマンド
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// ウィンドウを開かずに写真撮影・録画を行って終了する (サーバー・テスト用)
+    Capture(CaptureArgs),
+}
+
+/// `capture`サブコマンドの引数 (写真・連写・録画のいずれか1つを指定)
+#[derive(Debug, Args)]
+#[group(required = true, multiple = false)]
+pub struct CaptureArgs {
+    /// 写真を1枚撮影する
+    #[arg(long)]
+    pub photo: bool,
+
+    /// 指定した枚数を連写する (間隔は設定ファイルの`burst_interval_ms`)
+    #[arg(long, value_name = "COUNT", value_parser = clap::value_parser!(u32).range(1..=i64::from(MAX_BURST_COUNT)))]
+    pub burst: Option<u32>,
+
+    /// 指定した時間だけ録画する (例: 30s, 2m, 1500ms。単位省略時は秒)
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub record: Option<Duration>,
+}
+
+/// `--device`で指定されたソース
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourceArg {
+    /// カメラインデックス (現在の役割に割り当てる)
+    Camera(i32),
+    /// カメラインデックス以外のソース (デバイスパス・テストパターン・ファイル再生)
+    Source(SourceKind),
+}
+
+/// `--device`の値を解析
+///
+/// 数字はカメラインデックス、`test`で始まる場合はテストパターン、
+/// デバイスパス・GStreamerパイプラインはカメラ、それ以外は再生するファイル/フォルダとみなす。
+fn parse_source(text: &str) -> Result<SourceArg, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("ソースが指定されていません".to_string());
+    }
+    if let Ok(index) = text.parse::<i32>() {
+        if index < 0 {
+            return Err(format!(
+                "カメラ番号は0以上の整数で指定してください: {}",
+                text
+            ));
+        }
+        return Ok(SourceArg::Camera(index));
+    }
+    if let Some(spec) = text.strip_prefix(TEST_PATTERN_PREFIX) {
+        let config = match spec.strip_prefix(':') {
+            Some(spec) => TestPatternConfig::parse(spec)?,
+            None if spec.is_empty() => TestPatternConfig::default(),
+            // "testdata/"等、testで始まるパスは再生するファイルとみなす
+            None => return Ok(playback_source(text)),
+        };
+        return Ok(SourceArg::Source(SourceKind::TestPattern(config)));
+    }
+    if CameraDevice::looks_like_device(text) {
+        return Ok(SourceArg::Source(SourceKind::Device(text.to_string())));
+    }
+    Ok(playback_source(text))
+}
+
+/// ファイル再生のソース (コマンドラインから開く場合はループ再生)
+fn playback_source(path: &str) -> SourceArg {
+    SourceArg::Source(SourceKind::Playback(PlaybackConfig {
+        path: PathBuf::from(path),
+        mode: PlaybackMode::Loop,
+        fps: None,
+    }))
+}
+
+/// `WIDTHxHEIGHT`形式の文字列を解析
+fn parse_size(text: &str) -> Result<(i32, i32), String> {
+    let (width, height) = text
+        .split_once(['x', 'X'])
+        .ok_or_else(|| format!("サイズは WIDTHxHEIGHT の形式で指定してください: {}", text))?;
+    let width: i32 = width
+        .trim()
+        .parse()
+        .map_err(|_| format!("幅が不正です: {}", width))?;
+    let height: i32 = height
+        .trim()
+        .parse()
+        .map_err(|_| format!("高さが不正です: {}", height))?;
+    if width <= 0 || height <= 0 {
+        return Err(format!("サイズは正の値で指定してください: {}", text));
+    }
+    Ok((width, height))
+}
+
+/// 時間を解析 (`ms`/`s`/`m`/`h`の単位付き、単位省略時は秒)
+fn parse_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let (value, unit) = text.split_at(split);
+    let value: f64 = value
+        .parse()
+        .map_err(|_| format!("時間の形式が不正です: {}", text))?;
+    let seconds = match unit.trim() {
+        "ms" => value / 1000.0,
+        "" | "s" => value,
+        "m" => value * 60.0,
+        "h" => value * 3600.0,
+        _ => {
+            return Err(format!(
+                "時間の単位は ms / s / m / h で指定してください: {}",
+                text
+            ))
+        }
+    };
+    if seconds <= 0.0 {
+        return Err(format!("時間は正の値で指定してください: {}", text));
+    }
+    Duration::try_from_secs_f64(seconds).map_err(|_| format!("時間が長すぎます: {}", text))
+}
+
+/// FPSを解析 (録画で使える範囲の有限の値のみ)
+fn parse_fps(text: &str) -> Result<f64, String> {
+    match text.trim().parse::<f64>() {
+        Ok(fps) if fps.is_finite() && fps > MIN_FPS && fps <= MAX_FPS => Ok(fps),
+        _ => Err(format!(
+            "FPSは{}より大きく{}以下の数で指定してください: {}",
+            MIN_FPS, MAX_FPS, text
+        )),
+    }
+}
+
+/// キャプチャモードを解析
+fn parse_capture_mode(text: &str) -> Result<CaptureMode, String> {
+    match text.trim().to_ascii_lowercase().as_str() {
+        "photo" => Ok(CaptureMode::Photo),
+        "burst" => Ok(CaptureMode::Burst),
+        "timelapse" => Ok(CaptureMode::Timelapse),
+        "video" => Ok(CaptureMode::Video),
+        _ => Err(format!(
+            "モードは photo、burst、timelapse、video のいずれかで指定してください: {}",
+            text
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_camera_index_and_device_path() {
+        assert_eq!(parse_source(" 1 "), Ok(SourceArg::Camera(1)));
+        assert_eq!(
+            parse_source("/dev/video2"),
+            Ok(SourceArg::Source(SourceKind::Device(
+                "/dev/video2".to_string()
+            )))
+        );
+        assert!(matches!(
+            parse_source("v4l2src ! videoconvert ! appsink"),
+            Ok(SourceArg::Source(SourceKind::Device(_)))
+        ));
+    }
+
+    #[test]
+    fn parses_test_pattern() {
+        assert_eq!(
+            parse_source("test"),
+            Ok(SourceArg::Source(SourceKind::TestPattern(
+                TestPatternConfig::default()
+            )))
+        );
+        assert_eq!(
+            parse_source("test:640x480@15"),
+            Ok(SourceArg::Source(SourceKind::TestPattern(
+                TestPatternConfig {
+                    width: 640,
+                    height: 480,
+                    fps: 15.0,
+                }
+            )))
+        );
+    }
+
+    #[test]
+    fn paths_starting_with_test_are_played_back() {
+        assert_eq!(
+            parse_source("testdata/clip.mp4"),
+            Ok(playback_source("testdata/clip.mp4"))
+        );
+        assert!(matches!(
+            parse_source("clip.mp4"),
+            Ok(SourceArg::Source(SourceKind::Playback(PlaybackConfig {
+                mode: PlaybackMode::Loop,
+                ..
+            })))
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_sources() {
+        assert!(parse_source("").is_err());
+        assert!(parse_source("   ").is_err());
+        assert!(parse_source("test:").is_err());
+        assert!(parse_source("test:640").is_err());
+        assert!(parse_source("test:640x480@fast").is_err());
+        assert!(parse_source("test:0x480").is_err());
+    }
+
+    #[test]
+    fn rejects_negative_camera_index() {
+        let result = parse_source("-1");
+        assert!(
+            matches!(&result, Err(error) if error.contains("0以上")),
+            "{:?}",
+            result
+        );
+        assert_eq!(parse_source("0"), Ok(SourceArg::Camera(0)));
+
+        // 負の数もオプションと誤認されずに解析まで届く
+        let result = Cli::try_parse_from(["camera_app", "--device", "-1"]);
+        assert!(
+            matches!(&result, Err(error) if error.to_string().contains("0以上")),
+            "{:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn parses_fps_within_recording_range() {
+        assert_eq!(parse_fps("30"), Ok(30.0));
+        assert_eq!(parse_fps(" 29.97 "), Ok(29.97));
+        assert_eq!(parse_fps(&MAX_FPS.to_string()), Ok(MAX_FPS));
+    }
+
+    #[test]
+    fn rejects_non_finite_and_out_of_range_fps() {
+        for text in ["", "fast", "0", "-30", "120.5", "1000", "inf", "NaN"] {
+            assert!(parse_fps(text).is_err(), "{}", text);
+        }
+        assert!(Cli::try_parse_from(["camera_app", "--fps", "-30"]).is_err());
+    }
+
+    #[test]
+    fn parses_durations_with_units() {
+        assert_eq!(parse_duration("10"), Ok(Duration::from_secs(10)));
+        assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration(" 1h "), Ok(Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn rejects_malformed_durations() {
+        for text in ["", "s", "abc", "10x", "1.2.3s", "-5s", "0", "0ms", "5 min"] {
+            assert!(parse_duration(text).is_err(), "{}", text);
+        }
+        // Durationで表せない長さ
+        assert!(parse_duration(&format!("{}h", f64::MAX)).is_err());
+    }
+}
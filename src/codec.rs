@@ -0,0 +1,329 @@
+// 標準ライブラリのインポート
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+// サードパーティクレートのインポート
+use opencv::core::Size;
+use serde::{Deserialize, Serialize};
+
+use crate::recorder;
+use crate::source::FourCc;
+
+/// 録画形式の選択肢に並べるコーデック (上から優先して代わりに使う)
+pub const VIDEO_CODECS: [FourCc; 4] = [FourCc::MP4V, FourCc::H264, FourCc::XVID, FourCc::MJPG];
+/// 検出に使うフレームサイズ (どのエンコーダーでも扱える偶数の大きさ)
+const PROBE_FRAME_SIZE: Size = Size {
+    width: 320,
+    height: 240,
+};
+/// 検出に使うFPS
+const PROBE_FPS: f64 = 30.0;
+/// 検出用の一時ファイル名 (拡張子を除く)
+const PROBE_FILE_STEM: &str = "camera_app_codec_probe";
+
+/// 録画ファイルのコンテナ形式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VideoContainer {
+    #[default]
+    Mp4, // MPEG-4 (互換性が高い)
+    Avi, // AVI (OpenCV内蔵のMJPGで必ず書ける)
+    Mkv, // Matroska (異常終了しても途中まで再生しやすい)
+}
+
+impl VideoContainer {
+    /// UIに表示する順序
+    pub const ALL: [VideoContainer; 3] = [
+        VideoContainer::Mp4,
+        VideoContainer::Avi,
+        VideoContainer::Mkv,
+    ];
+
+    /// ファイルの拡張子
+    pub fn extension(self) -> &'static str {
+        match self {
+            VideoContainer::Mp4 => "mp4",
+            VideoContainer::Avi => "avi",
+            VideoContainer::Mkv => "mkv",
+        }
+    }
+
+    /// 拡張子からコンテナ形式を判定 (大文字小文字は区別しない)
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|container| container.extension().eq_ignore_ascii_case(extension))
+    }
+}
+
+/// コーデックの表示名
+pub fn codec_label(codec: FourCc) -> String {
+    match codec {
+        FourCc::MP4V => "MPEG-4 (mp4v)".to_string(),
+        FourCc::H264 => "H.264".to_string(),
+        FourCc::XVID => "Xvid".to_string(),
+        FourCc::MJPG => "Motion JPEG".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// 録画形式 (コーデックとコンテナの組み合わせ)
+///
+/// 設定ファイルでは`mp4v/mp4`のように`{FOURCC}/{拡張子}`の文字列で書く。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct VideoFormat {
+    /// コーデック
+    pub codec: FourCc,
+    /// コンテナ形式
+    pub container: VideoContainer,
+}
+
+impl VideoFormat {
+    /// どの環境でも書き込める形式 (OpenCV内蔵のMJPGエンコーダーとAVI)
+    pub const SAFE: VideoFormat = VideoFormat {
+        codec: FourCc::MJPG,
+        container: VideoContainer::Avi,
+    };
+
+    /// 選択肢に並べるすべての組み合わせ (同じコンテナのコーデックを続けて並べる)
+    pub fn all() -> impl Iterator<Item = VideoFormat> {
+        VideoContainer::ALL.into_iter().flat_map(|container| {
+            VIDEO_CODECS
+                .into_iter()
+                .map(move |codec| VideoFormat { codec, container })
+        })
+    }
+
+    /// `mp4v/mp4`形式の文字列から作成
+    ///
+    /// # 戻り値
+    /// 成功時は`VideoFormat`、解釈できない場合は`Err(String)`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (codec, extension) = text
+            .split_once('/')
+            .ok_or_else(|| format!("録画形式は`mp4v/mp4`の形式で指定してください: {}", text))?;
+        let container = VideoContainer::from_extension(extension.trim())
+            .ok_or_else(|| format!("対応していないコンテナ形式です: {}", extension))?;
+        Ok(Self {
+            codec: FourCc::parse(codec.trim())?,
+            container,
+        })
+    }
+}
+
+impl fmt::Display for VideoFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.codec, self.container.extension())
+    }
+}
+
+impl TryFrom<String> for VideoFormat {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        Self::parse(&text)
+    }
+}
+
+impl From<VideoFormat> for String {
+    fn from(format: VideoFormat) -> Self {
+        format.to_string()
+    }
+}
+
+/// この環境で書き込めた・書き込めなかった録画形式の記録
+///
+/// 検出 (`probe`) の結果と、実際に録画を開始した際の結果を覚えておき、
+/// 書き込めない形式を選んでいる場合は書き込める形式に切り替える。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VideoSupport {
+    /// 書き込めた形式
+    pub supported: Vec<VideoFormat>,
+    /// 書き込めなかった形式
+    pub unsupported: Vec<VideoFormat>,
+}
+
+impl VideoSupport {
+    /// 書き込めるかどうか (まだ試していない場合はNone)
+    pub fn status(&self, format: VideoFormat) -> Option<bool> {
+        if self.supported.contains(&format) {
+            Some(true)
+        } else if self.unsupported.contains(&format) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// 試した結果を記録 (同じ形式の以前の結果は置き換える)
+    pub fn record(&mut self, format: VideoFormat, works: bool) {
+        self.supported.retain(|known| *known != format);
+        self.unsupported.retain(|known| *known != format);
+        if works {
+            self.supported.push(format);
+        } else {
+            self.unsupported.push(format);
+        }
+    }
+
+    /// 一度も試していないかどうか
+    pub fn is_empty(&self) -> bool {
+        self.supported.is_empty() && self.unsupported.is_empty()
+    }
+
+    /// 実際に使う録画形式を決める
+    ///
+    /// 選んだ形式が書き込めないと分かっている場合は、同じコンテナで書き込めるコーデック、
+    /// 他のコンテナで書き込める形式、`VideoFormat::SAFE`の順に代わりを探す。
+    /// まだ試していない形式はそのまま使う (開けない場合は録画開始時にMJPGで再試行する)。
+    pub fn resolve(&self, preferred: VideoFormat) -> VideoFormat {
+        if self.status(preferred) != Some(false) {
+            return preferred;
+        }
+        let same_container = VIDEO_CODECS.into_iter().map(|codec| VideoFormat {
+            codec,
+            container: preferred.container,
+        });
+        same_container
+            .chain(VideoFormat::all())
+            .find(|format| self.status(*format) == Some(true))
+            .unwrap_or(VideoFormat::SAFE)
+    }
+}
+
+/// すべての録画形式を実際に開いて、書き込めるかどうかを調べる
+///
+/// 一時ディレクトリに小さなファイルを作って`VideoWriter::is_opened`を確認し、すぐに削除する。
+/// エンコーダーの初期化に時間がかかる場合があるため、専用スレッドで実行する。
+///
+/// # 戻り値
+/// 検出結果を受け取る受信口
+pub fn probe() -> Receiver<VideoSupport> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut support = VideoSupport::default();
+        for format in VideoFormat::all() {
+            let works = probe_format(format);
+            log::debug!(
+                "録画形式 {}: {}",
+                format,
+                if works { "対応" } else { "非対応" }
+            );
+            support.record(format, works);
+        }
+        let _ = sender.send(support);
+    });
+    receiver
+}
+
+/// 1つの録画形式を開けるかどうか
+fn probe_format(format: VideoFormat) -> bool {
+    let path = probe_path(format);
+    let works = recorder::open_writer(&path, format.codec, PROBE_FPS, PROBE_FRAME_SIZE).is_ok();
+    // VideoWriterは上で解放済み: 作成されたファイルを削除
+    if path.exists() {
+        if let Err(e) = fs::remove_file(&path) {
+            log::warn!("検出用のファイルを削除できませんでした: {:?} ({})", path, e);
+        }
+    }
+    works
+}
+
+/// 検出用の一時ファイルのパス (同時に起動したアプリと重ならないようにプロセスIDを付ける)
+fn probe_path(format: VideoFormat) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "{}_{}.{}",
+        PROBE_FILE_STEM,
+        std::process::id(),
+        format.container.extension()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// コーデックとコンテナから録画形式を作る
+    fn format(codec: FourCc, container: VideoContainer) -> VideoFormat {
+        VideoFormat { codec, container }
+    }
+
+    #[test]
+    fn parses_and_formats_video_format() -> Result<(), String> {
+        let parsed = VideoFormat::parse("mp4v/MP4")?;
+        assert_eq!(parsed, format(FourCc::MP4V, VideoContainer::Mp4));
+        assert_eq!(parsed.to_string(), "mp4v/mp4");
+
+        assert!(VideoFormat::parse("mp4v").is_err());
+        assert!(VideoFormat::parse("mp4v/mov").is_err());
+        assert!(VideoFormat::parse("toolong/mp4").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn untested_or_supported_format_is_used_as_is() {
+        let preferred = format(FourCc::H264, VideoContainer::Mp4);
+        let mut support = VideoSupport::default();
+        assert_eq!(support.resolve(preferred), preferred);
+
+        support.record(preferred, true);
+        assert_eq!(support.resolve(preferred), preferred);
+    }
+
+    #[test]
+    fn unsupported_codec_falls_back_within_same_container() {
+        let preferred = format(FourCc::H264, VideoContainer::Mkv);
+        let mut support = VideoSupport::default();
+        support.record(preferred, false);
+        support.record(format(FourCc::MP4V, VideoContainer::Mp4), true);
+        support.record(format(FourCc::XVID, VideoContainer::Mkv), true);
+
+        // 他のコンテナで書き込める形式より、同じコンテナの形式を優先する
+        assert_eq!(
+            support.resolve(preferred),
+            format(FourCc::XVID, VideoContainer::Mkv)
+        );
+    }
+
+    #[test]
+    fn unsupported_container_falls_back_to_other_container() {
+        let preferred = format(FourCc::MP4V, VideoContainer::Mp4);
+        let mut support = VideoSupport::default();
+        for codec in VIDEO_CODECS {
+            support.record(format(codec, VideoContainer::Mp4), false);
+        }
+        support.record(format(FourCc::MJPG, VideoContainer::Mkv), true);
+
+        assert_eq!(
+            support.resolve(preferred),
+            format(FourCc::MJPG, VideoContainer::Mkv)
+        );
+    }
+
+    #[test]
+    fn nothing_supported_falls_back_to_safe_format() {
+        let preferred = format(FourCc::H264, VideoContainer::Mp4);
+        let mut support = VideoSupport::default();
+        support.record(preferred, false);
+        assert_eq!(support.resolve(preferred), VideoFormat::SAFE);
+    }
+
+    #[test]
+    fn record_replaces_previous_result() {
+        let tested = format(FourCc::XVID, VideoContainer::Avi);
+        let mut support = VideoSupport::default();
+        assert!(support.is_empty());
+        assert_eq!(support.status(tested), None);
+
+        support.record(tested, false);
+        support.record(tested, true);
+        assert_eq!(support.status(tested), Some(true));
+        assert_eq!(support.supported, vec![tested]);
+        assert!(support.unsupported.is_empty());
+    }
+}
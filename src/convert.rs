@@ -0,0 +1,50 @@
+// サードパーティクレートのインポート
+use opencv::{core::Mat, imgproc, prelude::*};
+
+use crate::error::CameraError;
+
+/// OpenCVのフレームをeGui表示用の画像に変換
+///
+/// 以下の処理を行う:
+/// 1. BGR (OpenCV) → RGB (eGui) の色空間変換
+/// 2. バイトデータをegui::ColorImageに変換
+///
+/// # 戻り値
+/// 変換に成功した場合は`Ok(ColorImage)`、失敗した場合は`Err(CameraError::Conversion)`
+pub fn mat_to_color_image(frame: &Mat) -> Result<egui::ColorImage, CameraError> {
+    let conversion = |e: opencv::Error| CameraError::Conversion(e.to_string());
+
+    // フレームをBGR (OpenCV形式) からRGB (eGui形式) に変換
+    let mut rgb_frame = Mat::default();
+    imgproc::cvt_color(frame, &mut rgb_frame, imgproc::COLOR_BGR2RGB, 0).map_err(conversion)?;
+
+    // フレームのサイズを取得
+    let size = rgb_frame.size().map_err(conversion)?;
+    let width = size.width as usize;
+    let height = size.height as usize;
+
+    // フレームのバイトデータを取得
+    let data = rgb_frame.data_bytes().map_err(conversion)?;
+
+    // バイトデータをegui::Color32に変換
+    // 3バイト (R, G, B) を1ピクセルとして処理
+    let pixels: Vec<egui::Color32> = data
+        .chunks(3)
+        .map(|rgb| egui::Color32::from_rgb(rgb[0], rgb[1], rgb[2]))
+        .collect();
+
+    // ピクセル数が正しいか確認 (width × height)
+    if pixels.len() != width * height {
+        return Err(CameraError::Conversion(format!(
+            "ピクセル数が一致しません ({}x{}に対して{})",
+            width,
+            height,
+            pixels.len()
+        )));
+    }
+
+    Ok(egui::ColorImage {
+        size: [width, height],
+        pixels,
+    })
+}
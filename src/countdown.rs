@@ -0,0 +1,86 @@
+// 標準ライブラリのインポート
+use std::time::{Duration, Instant};
+
+// サードパーティクレートのインポート
+use camera_app::CaptureMode;
+use eframe::egui;
+
+/// セルフタイマーの選択肢 (秒)
+pub const SELF_TIMER_PRESETS: [u32; 3] = [3, 5, 10];
+/// カウントダウンの数字の大きさ (ポイント)
+const OVERLAY_FONT_SIZE: f32 = 160.0;
+/// プレビューを暗くする度合い (0〜255)
+const OVERLAY_DIM_ALPHA: u8 = 96;
+/// キャンセルボタンの大きさ
+const CANCEL_BUTTON_SIZE: egui::Vec2 = egui::vec2(160.0, 40.0);
+/// 数字の中心からキャンセルボタンまでの距離 (ピクセル)
+const CANCEL_BUTTON_OFFSET: f32 = 120.0;
+
+/// セルフタイマーのカウントダウン
+///
+/// 撮影・録画開始ボタンを押してから実際に実行するまでの待ち時間を管理する。
+/// 実行はUIスレッドで`is_finished`を確認して行う。
+pub struct Countdown {
+    /// 終了時に実行する操作 (写真撮影・連写・タイムラプス・録画のいずれを開始するか)
+    action: CaptureMode,
+    /// 実行する時刻
+    fires_at: Instant,
+}
+
+impl Countdown {
+    /// カウントダウンを開始
+    pub fn start(action: CaptureMode, seconds: u32) -> Self {
+        Self {
+            action,
+            fires_at: Instant::now() + Duration::from_secs(u64::from(seconds)),
+        }
+    }
+
+    /// 終了時に実行する操作
+    pub fn action(&self) -> CaptureMode {
+        self.action
+    }
+
+    /// 実行する時刻になったかどうか
+    pub fn is_finished(&self) -> bool {
+        Instant::now() >= self.fires_at
+    }
+
+    /// 残り秒数 (切り上げ、表示用)
+    fn remaining_secs(&self) -> u64 {
+        let remaining = self.fires_at.saturating_duration_since(Instant::now());
+        remaining.as_millis().div_ceil(1000) as u64
+    }
+
+    /// プレビューの上に残り秒数とキャンセルボタンを重ねて描画
+    ///
+    /// # 戻り値
+    /// キャンセルボタンが押された場合はtrue
+    pub fn show_overlay(&self, ui: &mut egui::Ui, preview: egui::Rect) -> bool {
+        let painter = ui.painter_at(preview);
+        painter.rect_filled(
+            preview,
+            0.0,
+            egui::Color32::from_black_alpha(OVERLAY_DIM_ALPHA),
+        );
+        painter.text(
+            preview.center(),
+            egui::Align2::CENTER_CENTER,
+            self.remaining_secs().to_string(),
+            egui::FontId::proportional(OVERLAY_FONT_SIZE),
+            egui::Color32::WHITE,
+        );
+
+        let label = match self.action {
+            CaptureMode::Photo => "✖ 撮影をキャンセル",
+            CaptureMode::Burst => "✖ 連写をキャンセル",
+            CaptureMode::Timelapse => "✖ タイムラプスをキャンセル",
+            CaptureMode::Video => "✖ 録画をキャンセル",
+        };
+        let button_rect = egui::Rect::from_center_size(
+            preview.center() + egui::vec2(0.0, CANCEL_BUTTON_OFFSET),
+            CANCEL_BUTTON_SIZE,
+        );
+        ui.put(button_rect, egui::Button::new(label)).clicked()
+    }
+}
@@ -0,0 +1,109 @@
+// 標準ライブラリのインポート
+use std::thread::{self, JoinHandle};
+
+// サードパーティクレートのインポート
+use opencv::{
+    prelude::*,
+    videoio::{self, VideoCapture},
+};
+
+use crate::source::backend_name;
+
+/// 検出を試みるカメラインデックスの上限 (0からこの値未満まで)
+const MAX_PROBE_INDEX: i32 = 8;
+
+/// 検出されたカメラデバイスの情報
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceInfo {
+    /// カメラデバイスのインデックス
+    pub index: i32,
+    /// 開くことができたバックエンド
+    pub backend: i32,
+    /// バックエンドの名前 (MSMF, DSHOW等)
+    pub backend_name: String,
+    /// デフォルトの解像度 (幅)
+    pub width: i32,
+    /// デフォルトの解像度 (高さ)
+    pub height: i32,
+}
+
+impl DeviceInfo {
+    /// ピッカーに表示するラベル
+    pub fn label(&self) -> String {
+        format!(
+            "カメラ {} - {} ({}x{})",
+            self.index, self.backend_name, self.width, self.height
+        )
+    }
+}
+
+/// 接続されているカメラデバイスを検出
+///
+/// インデックス0から順に、指定されたバックエンドを順番に試して開けるかを確認する。
+/// 使用中のデバイスは開けない場合があるため、呼び出し側で補完すること。
+/// デバイスを開くため時間がかかる (1台あたり数百ミリ秒〜数秒)。
+pub fn probe_devices(backends: &[i32]) -> Vec<DeviceInfo> {
+    let mut devices = Vec::new();
+
+    for index in 0..MAX_PROBE_INDEX {
+        for backend in backends {
+            let cam = match VideoCapture::new(index, *backend) {
+                Ok(cam) => cam,
+                Err(e) => {
+                    log::debug!("検出: カメラ {} - {}: {}", index, backend_name(*backend), e);
+                    continue;
+                }
+            };
+            if !cam.is_opened().unwrap_or(false) {
+                log::debug!(
+                    "検出: カメラ {} - {}: 開けません",
+                    index,
+                    backend_name(*backend)
+                );
+                continue;
+            }
+
+            devices.push(DeviceInfo {
+                index,
+                backend: *backend,
+                backend_name: cam
+                    .get_backend_name()
+                    .unwrap_or_else(|_| backend_name(*backend)),
+                width: cam.get(videoio::CAP_PROP_FRAME_WIDTH).unwrap_or(0.0) as i32,
+                height: cam.get(videoio::CAP_PROP_FRAME_HEIGHT).unwrap_or(0.0) as i32,
+            });
+            // 1つのバックエンドで開けたら次のインデックスへ
+            break;
+        }
+    }
+
+    devices
+}
+
+/// バックグラウンドでのデバイス検出 (UIを止めないため)
+pub struct DeviceProbe {
+    /// 検出スレッドのハンドル
+    handle: Option<JoinHandle<Vec<DeviceInfo>>>,
+}
+
+impl DeviceProbe {
+    /// 指定したバックエンドで検出スレッドを起動
+    pub fn spawn(backends: Vec<i32>) -> Self {
+        Self {
+            handle: Some(thread::spawn(move || probe_devices(&backends))),
+        }
+    }
+
+    /// 検出が終わっていれば結果を取り出す
+    ///
+    /// # 戻り値
+    /// 完了済みの場合は`Some(検出結果)`、実行中の場合は`None`
+    pub fn try_take(&mut self) -> Option<Vec<DeviceInfo>> {
+        if !self.handle.as_ref()?.is_finished() {
+            return None;
+        }
+        self.handle
+            .take()
+            .map(|handle| handle.join().unwrap_or_default())
+    }
+}
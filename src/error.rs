@@ -0,0 +1,133 @@
+// 標準ライブラリのインポート
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::source::FourCc;
+
+/// カメラ操作 (ソースを開く・撮影・録画・保存) のエラー
+///
+/// UIはこの値をそのまま表示するため、`Display`は利用者向けの日本語メッセージとする。
+#[derive(Debug)]
+pub enum CameraError {
+    /// フレームソースが開かれていない
+    NotOpen,
+    /// デバイス・動画ファイル・画像フォルダを開けなかった
+    DeviceOpen {
+        /// 開こうとしたソースの表示名
+        device: String,
+        /// 失敗理由 (バックエンドごとの失敗をまとめたもの等)
+        reason: String,
+    },
+    /// ソースの設定 (解像度・FPS・パス等) が不正
+    InvalidConfig(String),
+    /// ソースが要求された操作に対応していない
+    Unsupported(String),
+    /// 動作モードの変更中のため操作できない
+    ModeChangeInProgress,
+    /// 時間内に新しいフレームを取得できなかった
+    FrameTimeout,
+    /// ソースからフレームを読み取れなかった (カメラの切断等)
+    FrameRead {
+        /// ソースの表示名
+        device: String,
+        /// 失敗理由
+        reason: String,
+    },
+    /// 画像の変換 (色空間変換・エンコード) に失敗した
+    Conversion(String),
+    /// 保存先のディスク容量が不足している
+    DiskFull(PathBuf),
+    /// ファイル・ディレクトリの読み書きに失敗した
+    Io {
+        /// 対象のパス
+        path: PathBuf,
+        /// 元のI/Oエラー
+        source: io::Error,
+    },
+    /// VideoWriterを開けなかった
+    WriterOpen {
+        /// 試したコーデック
+        codec: FourCc,
+        /// 失敗理由
+        reason: String,
+    },
+    /// 録画中にフレームを書き込めなかった
+    FrameWrite {
+        /// 録画ファイルのパス
+        path: PathBuf,
+        /// 書き込めなかったフレーム数
+        count: u64,
+    },
+    /// 録画スレッドが異常終了した
+    RecorderFailed(PathBuf),
+}
+
+impl CameraError {
+    /// I/Oエラーを変換 (容量不足は`DiskFull`として区別する)
+    pub fn io(path: &Path, source: io::Error) -> Self {
+        if source.kind() == io::ErrorKind::StorageFull {
+            CameraError::DiskFull(path.to_path_buf())
+        } else {
+            CameraError::Io {
+                path: path.to_path_buf(),
+                source,
+            }
+        }
+    }
+}
+
+impl fmt::Display for CameraError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CameraError::NotOpen => write!(f, "カメラが開かれていません"),
+            CameraError::DeviceOpen { device, reason } => {
+                write!(f, "{}を開けませんでした: {}", device, reason)
+            }
+            CameraError::InvalidConfig(message) => write!(f, "{}", message),
+            CameraError::Unsupported(message) => write!(f, "{}", message),
+            CameraError::ModeChangeInProgress => {
+                write!(f, "動作モードの変更中は撮影・録画できません")
+            }
+            CameraError::FrameTimeout => write!(f, "フレームを取得できませんでした"),
+            CameraError::FrameRead { device, reason } => {
+                write!(f, "{}からフレームを読み取れません: {}", device, reason)
+            }
+            CameraError::Conversion(message) => {
+                write!(f, "画像の変換に失敗しました: {}", message)
+            }
+            CameraError::DiskFull(path) => {
+                write!(f, "ディスクの空き容量が不足しています: {}", path.display())
+            }
+            CameraError::Io { path, source } => {
+                write!(
+                    f,
+                    "ファイルを保存できませんでした: {} ({})",
+                    path.display(),
+                    source
+                )
+            }
+            CameraError::WriterOpen { codec, reason } => {
+                write!(f, "録画ファイルを開けませんでした ({}): {}", codec, reason)
+            }
+            CameraError::FrameWrite { path, count } => write!(
+                f,
+                "録画中に{}フレームを書き込めませんでした: {}",
+                count,
+                path.display()
+            ),
+            CameraError::RecorderFailed(path) => {
+                write!(f, "録画スレッドが異常終了しました: {}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for CameraError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CameraError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,364 @@
+// 標準ライブラリのインポート
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+// サードパーティクレートのインポート
+use chrono::{DateTime, Local};
+
+use crate::CaptureMode;
+
+/// ファイル名テンプレートのデフォルト値 (例: `photo_20250101_120000_123.jpg`)
+pub const DEFAULT_FILENAME_TEMPLATE: &str = "{mode}_{date}_{time}_{ms}";
+/// 連番の桁数 (ゼロ埋め)
+const SEQUENCE_DIGITS: usize = 4;
+/// ファイル名に含めるカメラ名の最大文字数 (パイプライン文字列等が長すぎる場合に切り詰める)
+const MAX_CAMERA_NAME_CHARS: usize = 32;
+/// 同名のファイルがある場合に付ける番号の上限
+const MAX_COLLISION_SUFFIX: u32 = 9999;
+/// ファイル名に使えない文字 (Windowsの制限に合わせる)
+const FORBIDDEN_CHARS: [char; 9] = ['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+/// テンプレートで使用できるトークン
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    /// 日付 (`YYYYMMDD`)
+    Date,
+    /// 時刻 (`HHMMSS`)
+    Time,
+    /// ミリ秒 (`000`〜`999`)
+    Millis,
+    /// セッション内の連番 (`0001`〜)
+    Sequence,
+    /// カメラ名 (ファイル名に使えない文字は`_`に置き換え)
+    Camera,
+    /// キャプチャモード (`photo` / `burst` / `timelapse` / `video`)
+    Mode,
+    /// 解像度 (`1280x720`)
+    Resolution,
+}
+
+impl Token {
+    /// すべてのトークンとテンプレート中の名前
+    const ALL: [(Token, &'static str); 7] = [
+        (Token::Date, "date"),
+        (Token::Time, "time"),
+        (Token::Millis, "ms"),
+        (Token::Sequence, "seq"),
+        (Token::Camera, "camera"),
+        (Token::Mode, "mode"),
+        (Token::Resolution, "resolution"),
+    ];
+
+    /// 名前からトークンを取得
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|(_, known)| *known == name)
+            .map(|(token, _)| *token)
+    }
+}
+
+/// テンプレートの構成要素
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// そのまま出力する文字列
+    Literal(String),
+    /// 撮影時の値に置き換えるトークン
+    Token(Token),
+}
+
+/// ファイル名の生成に使う撮影時の情報
+#[derive(Debug, Clone, Copy)]
+pub struct FilenameContext<'a> {
+    /// キャプチャモード
+    pub mode: CaptureMode,
+    /// カメラ (フレームソース) の表示名
+    pub camera: &'a str,
+    /// 解像度 (幅)
+    pub width: i32,
+    /// 解像度 (高さ)
+    pub height: i32,
+    /// セッション内の連番 (1始まり)
+    pub sequence: u64,
+}
+
+/// ファイル名テンプレート (拡張子を除く部分)
+///
+/// `{date}`, `{time}`, `{ms}`, `{seq}`, `{camera}`, `{mode}`, `{resolution}` の
+/// トークンを撮影時の値に置き換える。例: `{camera}_{date}-{time}_{seq}`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilenameTemplate {
+    /// 解析済みの構成要素
+    segments: Vec<Segment>,
+    /// 元のテンプレート文字列 (設定ファイル保存用)
+    text: String,
+}
+
+impl Default for FilenameTemplate {
+    fn default() -> Self {
+        // デフォルト値は必ず解析できるが、念のためモード名だけのテンプレートで代替する
+        Self::parse(DEFAULT_FILENAME_TEMPLATE).unwrap_or_else(|_| Self {
+            segments: vec![Segment::Token(Token::Mode)],
+            text: "{mode}".to_string(),
+        })
+    }
+}
+
+impl FilenameTemplate {
+    /// テンプレート文字列を解析
+    ///
+    /// # 戻り値
+    /// 成功時はテンプレート。不明なトークン、閉じていない`{`、
+    /// ファイル名に使えない文字 (`/`, `:`等) を含む場合、または空の場合は`Err(String)`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        if let Some(c) = text.chars().find(|c| FORBIDDEN_CHARS.contains(c)) {
+            return Err(format!(
+                "ファイル名テンプレートに使えない文字が含まれています: {} ({})",
+                c, text
+            ));
+        }
+
+        let mut segments = Vec::new();
+        let mut rest = text;
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                segments.push(Segment::Literal(rest[..start].to_string()));
+            }
+            let after = &rest[start + 1..];
+            let end = after.find('}').ok_or_else(|| {
+                format!("ファイル名テンプレートの{{が閉じられていません: {}", text)
+            })?;
+            let name = &after[..end];
+            let token = Token::from_name(name).ok_or_else(|| {
+                format!(
+                    "ファイル名テンプレートに不明なトークンがあります: {{{}}} (使用可能: {})",
+                    name,
+                    Self::token_names()
+                )
+            })?;
+            segments.push(Segment::Token(token));
+            rest = &after[end + 1..];
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Literal(rest.to_string()));
+        }
+
+        if segments.is_empty() {
+            return Err("ファイル名テンプレートが空です".to_string());
+        }
+        Ok(Self {
+            segments,
+            text: text.to_string(),
+        })
+    }
+
+    /// 使用可能なトークンの一覧 (エラーメッセージ・ヘルプ用)
+    pub fn token_names() -> String {
+        Token::ALL
+            .iter()
+            .map(|(_, name)| format!("{{{}}}", name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// 撮影時の情報からファイル名 (拡張子を除く) を生成
+    pub fn render(&self, context: &FilenameContext, now: DateTime<Local>) -> String {
+        let mut name = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => name.push_str(text),
+                Segment::Token(Token::Date) => name.push_str(&now.format("%Y%m%d").to_string()),
+                Segment::Token(Token::Time) => name.push_str(&now.format("%H%M%S").to_string()),
+                Segment::Token(Token::Millis) => name.push_str(&now.format("%3f").to_string()),
+                Segment::Token(Token::Sequence) => name.push_str(&format!(
+                    "{:0width$}",
+                    context.sequence,
+                    width = SEQUENCE_DIGITS
+                )),
+                Segment::Token(Token::Camera) => name.push_str(&sanitize(context.camera)),
+                Segment::Token(Token::Mode) => name.push_str(context.mode.file_label()),
+                Segment::Token(Token::Resolution) => {
+                    name.push_str(&format!("{}x{}", context.width, context.height))
+                }
+            }
+        }
+        name
+    }
+
+    /// テンプレート文字列
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+}
+
+impl fmt::Display for FilenameTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+/// ファイル名に使えない文字を`_`に置き換え、長すぎる場合は切り詰める
+///
+/// 英数字 (日本語を含む)、`-`、`_` 以外は`_`にし、連続する`_`は1つにまとめる。
+fn sanitize(text: &str) -> String {
+    let mut result = String::new();
+    for c in text.chars().take(MAX_CAMERA_NAME_CHARS) {
+        let c = if c.is_alphanumeric() || c == '-' {
+            c
+        } else {
+            '_'
+        };
+        if c == '_' && result.ends_with('_') {
+            continue;
+        }
+        result.push(c);
+    }
+    let trimmed = result.trim_matches('_');
+    if trimmed.is_empty() {
+        "camera".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// 同名のファイルが無いパスを返す
+///
+/// `{stem}.{extension}`が既にある場合は、`{stem}_1.{extension}`, `{stem}_2.{extension}` ...
+/// と番号を付けて空いている名前を探す (上限まで埋まっている場合は元の名前を返す)。
+pub fn unique_path(dir: &Path, stem: &str, extension: &str) -> PathBuf {
+    let path = dir.join(format!("{}.{}", stem, extension));
+    if !path.exists() {
+        return path;
+    }
+    (1..=MAX_COLLISION_SUFFIX)
+        .map(|suffix| dir.join(format!("{}_{}.{}", stem, suffix, extension)))
+        .find(|candidate| !candidate.exists())
+        .unwrap_or(path)
+}
+
+/// 同名のファイル・フォルダが無い名前を返す (フォルダを作成する場合に使う)
+///
+/// `{stem}`が既にある場合は、`{stem}_1`, `{stem}_2` ... と番号を付けて空いている名前を探す
+/// (上限まで埋まっている場合は元の名前を返す)。
+pub fn unique_stem(dir: &Path, stem: &str) -> String {
+    if !dir.join(stem).exists() {
+        return stem.to_string();
+    }
+    (1..=MAX_COLLISION_SUFFIX)
+        .map(|suffix| format!("{}_{}", stem, suffix))
+        .find(|candidate| !dir.join(candidate).exists())
+        .unwrap_or_else(|| stem.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    // 標準ライブラリのインポート
+    use std::fs;
+
+    // サードパーティクレートのインポート
+    use chrono::TimeZone;
+
+    use super::*;
+
+    /// テスト用の撮影時の情報
+    fn context(camera: &str) -> FilenameContext<'_> {
+        FilenameContext {
+            mode: CaptureMode::Photo,
+            camera,
+            width: 1280,
+            height: 720,
+            sequence: 7,
+        }
+    }
+
+    /// テスト用の撮影時刻 (2025-01-02 03:04:05.006)
+    fn now() -> Result<DateTime<Local>, String> {
+        Local
+            .with_ymd_and_hms(2025, 1, 2, 3, 4, 5)
+            .single()
+            .map(|time| time + chrono::Duration::milliseconds(6))
+            .ok_or_else(|| "時刻を作成できません".to_string())
+    }
+
+    #[test]
+    fn renders_all_tokens() -> Result<(), String> {
+        let template = FilenameTemplate::parse("{mode}_{date}-{time}.{ms}_{seq}_{resolution}")?;
+        assert_eq!(
+            template.render(&context("cam"), now()?),
+            "photo_20250102-030405.006_0007_1280x720"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_unknown_token() {
+        let result = FilenameTemplate::parse("{mode}_{year}");
+        assert!(
+            matches!(&result, Err(error) if error.contains("{year}")),
+            "{:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn rejects_unclosed_brace() {
+        assert!(FilenameTemplate::parse("{mode}_{date").is_err());
+        assert!(FilenameTemplate::parse("photo_{").is_err());
+    }
+
+    #[test]
+    fn rejects_forbidden_chars_and_empty_template() {
+        assert!(FilenameTemplate::parse("photos/{date}").is_err());
+        assert!(FilenameTemplate::parse("{date}:{time}").is_err());
+        assert!(FilenameTemplate::parse("").is_err());
+    }
+
+    #[test]
+    fn rendered_name_has_no_path_separators() -> Result<(), String> {
+        let template = FilenameTemplate::parse("{camera}_{seq}")?;
+        let name = template.render(&context("../dev/video0 \\ usb:1"), now()?);
+        assert!(!name.contains(FORBIDDEN_CHARS), "{}", name);
+        assert!(!name.contains(".."), "{}", name);
+        assert_eq!(name, "dev_video0_usb_1_0007");
+        Ok(())
+    }
+
+    #[test]
+    fn camera_name_is_truncated_or_replaced() -> Result<(), String> {
+        let template = FilenameTemplate::parse("{camera}")?;
+        let long = "a".repeat(MAX_CAMERA_NAME_CHARS * 2);
+        assert_eq!(
+            template.render(&context(&long), now()?).len(),
+            MAX_CAMERA_NAME_CHARS
+        );
+        assert_eq!(template.render(&context("///"), now()?), "camera");
+        Ok(())
+    }
+
+    #[test]
+    fn unique_path_adds_collision_suffix() -> Result<(), String> {
+        let temp = tempfile::tempdir().map_err(|e| e.to_string())?;
+        let dir = temp.path();
+        assert_eq!(unique_path(dir, "photo", "jpg"), dir.join("photo.jpg"));
+
+        fs::write(dir.join("photo.jpg"), b"").map_err(|e| e.to_string())?;
+        assert_eq!(unique_path(dir, "photo", "jpg"), dir.join("photo_1.jpg"));
+
+        fs::write(dir.join("photo_1.jpg"), b"").map_err(|e| e.to_string())?;
+        assert_eq!(unique_path(dir, "photo", "jpg"), dir.join("photo_2.jpg"));
+        // 拡張子が違えば重ならない
+        assert_eq!(unique_path(dir, "photo", "png"), dir.join("photo.png"));
+        Ok(())
+    }
+
+    #[test]
+    fn unique_stem_skips_existing_folders() -> Result<(), String> {
+        let temp = tempfile::tempdir().map_err(|e| e.to_string())?;
+        let dir = temp.path();
+        assert_eq!(unique_stem(dir, "timelapse"), "timelapse");
+
+        fs::create_dir(dir.join("timelapse")).map_err(|e| e.to_string())?;
+        assert_eq!(unique_stem(dir, "timelapse"), "timelapse_1");
+        Ok(())
+    }
+}
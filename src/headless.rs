@@ -0,0 +1,117 @@
+// 標準ライブラリのインポート
+use std::thread;
+use std::time::{Duration, Instant};
+
+// サードパーティクレートのインポート
+use camera_app::session::CameraSession;
+
+use crate::cli::{CaptureArgs, Cli};
+
+/// 正常終了時の終了コード
+const EXIT_SUCCESS: i32 = 0;
+/// 異常終了時の終了コード (スクリプトから失敗を検知できるように)
+const EXIT_FAILURE: i32 = 1;
+/// 連写の完了を確認する間隔
+const BURST_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// 連写の待ち時間の余裕 (撮影間隔×枚数に加える、カメラが止まった場合の打ち切り用)
+const BURST_TIMEOUT_MARGIN: Duration = Duration::from_secs(10);
+
+/// ウィンドウを開かずに写真撮影・連写・録画を行う
+///
+/// GUIと同じカメラセッションを構築し、撮影・録画の処理をそのまま使用する。
+/// 設定ファイルは読み込むが、保存はしない。
+///
+/// # 戻り値
+/// プロセスの終了コード (成功時は0)
+pub fn run(cli: &Cli, args: &CaptureArgs) -> i32 {
+    let (mut session, _) = crate::load_session(cli);
+
+    let result = session.open().map_err(|e| e.to_string()).and_then(|()| {
+        if args.photo {
+            session
+                .capture_photo()
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        } else if let Some(count) = args.burst {
+            burst_for(&mut session, count)
+        } else if let Some(duration) = args.record {
+            record_for(&mut session, duration)
+        } else {
+            Ok(())
+        }
+    });
+
+    // 録画停止時の書き込みエラー等も表示
+    for error in session.take_errors() {
+        log::error!("{}", error);
+    }
+
+    match result {
+        Ok(()) => EXIT_SUCCESS,
+        Err(e) => {
+            log::error!("{}", e);
+            EXIT_FAILURE
+        }
+    }
+}
+
+/// 指定した枚数を連写し、すべて保存し終えるまで待つ
+///
+/// # 戻り値
+/// すべての枚数を保存できた場合は`Ok(())`、それ以外は`Err(String)`
+fn burst_for(session: &mut CameraSession, count: u32) -> Result<(), String> {
+    let mut options = *session.burst_options();
+    options.count = count;
+    session.set_burst_options(options);
+    let options = *session.burst_options();
+
+    session.start_burst().map_err(|e| e.to_string())?;
+    log::info!("{}枚連写します...", options.count);
+
+    let deadline = Instant::now()
+        + Duration::from_millis(u64::from(options.interval_ms)) * options.count
+        + BURST_TIMEOUT_MARGIN;
+    let summary = loop {
+        if let Some(summary) = session.poll_burst() {
+            break Some(summary);
+        }
+        if Instant::now() >= deadline {
+            log::warn!("連写が時間内に終わらなかったため打ち切ります");
+            break session.finish_burst();
+        }
+        thread::sleep(BURST_POLL_INTERVAL);
+    };
+
+    let Some(summary) = summary else {
+        return Err("連写を完了できませんでした".to_string());
+    };
+    for error in &summary.errors {
+        log::error!("{}", error);
+    }
+    if summary.paths.len() == options.count as usize {
+        Ok(())
+    } else {
+        Err(format!(
+            "{}枚中{}枚しか保存できませんでした",
+            options.count,
+            summary.paths.len()
+        ))
+    }
+}
+
+/// 指定した時間だけ録画する
+///
+/// # 戻り値
+/// 1フレーム以上書き込めた場合は`Ok(())`、それ以外は`Err(String)`
+fn record_for(session: &mut CameraSession, duration: Duration) -> Result<(), String> {
+    let path = session.start_recording().map_err(|e| e.to_string())?;
+    log::info!("{:.1}秒間録画します...", duration.as_secs_f64());
+    thread::sleep(duration);
+
+    match session.stop_recording() {
+        Some(summary) if summary.frames_written > 0 => Ok(()),
+        Some(_) => Err(format!("フレームを1枚も書き込めませんでした: {:?}", path)),
+        // 録画スレッドの異常終了はセッションのエラーとして表示される
+        None => Err(format!("録画を完了できませんでした: {:?}", path)),
+    }
+}
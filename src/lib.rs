@@ -0,0 +1,86 @@
+//! Surface Go 4向けカメラアプリケーションのライブラリ
+//!
+//! フレームソース (カメラ・テストパターン・ファイル再生)、キャプチャスレッド、
+//! 録画 (分割録画を含む)、連写・タイムラプス、画像変換、保存、設定ファイルの各APIを提供する。
+//! eGuiのフロントエンド (`camera_app`バイナリ) とヘッドレスモードは、
+//! どちらも[`session::CameraSession`]を通してこれらを使用する。
+
+// サードパーティクレートのインポート
+use serde::{Deserialize, Serialize};
+
+pub mod burst;
+pub mod capture;
+pub mod codec;
+pub mod convert;
+pub mod devices;
+pub mod error;
+pub mod filename;
+pub mod logging;
+pub mod metadata;
+pub mod photo;
+pub mod prerecord;
+pub mod recorder;
+pub mod segment;
+pub mod session;
+pub mod settings;
+pub mod source;
+pub mod storage;
+pub mod timelapse;
+
+/// キャプチャモード: 写真撮影・連写・タイムラプス・動画録画を区別
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureMode {
+    Photo,     // 写真撮影モード
+    Burst,     // 連写モード (指定した枚数・間隔で写真を保存)
+    Timelapse, // タイムラプスモード (数秒〜数分ごとに撮影し、写真・動画を保存)
+    Video,     // 動画録画モード
+}
+
+impl CaptureMode {
+    /// ファイル名に使う名前 (ファイル名テンプレートの`{mode}`)
+    pub fn file_label(self) -> &'static str {
+        match self {
+            CaptureMode::Photo => "photo",
+            CaptureMode::Burst => "burst",
+            CaptureMode::Timelapse => "timelapse",
+            CaptureMode::Video => "video",
+        }
+    }
+}
+
+/// カメラポジション: カメラの役割 (どの物理デバイスを割り当てるかは別途管理)
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CameraPosition {
+    Front,    // フロントカメラ
+    Rear,     // リアカメラ
+    External, // 外付けカメラ (USBカメラ等)
+}
+
+impl CameraPosition {
+    /// UIに表示する順序
+    pub const ALL: [CameraPosition; 3] = [
+        CameraPosition::Rear,
+        CameraPosition::Front,
+        CameraPosition::External,
+    ];
+
+    /// UIに表示するラベル
+    pub fn label(self) -> &'static str {
+        match self {
+            CameraPosition::Front => "🤳 フロント",
+            CameraPosition::Rear => "🔲 リア",
+            CameraPosition::External => "🔌 外部",
+        }
+    }
+
+    /// 役割ごとのデフォルトのカメラインデックス (一般的な配置)
+    pub fn default_index(self) -> i32 {
+        match self {
+            CameraPosition::Rear => 0,
+            CameraPosition::Front => 1,
+            CameraPosition::External => 2,
+        }
+    }
+}
@@ -0,0 +1,299 @@
+// 標準ライブラリのインポート
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+// サードパーティクレートのインポート
+use chrono::Local;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// ログファイルを置くディレクトリ名 (プラットフォームのデータディレクトリ配下)
+const APP_DIR_NAME: &str = "camera_app";
+/// ログファイルを置くサブディレクトリ名
+const LOG_DIR_NAME: &str = "logs";
+/// ログファイル名 (ローテーション後は`camera_app.1.log`のように番号が付く)
+const LOG_FILE_STEM: &str = "camera_app";
+/// ログファイルの拡張子
+const LOG_EXTENSION: &str = "log";
+/// ログファイル1つあたりの最大サイズ (超えたらローテーション)
+const MAX_LOG_FILE_SIZE: u64 = 1024 * 1024;
+/// 保持するログファイルの数 (現在のファイルを含む)
+const MAX_LOG_FILES: usize = 5;
+/// ログレベルを指定する環境変数
+pub const LOG_LEVEL_ENV: &str = "CAMERA_APP_LOG";
+/// ログレベルのデフォルト値
+pub const DEFAULT_LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+/// サイズでローテーションするログファイル
+///
+/// 最大サイズを超えたら`camera_app.log` → `camera_app.1.log` → ... と名前をずらし、
+/// 最も古いファイルを削除する。
+struct RotatingFile {
+    /// ログファイルを置くディレクトリ
+    dir: PathBuf,
+    /// 書き込み中のファイル
+    file: File,
+    /// 書き込み中のファイルのサイズ (バイト)
+    size: u64,
+}
+
+impl RotatingFile {
+    /// ディレクトリを作成し、現在のログファイルを追記モードで開く
+    fn open(dir: &Path) -> std::io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let file = Self::open_current(dir)?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            file,
+            size,
+        })
+    }
+
+    /// 番号に対応するログファイルのパス (0は現在のファイル)
+    fn path_for(dir: &Path, number: usize) -> PathBuf {
+        if number == 0 {
+            dir.join(format!("{}.{}", LOG_FILE_STEM, LOG_EXTENSION))
+        } else {
+            dir.join(format!("{}.{}.{}", LOG_FILE_STEM, number, LOG_EXTENSION))
+        }
+    }
+
+    /// 現在のログファイルを追記モードで開く
+    fn open_current(dir: &Path) -> std::io::Result<File> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(Self::path_for(dir, 0))
+    }
+
+    /// 1行を書き込み、最大サイズを超えたらローテーション
+    fn write_line(&mut self, line: &str) {
+        if self.file.write_all(line.as_bytes()).is_ok() {
+            self.size += line.len() as u64;
+        }
+        if self.size >= MAX_LOG_FILE_SIZE {
+            self.rotate();
+        }
+    }
+
+    /// 古いファイルから順に番号をずらし、新しいファイルを開く
+    ///
+    /// 失敗した場合は現在のファイルに書き込み続ける (ログのために処理を止めない)。
+    fn rotate(&mut self) {
+        let _ = fs::remove_file(Self::path_for(&self.dir, MAX_LOG_FILES - 1));
+        for number in (0..MAX_LOG_FILES - 1).rev() {
+            let from = Self::path_for(&self.dir, number);
+            if from.exists() {
+                let _ = fs::rename(&from, Self::path_for(&self.dir, number + 1));
+            }
+        }
+        if let Ok(file) = Self::open_current(&self.dir) {
+            self.file = file;
+            self.size = 0;
+        }
+    }
+}
+
+/// コンソールとログファイルの両方に出力するロガー
+///
+/// 出力するレベルは`log::set_max_level`で制御する (起動後に設定ファイルの値で変更するため)。
+struct AppLogger {
+    /// ログファイル (作成できなかった場合はNone: コンソールのみ)
+    file: Mutex<Option<RotatingFile>>,
+}
+
+impl Log for AppLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        // コンソール: 警告・エラーは標準エラー出力、それ以外は標準出力
+        match record.level() {
+            Level::Error | Level::Warn => eprintln!("[{}] {}", record.level(), record.args()),
+            Level::Info => println!("{}", record.args()),
+            Level::Debug | Level::Trace => println!("[{}] {}", record.level(), record.args()),
+        }
+
+        // ログファイル: 時刻・レベル・モジュールを付けて記録 (サポートでの調査用)
+        if let Ok(mut file_lock) = self.file.lock() {
+            if let Some(file) = file_lock.as_mut() {
+                file.write_line(&format!(
+                    "{} [{:<5}] {}: {}\n",
+                    Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+                    record.level(),
+                    record.target(),
+                    record.args()
+                ));
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file_lock) = self.file.lock() {
+            if let Some(file) = file_lock.as_mut() {
+                let _ = file.file.flush();
+            }
+        }
+    }
+}
+
+/// プラットフォームのデータディレクトリのログ置き場
+///
+/// Windows: `%LOCALAPPDATA%\camera_app\logs`、
+/// Linux: `~/.local/share/camera_app/logs`、
+/// macOS: `~/Library/Application Support/camera_app/logs`
+pub fn default_log_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join(APP_DIR_NAME).join(LOG_DIR_NAME))
+}
+
+/// ロガーを初期化 (起動時に1回だけ呼ぶ)
+///
+/// ログファイルを作成できない場合はコンソールのみに出力する。
+/// レベルはデフォルト値で開始し、`set_level`で変更する。
+///
+/// # 戻り値
+/// 書き込み中のログファイルのパス (コンソールのみの場合はNone)
+pub fn init(log_dir: Option<&Path>) -> Option<PathBuf> {
+    let file = log_dir.and_then(|dir| match RotatingFile::open(dir) {
+        Ok(file) => Some(file),
+        Err(e) => {
+            eprintln!("ログファイルを作成できませんでした: {:?} ({})", dir, e);
+            None
+        }
+    });
+    let path = file
+        .as_ref()
+        .map(|file| RotatingFile::path_for(&file.dir, 0));
+
+    let logger = AppLogger {
+        file: Mutex::new(file),
+    };
+    if log::set_boxed_logger(Box::new(logger)).is_err() {
+        // 既に初期化済み (ライブラリ利用側が独自のロガーを設定した場合等)
+        return None;
+    }
+    log::set_max_level(DEFAULT_LOG_LEVEL);
+    path
+}
+
+/// 出力するログレベルを変更
+pub fn set_level(level: LevelFilter) {
+    log::set_max_level(level);
+}
+
+/// パニックの内容もログファイルに記録する (元のパニック出力はそのまま行う)
+pub fn log_panics() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        log::error!("パニックが発生しました: {}", info);
+        log::logger().flush();
+        default_hook(info);
+    }));
+}
+
+/// ログレベル名 (`off`, `error`, `warn`, `info`, `debug`, `trace`) を解析
+///
+/// # 戻り値
+/// 成功時はログレベル、不明な名前の場合は`Err(String)`
+pub fn parse_level(text: &str) -> Result<LevelFilter, String> {
+    text.trim()
+        .parse::<LevelFilter>()
+        .map_err(|_| format!("不明なログレベルです: {}", text))
+}
+
+/// 環境変数`CAMERA_APP_LOG`で指定されたログレベル (不正な値は警告して無視)
+pub fn level_from_env() -> Option<LevelFilter> {
+    let text = std::env::var(LOG_LEVEL_ENV).ok()?;
+    match parse_level(&text) {
+        Ok(level) => Some(level),
+        Err(e) => {
+            log::warn!("{}を無視します: {}", LOG_LEVEL_ENV, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ディレクトリ内のファイル名 (名前順)
+    fn file_names(dir: &Path) -> Result<Vec<String>, String> {
+        let mut names = fs::read_dir(dir)
+            .map_err(|e| e.to_string())?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        names.sort();
+        Ok(names)
+    }
+
+    #[test]
+    fn rotates_and_keeps_max_files() -> Result<(), String> {
+        let dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+        let mut file = RotatingFile::open(dir.path()).map_err(|e| e.to_string())?;
+
+        // 4行ごとに最大サイズを超える: ファイルごとに異なる文字で書き、7回ローテーションさせる
+        let lines_per_file = 4;
+        let line_len = MAX_LOG_FILE_SIZE as usize / lines_per_file;
+        let batches = ['a', 'b', 'c', 'd', 'e', 'f', 'g'];
+        for c in batches {
+            for _ in 0..lines_per_file {
+                file.write_line(&format!("{}\n", c.to_string().repeat(line_len)));
+            }
+        }
+
+        assert_eq!(
+            file_names(dir.path())?,
+            [
+                "camera_app.1.log",
+                "camera_app.2.log",
+                "camera_app.3.log",
+                "camera_app.4.log",
+                "camera_app.log",
+            ]
+        );
+        // 最後のローテーション直後のため、現在のファイルは空
+        let current = RotatingFile::path_for(dir.path(), 0);
+        assert_eq!(fs::metadata(&current).map_err(|e| e.to_string())?.len(), 0);
+
+        // 番号が小さいほど新しい (最も古いa・b・cは削除済み)
+        for (number, expected) in [(1, 'g'), (2, 'f'), (3, 'e'), (4, 'd')] {
+            let text = fs::read_to_string(RotatingFile::path_for(dir.path(), number))
+                .map_err(|e| e.to_string())?;
+            assert!(text.starts_with(expected), "{}: {}", number, &text[..1]);
+            assert!(text.len() as u64 >= MAX_LOG_FILE_SIZE);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn reopening_continues_existing_file() -> Result<(), String> {
+        let dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+        RotatingFile::open(dir.path())
+            .map_err(|e| e.to_string())?
+            .write_line("first\n");
+        let mut reopened = RotatingFile::open(dir.path()).map_err(|e| e.to_string())?;
+        assert_eq!(reopened.size, "first\n".len() as u64);
+        reopened.write_line("second\n");
+
+        let text =
+            fs::read_to_string(RotatingFile::path_for(dir.path(), 0)).map_err(|e| e.to_string())?;
+        assert_eq!(text, "first\nsecond\n");
+        Ok(())
+    }
+
+    #[test]
+    fn parses_level_names() {
+        assert_eq!(parse_level(" debug "), Ok(LevelFilter::Debug));
+        assert_eq!(parse_level("OFF"), Ok(LevelFilter::Off));
+        assert!(parse_level("verbose").is_err());
+    }
+}
@@ -0,0 +1,803 @@
+// 標準ライブラリのインポート
+use std::time::Instant;
+
+// サードパーティクレートのインポート
+use chrono::{DateTime, Local};
+
+use crate::error::CameraError;
+
+/// 写真に書き込むソフトウェア名
+pub const SOFTWARE_NAME: &str = concat!("camera_app ", env!("CARGO_PKG_VERSION"));
+/// 向き: 回転・反転なし (フレームはそのままの向きで保存する)
+pub const ORIENTATION_NORMAL: u16 = 1;
+
+/// JPEGのマーカー: 画像の開始 (SOI)
+const JPEG_SOI: [u8; 2] = [0xFF, 0xD8];
+/// JPEGのマーカー: APP0 (JFIF)
+const JPEG_APP0: [u8; 2] = [0xFF, 0xE0];
+/// JPEGのマーカー: APP1 (EXIF・XMP)
+const JPEG_APP1: [u8; 2] = [0xFF, 0xE1];
+/// JPEGのセグメントに格納できる最大バイト数 (長さフィールドの2バイトを除く)
+const MAX_SEGMENT_PAYLOAD: usize = u16::MAX as usize - 2;
+/// EXIFセグメントの識別子
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+/// XMPセグメントの識別子
+const XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+/// TIFFヘッダー (ビッグエンディアン、先頭IFDはオフセット8)
+const TIFF_HEADER: [u8; 8] = [b'M', b'M', 0, 42, 0, 0, 0, 8];
+/// EXIFのバージョン (オフセット時刻のタグは2.31以降)
+const EXIF_VERSION: &[u8] = b"0231";
+/// GPSタグのバージョン
+const GPS_VERSION: [u8; 4] = [2, 3, 0, 0];
+/// GPSの秒を有理数で表す際の分母 (1/1000秒まで)
+const GPS_SECONDS_DENOMINATOR: u32 = 1000;
+/// XMPのGPS座標で分を表す小数点以下の桁数
+const XMP_GPS_MINUTE_DECIMALS: u32 = 6;
+/// 1度あたりの分
+const MINUTES_PER_DEGREE: u64 = 60;
+/// 1分あたりの秒
+const SECONDS_PER_MINUTE: u64 = 60;
+/// 文字コードの識別子: ASCII (UserComment・GPSAreaInformation用)
+const CHARSET_ASCII: &[u8; 8] = b"ASCII\0\0\0";
+/// 文字コードの識別子: UCS-2 (バイト順はTIFFヘッダーに従う)
+const CHARSET_UNICODE: &[u8; 8] = b"UNICODE\0";
+
+/// EXIFのタグ番号
+mod tag {
+    // IFD0
+    pub const MODEL: u16 = 0x0110;
+    pub const ORIENTATION: u16 = 0x0112;
+    pub const SOFTWARE: u16 = 0x0131;
+    pub const DATE_TIME: u16 = 0x0132;
+    pub const EXIF_IFD: u16 = 0x8769;
+    pub const GPS_IFD: u16 = 0x8825;
+    // EXIF IFD
+    pub const EXIF_VERSION: u16 = 0x9000;
+    pub const DATE_TIME_ORIGINAL: u16 = 0x9003;
+    pub const DATE_TIME_DIGITIZED: u16 = 0x9004;
+    pub const OFFSET_TIME: u16 = 0x9010;
+    pub const OFFSET_TIME_ORIGINAL: u16 = 0x9011;
+    pub const OFFSET_TIME_DIGITIZED: u16 = 0x9012;
+    pub const USER_COMMENT: u16 = 0x9286;
+    pub const SUB_SEC_TIME_ORIGINAL: u16 = 0x9291;
+    pub const PIXEL_X_DIMENSION: u16 = 0xA002;
+    pub const PIXEL_Y_DIMENSION: u16 = 0xA003;
+    // GPS IFD
+    pub const GPS_VERSION_ID: u16 = 0x0000;
+    pub const GPS_LATITUDE_REF: u16 = 0x0001;
+    pub const GPS_LATITUDE: u16 = 0x0002;
+    pub const GPS_LONGITUDE_REF: u16 = 0x0003;
+    pub const GPS_LONGITUDE: u16 = 0x0004;
+    pub const GPS_AREA_INFORMATION: u16 = 0x001C;
+}
+
+/// 緯度・経度 (度、北緯・東経が正)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpsPosition {
+    /// 緯度 (-90〜90)
+    pub latitude: f64,
+    /// 経度 (-180〜180)
+    pub longitude: f64,
+}
+
+impl GpsPosition {
+    /// 範囲内の値の場合のみ位置を作成
+    pub fn new(latitude: f64, longitude: f64) -> Option<Self> {
+        let valid = (-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude);
+        valid.then_some(Self {
+            latitude,
+            longitude,
+        })
+    }
+}
+
+/// 写真に埋め込むメタデータ
+#[derive(Debug, Clone)]
+pub struct PhotoMetadata {
+    /// 撮影日時 (タイムゾーン付きで記録する)
+    pub taken_at: DateTime<Local>,
+    /// カメラ (フレームソース) の表示名
+    pub camera: String,
+    /// 画像の幅 (ピクセル)
+    pub width: u32,
+    /// 画像の高さ (ピクセル)
+    pub height: u32,
+    /// 向き (EXIFのOrientationの値)
+    pub orientation: u16,
+    /// 撮影したソフトウェアの名前とバージョン
+    pub software: String,
+    /// 撮影場所の緯度・経度
+    pub gps: Option<GpsPosition>,
+    /// 撮影場所の説明 (例: `本社 3F 検査室`)
+    pub location: Option<String>,
+    /// 利用者のコメント
+    pub user_comment: Option<String>,
+}
+
+/// IFDエントリの値
+enum Value {
+    Byte(Vec<u8>),
+    Ascii(String),
+    Short(u16),
+    Long(u32),
+    Rational(Vec<(u32, u32)>),
+    Undefined(Vec<u8>),
+}
+
+impl Value {
+    /// TIFFの型番号
+    fn type_id(&self) -> u16 {
+        match self {
+            Value::Byte(_) => 1,
+            Value::Ascii(_) => 2,
+            Value::Short(_) => 3,
+            Value::Long(_) => 4,
+            Value::Rational(_) => 5,
+            Value::Undefined(_) => 7,
+        }
+    }
+
+    /// 値の個数 (ASCIIは終端のNULを含むバイト数)
+    fn count(&self) -> u32 {
+        let count = match self {
+            Value::Byte(bytes) | Value::Undefined(bytes) => bytes.len(),
+            Value::Ascii(text) => text.len() + 1,
+            Value::Short(_) | Value::Long(_) => 1,
+            Value::Rational(values) => values.len(),
+        };
+        count as u32
+    }
+
+    /// 値のバイト列 (ビッグエンディアン)
+    fn bytes(&self) -> Vec<u8> {
+        match self {
+            Value::Byte(bytes) | Value::Undefined(bytes) => bytes.clone(),
+            Value::Ascii(text) => {
+                let mut bytes = text.as_bytes().to_vec();
+                bytes.push(0);
+                bytes
+            }
+            Value::Short(value) => value.to_be_bytes().to_vec(),
+            Value::Long(value) => value.to_be_bytes().to_vec(),
+            Value::Rational(values) => values
+                .iter()
+                .flat_map(|(numerator, denominator)| {
+                    let mut bytes = numerator.to_be_bytes().to_vec();
+                    bytes.extend_from_slice(&denominator.to_be_bytes());
+                    bytes
+                })
+                .collect(),
+        }
+    }
+}
+
+/// IFDエントリ (タグと値)
+struct Entry {
+    tag: u16,
+    value: Value,
+}
+
+/// IFDの長さ (エントリ・次のIFDへのオフセット・4バイトを超える値の領域)
+fn ifd_len(entries: &[Entry]) -> usize {
+    let data_len: usize = entries
+        .iter()
+        .map(|entry| entry.value.bytes().len())
+        .filter(|len| *len > 4)
+        .map(|len| len + len % 2)
+        .sum();
+    2 + entries.len() * 12 + 4 + data_len
+}
+
+/// IFDを書き込む (`tiff`の末尾がIFDの開始位置になる)
+///
+/// 4バイトを超える値はエントリの直後にまとめて置き、オフセットで参照する。
+fn write_ifd(tiff: &mut Vec<u8>, mut entries: Vec<Entry>) {
+    // エントリはタグ番号順に並べる必要がある
+    entries.sort_by_key(|entry| entry.tag);
+    let start = tiff.len();
+    let mut data_offset = start + 2 + entries.len() * 12 + 4;
+    let mut data = Vec::new();
+
+    tiff.extend_from_slice(&(entries.len() as u16).to_be_bytes());
+    for entry in &entries {
+        tiff.extend_from_slice(&entry.tag.to_be_bytes());
+        tiff.extend_from_slice(&entry.value.type_id().to_be_bytes());
+        tiff.extend_from_slice(&entry.value.count().to_be_bytes());
+        let mut bytes = entry.value.bytes();
+        if bytes.len() <= 4 {
+            // 4バイト以下の値はエントリに直接格納 (左詰め)
+            bytes.resize(4, 0);
+            tiff.extend_from_slice(&bytes);
+        } else {
+            tiff.extend_from_slice(&(data_offset as u32).to_be_bytes());
+            if bytes.len() % 2 == 1 {
+                bytes.push(0);
+            }
+            data_offset += bytes.len();
+            data.extend_from_slice(&bytes);
+        }
+    }
+    // 次のIFDは無い
+    tiff.extend_from_slice(&0u32.to_be_bytes());
+    tiff.extend_from_slice(&data);
+}
+
+/// 文字コードの識別子付きのテキスト (ASCII以外を含む場合はUCS-2)
+fn encoded_text(text: &str) -> Vec<u8> {
+    if text.is_ascii() {
+        let mut bytes = CHARSET_ASCII.to_vec();
+        bytes.extend_from_slice(text.as_bytes());
+        bytes
+    } else {
+        let mut bytes = CHARSET_UNICODE.to_vec();
+        bytes.extend(text.encode_utf16().flat_map(|unit| unit.to_be_bytes()));
+        bytes
+    }
+}
+
+/// 度を度・分・秒の有理数に変換 (符号は参照方位で表すため絶対値を使う)
+///
+/// 秒を丸めた結果が60秒にならないように、全体を秒の最小単位 (1/1000秒) で丸めてから
+/// 度・分・秒に分ける (繰り上がりは分・度に反映される)。
+fn degrees_to_rationals(degrees: f64) -> Vec<(u32, u32)> {
+    let per_second = u64::from(GPS_SECONDS_DENOMINATOR);
+    let per_minute = per_second * SECONDS_PER_MINUTE;
+    let per_degree = per_minute * MINUTES_PER_DEGREE;
+    let total = (degrees.abs() * per_degree as f64).round() as u64;
+    vec![
+        ((total / per_degree) as u32, 1),
+        ((total % per_degree / per_minute) as u32, 1),
+        ((total % per_minute) as u32, GPS_SECONDS_DENOMINATOR),
+    ]
+}
+
+/// XMPのGPS座標の形式 (`DDD,MM.mmmmmmR`) に変換
+///
+/// 分を丸めた結果が60分にならないように、全体を分の最小桁で丸めてから度と分に分ける。
+fn xmp_coordinate(degrees: f64, positive: char, negative: char) -> String {
+    let direction = if degrees < 0.0 { negative } else { positive };
+    let per_minute = 10_u64.pow(XMP_GPS_MINUTE_DECIMALS);
+    let per_degree = per_minute * MINUTES_PER_DEGREE;
+    let total = (degrees.abs() * per_degree as f64).round() as u64;
+    let minutes = total % per_degree;
+    format!(
+        "{},{}.{:0width$}{}",
+        total / per_degree,
+        minutes / per_minute,
+        minutes % per_minute,
+        direction,
+        width = XMP_GPS_MINUTE_DECIMALS as usize
+    )
+}
+
+/// XMLの特殊文字をエスケープ
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl PhotoMetadata {
+    /// 撮影日時をフレームの取得時刻に置き換える (保存が取得より遅れる場合に使う)
+    pub fn taken_at_instant(mut self, captured_at: Instant) -> Self {
+        let now = Local::now();
+        self.taken_at = chrono::Duration::from_std(captured_at.elapsed())
+            .map(|age| now - age)
+            .unwrap_or(now);
+        self
+    }
+
+    /// EXIFのTIFF構造 (EXIFセグメントの識別子の後ろに置く部分)
+    ///
+    /// 文字列 (ASCII型) のタグには、カメラ名等の日本語をUTF-8のまま書き込む。
+    /// コメントと撮影場所の説明は文字コードを指定できる型で書き込む。
+    fn exif_tiff(&self) -> Vec<u8> {
+        let date_time = self.taken_at.format("%Y:%m:%d %H:%M:%S").to_string();
+        let offset = self.taken_at.format("%:z").to_string();
+
+        let mut exif_entries = vec![
+            Entry {
+                tag: tag::EXIF_VERSION,
+                value: Value::Undefined(EXIF_VERSION.to_vec()),
+            },
+            Entry {
+                tag: tag::DATE_TIME_ORIGINAL,
+                value: Value::Ascii(date_time.clone()),
+            },
+            Entry {
+                tag: tag::DATE_TIME_DIGITIZED,
+                value: Value::Ascii(date_time.clone()),
+            },
+            Entry {
+                tag: tag::OFFSET_TIME,
+                value: Value::Ascii(offset.clone()),
+            },
+            Entry {
+                tag: tag::OFFSET_TIME_ORIGINAL,
+                value: Value::Ascii(offset.clone()),
+            },
+            Entry {
+                tag: tag::OFFSET_TIME_DIGITIZED,
+                value: Value::Ascii(offset),
+            },
+            Entry {
+                tag: tag::SUB_SEC_TIME_ORIGINAL,
+                value: Value::Ascii(self.taken_at.format("%3f").to_string()),
+            },
+            Entry {
+                tag: tag::PIXEL_X_DIMENSION,
+                value: Value::Long(self.width),
+            },
+            Entry {
+                tag: tag::PIXEL_Y_DIMENSION,
+                value: Value::Long(self.height),
+            },
+        ];
+        if let Some(comment) = &self.user_comment {
+            exif_entries.push(Entry {
+                tag: tag::USER_COMMENT,
+                value: Value::Undefined(encoded_text(comment)),
+            });
+        }
+
+        let mut gps_entries = Vec::new();
+        if let Some(gps) = self.gps {
+            let latitude_ref = if gps.latitude < 0.0 { "S" } else { "N" };
+            let longitude_ref = if gps.longitude < 0.0 { "W" } else { "E" };
+            gps_entries.extend([
+                Entry {
+                    tag: tag::GPS_LATITUDE_REF,
+                    value: Value::Ascii(latitude_ref.to_string()),
+                },
+                Entry {
+                    tag: tag::GPS_LATITUDE,
+                    value: Value::Rational(degrees_to_rationals(gps.latitude)),
+                },
+                Entry {
+                    tag: tag::GPS_LONGITUDE_REF,
+                    value: Value::Ascii(longitude_ref.to_string()),
+                },
+                Entry {
+                    tag: tag::GPS_LONGITUDE,
+                    value: Value::Rational(degrees_to_rationals(gps.longitude)),
+                },
+            ]);
+        }
+        if let Some(location) = &self.location {
+            gps_entries.push(Entry {
+                tag: tag::GPS_AREA_INFORMATION,
+                value: Value::Undefined(encoded_text(location)),
+            });
+        }
+        if !gps_entries.is_empty() {
+            gps_entries.push(Entry {
+                tag: tag::GPS_VERSION_ID,
+                value: Value::Byte(GPS_VERSION.to_vec()),
+            });
+        }
+
+        let mut ifd0 = vec![
+            Entry {
+                tag: tag::MODEL,
+                value: Value::Ascii(self.camera.clone()),
+            },
+            Entry {
+                tag: tag::ORIENTATION,
+                value: Value::Short(self.orientation),
+            },
+            Entry {
+                tag: tag::SOFTWARE,
+                value: Value::Ascii(self.software.clone()),
+            },
+            Entry {
+                tag: tag::DATE_TIME,
+                value: Value::Ascii(date_time),
+            },
+            Entry {
+                tag: tag::EXIF_IFD,
+                value: Value::Long(0),
+            },
+        ];
+        if !gps_entries.is_empty() {
+            ifd0.push(Entry {
+                tag: tag::GPS_IFD,
+                value: Value::Long(0),
+            });
+        }
+
+        // IFD0・EXIF IFD・GPS IFDの順に並べ、IFD0のオフセットを実際の位置に置き換える
+        // (オフセットはエントリに直接格納されるため、置き換えてもIFD0の長さは変わらない)
+        let exif_offset = TIFF_HEADER.len() + ifd_len(&ifd0);
+        let gps_offset = exif_offset + ifd_len(&exif_entries);
+        for entry in &mut ifd0 {
+            match entry.tag {
+                tag::EXIF_IFD => entry.value = Value::Long(exif_offset as u32),
+                tag::GPS_IFD => entry.value = Value::Long(gps_offset as u32),
+                _ => {}
+            }
+        }
+
+        let mut tiff = TIFF_HEADER.to_vec();
+        write_ifd(&mut tiff, ifd0);
+        write_ifd(&mut tiff, exif_entries);
+        if !gps_entries.is_empty() {
+            write_ifd(&mut tiff, gps_entries);
+        }
+        tiff
+    }
+
+    /// XMPパケット (EXIFと同じ内容をUnicodeで記録する)
+    fn xmp_packet(&self) -> String {
+        let mut attributes = vec![
+            format!(
+                "xmp:CreateDate=\"{}\"",
+                self.taken_at.format("%Y-%m-%dT%H:%M:%S%.3f%:z")
+            ),
+            format!("xmp:CreatorTool=\"{}\"", escape_xml(&self.software)),
+            format!("tiff:Model=\"{}\"", escape_xml(&self.camera)),
+            format!("tiff:Orientation=\"{}\"", self.orientation),
+            format!("exif:PixelXDimension=\"{}\"", self.width),
+            format!("exif:PixelYDimension=\"{}\"", self.height),
+        ];
+        if let Some(gps) = self.gps {
+            attributes.push(format!(
+                "exif:GPSLatitude=\"{}\"",
+                xmp_coordinate(gps.latitude, 'N', 'S')
+            ));
+            attributes.push(format!(
+                "exif:GPSLongitude=\"{}\"",
+                xmp_coordinate(gps.longitude, 'E', 'W')
+            ));
+        }
+        if let Some(location) = &self.location {
+            attributes.push(format!(
+                "Iptc4xmpCore:Location=\"{}\"",
+                escape_xml(location)
+            ));
+        }
+
+        let description = self
+            .user_comment
+            .as_ref()
+            .map(|comment| {
+                format!(
+                    "\n   <dc:description><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:description>\n  ",
+                    escape_xml(comment)
+                )
+            })
+            .unwrap_or_default();
+
+        format!(
+            "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+             <x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n \
+             <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n  \
+             <rdf:Description rdf:about=\"\"\n    \
+             xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\"\n    \
+             xmlns:tiff=\"http://ns.adobe.com/tiff/1.0/\"\n    \
+             xmlns:exif=\"http://ns.adobe.com/exif/1.0/\"\n    \
+             xmlns:dc=\"http://purl.org/dc/elements/1.1/\"\n    \
+             xmlns:Iptc4xmpCore=\"http://iptc.org/std/Iptc4xmpCore/1.0/xmlns/\"\n    \
+             {}>{}</rdf:Description>\n \
+             </rdf:RDF>\n\
+             </x:xmpmeta>\n\
+             <?xpacket end=\"w\"?>",
+            attributes.join("\n    "),
+            description
+        )
+    }
+
+    /// JPEGのバイト列にEXIF・XMPのセグメントを挿入
+    ///
+    /// JFIFのAPP0セグメントがある場合はその直後 (JFIFの規定でAPP0は先頭に置く)、
+    /// 無い場合はSOIの直後に挿入する。
+    ///
+    /// # 戻り値
+    /// 成功時はメタデータ付きのJPEG、JPEGとして解釈できない場合や
+    /// メタデータが大きすぎる場合は`Err(CameraError)`
+    pub fn embed_in_jpeg(&self, jpeg: &[u8]) -> Result<Vec<u8>, CameraError> {
+        if !jpeg.starts_with(&JPEG_SOI) {
+            return Err(CameraError::Conversion(
+                "メタデータを書き込めません: JPEGではありません".to_string(),
+            ));
+        }
+        let mut insert_at = JPEG_SOI.len();
+        if jpeg[insert_at..].starts_with(&JPEG_APP0) {
+            let length = jpeg
+                .get(insert_at + 2..insert_at + 4)
+                .map(|bytes| usize::from(u16::from_be_bytes([bytes[0], bytes[1]])))
+                .ok_or_else(|| {
+                    CameraError::Conversion(
+                        "メタデータを書き込めません: JPEGが壊れています".to_string(),
+                    )
+                })?;
+            insert_at += 2 + length;
+        }
+        if insert_at > jpeg.len() {
+            return Err(CameraError::Conversion(
+                "メタデータを書き込めません: JPEGが壊れています".to_string(),
+            ));
+        }
+
+        let mut exif = EXIF_HEADER.to_vec();
+        exif.extend_from_slice(&self.exif_tiff());
+        let mut xmp = XMP_HEADER.to_vec();
+        xmp.extend_from_slice(self.xmp_packet().as_bytes());
+
+        let mut output = Vec::with_capacity(jpeg.len() + exif.len() + xmp.len() + 8);
+        output.extend_from_slice(&jpeg[..insert_at]);
+        for payload in [exif, xmp] {
+            if payload.len() > MAX_SEGMENT_PAYLOAD {
+                return Err(CameraError::Conversion(format!(
+                    "メタデータが大きすぎます ({}バイト、上限{}バイト)",
+                    payload.len(),
+                    MAX_SEGMENT_PAYLOAD
+                )));
+            }
+            output.extend_from_slice(&JPEG_APP1);
+            output.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+            output.extend_from_slice(&payload);
+        }
+        output.extend_from_slice(&jpeg[insert_at..]);
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 画像データを持たない最小のJPEG (SOIとEOIのみ)
+    const MINIMAL_JPEG: [u8; 4] = [0xFF, 0xD8, 0xFF, 0xD9];
+    /// IFD0のエントリ数 (Model, Orientation, Software, DateTime, ExifIFD)
+    const IFD0_ENTRIES: u16 = 5;
+    /// EXIF IFDのエントリ数 (コメント無し)
+    const EXIF_IFD_ENTRIES: u16 = 9;
+
+    /// IFDエントリ (タグ・型・個数・値またはオフセット)
+    #[derive(Debug, Clone, Copy)]
+    struct RawEntry {
+        tag: u16,
+        type_id: u16,
+        count: u32,
+        value: u32,
+    }
+
+    /// `offset`から2バイトをビッグエンディアンで読む
+    fn be16(bytes: &[u8], offset: usize) -> u16 {
+        u16::from_be_bytes([bytes[offset], bytes[offset + 1]])
+    }
+
+    /// `offset`から4バイトをビッグエンディアンで読む
+    fn be32(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_be_bytes([
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ])
+    }
+
+    /// TIFF構造の`offset`にあるIFDのエントリを読み取る
+    fn read_ifd(tiff: &[u8], offset: usize) -> Vec<RawEntry> {
+        let count = usize::from(be16(tiff, offset));
+        (0..count)
+            .map(|index| {
+                let entry = offset + 2 + index * 12;
+                RawEntry {
+                    tag: be16(tiff, entry),
+                    type_id: be16(tiff, entry + 2),
+                    count: be32(tiff, entry + 4),
+                    value: be32(tiff, entry + 8),
+                }
+            })
+            .collect()
+    }
+
+    /// タグのエントリを探す
+    fn find(entries: &[RawEntry], tag: u16) -> Option<RawEntry> {
+        entries.iter().copied().find(|entry| entry.tag == tag)
+    }
+
+    /// JPEGの`offset`から始まるAPP1セグメントの中身 (長さフィールドの後ろ)
+    fn app1_payload(jpeg: &[u8], offset: usize) -> &[u8] {
+        assert_eq!(jpeg[offset..offset + 2], JPEG_APP1);
+        let length = usize::from(be16(jpeg, offset + 2));
+        &jpeg[offset + 4..offset + 2 + length]
+    }
+
+    /// GPS・場所・コメントの無いメタデータ
+    fn metadata() -> PhotoMetadata {
+        PhotoMetadata {
+            taken_at: Local::now(),
+            camera: "USB Camera".to_string(),
+            width: 1280,
+            height: 720,
+            orientation: ORIENTATION_NORMAL,
+            software: SOFTWARE_NAME.to_string(),
+            gps: None,
+            location: None,
+            user_comment: None,
+        }
+    }
+
+    #[test]
+    fn writes_exif_and_xmp_segments_after_soi() -> Result<(), CameraError> {
+        let jpeg = metadata().embed_in_jpeg(&MINIMAL_JPEG)?;
+        assert_eq!(jpeg[..2], JPEG_SOI);
+
+        let exif = app1_payload(&jpeg, JPEG_SOI.len());
+        assert!(exif.starts_with(EXIF_HEADER));
+        assert_eq!(exif[EXIF_HEADER.len()..][..TIFF_HEADER.len()], TIFF_HEADER);
+
+        let xmp_at = JPEG_SOI.len() + 2 + usize::from(be16(&jpeg, JPEG_SOI.len() + 2));
+        let xmp = app1_payload(&jpeg, xmp_at);
+        assert!(xmp.starts_with(XMP_HEADER));
+        let packet = String::from_utf8_lossy(&xmp[XMP_HEADER.len()..]);
+        assert!(packet.contains("tiff:Model=\"USB Camera\""), "{}", packet);
+
+        // 元のJPEGの残り (EOI) はそのまま続く
+        assert!(jpeg.ends_with(&MINIMAL_JPEG[2..]));
+        Ok(())
+    }
+
+    #[test]
+    fn inserts_after_jfif_app0() -> Result<(), CameraError> {
+        // APP0 (長さ4: 長さフィールド + 2バイト) の後ろに挿入される
+        let source = [0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0xAA, 0xBB, 0xFF, 0xD9];
+        let jpeg = metadata().embed_in_jpeg(&source)?;
+        assert_eq!(jpeg[..8], source[..8]);
+        assert!(app1_payload(&jpeg, 8).starts_with(EXIF_HEADER));
+        Ok(())
+    }
+
+    #[test]
+    fn ifd_entry_counts_and_offsets() {
+        let metadata = PhotoMetadata {
+            user_comment: Some("検査OK".to_string()),
+            ..metadata()
+        };
+        let tiff = metadata.exif_tiff();
+        let ifd0 = read_ifd(&tiff, TIFF_HEADER.len());
+        assert_eq!(ifd0.len(), usize::from(IFD0_ENTRIES));
+        // エントリはタグ番号順
+        assert!(ifd0.windows(2).all(|pair| pair[0].tag < pair[1].tag));
+        assert!(find(&ifd0, tag::GPS_IFD).is_none());
+
+        // 4バイトを超える値はオフセットで参照し、その位置に値がある
+        let Some(model) = find(&ifd0, tag::MODEL) else {
+            panic!("Modelタグがありません");
+        };
+        assert_eq!(model.type_id, 2);
+        assert_eq!(model.count as usize, "USB Camera".len() + 1);
+        let model_at = model.value as usize;
+        assert_eq!(
+            &tiff[model_at..model_at + model.count as usize],
+            b"USB Camera\0"
+        );
+        // オフセットは偶数 (ワード境界)
+        assert_eq!(model_at % 2, 0);
+
+        // 4バイト以下の値はエントリに左詰めで格納
+        let Some(orientation) = find(&ifd0, tag::ORIENTATION) else {
+            panic!("Orientationタグがありません");
+        };
+        assert_eq!(orientation.value >> 16, u32::from(ORIENTATION_NORMAL));
+
+        // EXIF IFDへのオフセットが指す位置にEXIF IFDがある
+        let Some(exif_ifd) = find(&ifd0, tag::EXIF_IFD) else {
+            panic!("ExifIFDタグがありません");
+        };
+        let exif = read_ifd(&tiff, exif_ifd.value as usize);
+        assert_eq!(exif.len(), usize::from(EXIF_IFD_ENTRIES) + 1);
+        let Some(comment) = find(&exif, tag::USER_COMMENT) else {
+            panic!("UserCommentタグがありません");
+        };
+        let comment_at = comment.value as usize;
+        assert_eq!(tiff[comment_at..comment_at + 8], *CHARSET_UNICODE);
+        assert_eq!(
+            comment.count as usize,
+            8 + "検査OK".encode_utf16().count() * 2
+        );
+        assert!(comment_at + comment.count as usize <= tiff.len());
+    }
+
+    #[test]
+    fn gps_ifd_is_linked_from_ifd0() {
+        let metadata = PhotoMetadata {
+            gps: GpsPosition::new(35.5, -139.25),
+            location: Some("本社".to_string()),
+            ..metadata()
+        };
+        let tiff = metadata.exif_tiff();
+        let ifd0 = read_ifd(&tiff, TIFF_HEADER.len());
+        assert_eq!(ifd0.len(), usize::from(IFD0_ENTRIES) + 1);
+
+        let Some(gps_ifd) = find(&ifd0, tag::GPS_IFD) else {
+            panic!("GPSIFDタグがありません");
+        };
+        let gps = read_ifd(&tiff, gps_ifd.value as usize);
+        // バージョン・緯度 (参照・値)・経度 (参照・値)・場所の説明
+        assert_eq!(gps.len(), 6);
+        let Some(longitude_ref) = find(&gps, tag::GPS_LONGITUDE_REF) else {
+            panic!("GPSLongitudeRefタグがありません");
+        };
+        assert_eq!(longitude_ref.value.to_be_bytes()[..2], *b"W\0");
+
+        let Some(latitude) = find(&gps, tag::GPS_LATITUDE) else {
+            panic!("GPSLatitudeタグがありません");
+        };
+        let at = latitude.value as usize;
+        // 35度30分0秒
+        assert_eq!(
+            (be32(&tiff, at), be32(&tiff, at + 8), be32(&tiff, at + 16)),
+            (35, 30, 0)
+        );
+    }
+
+    #[test]
+    fn gps_seconds_carry_into_minutes_and_degrees() {
+        assert_eq!(
+            degrees_to_rationals(35.681236),
+            vec![(35, 1), (40, 1), (52_450, GPS_SECONDS_DENOMINATOR)]
+        );
+        // 10°59′59.9996″ は 60.000″ ではなく 11°0′0″ になる
+        let boundary = 10.0 + 59.0 / 60.0 + 59.9996 / 3600.0;
+        assert_eq!(
+            degrees_to_rationals(boundary),
+            vec![(11, 1), (0, 1), (0, GPS_SECONDS_DENOMINATOR)]
+        );
+        assert_eq!(
+            degrees_to_rationals(-boundary),
+            degrees_to_rationals(boundary)
+        );
+    }
+
+    #[test]
+    fn xmp_minutes_carry_into_degrees() {
+        assert_eq!(xmp_coordinate(35.5, 'N', 'S'), "35,30.000000N");
+        assert_eq!(xmp_coordinate(-139.25, 'E', 'W'), "139,15.000000W");
+        assert_eq!(xmp_coordinate(10.999_999_999, 'N', 'S'), "11,0.000000N");
+    }
+
+    #[test]
+    fn rejects_segments_over_64_kib() {
+        let oversized = PhotoMetadata {
+            user_comment: Some("a".repeat(MAX_SEGMENT_PAYLOAD)),
+            ..metadata()
+        };
+        assert!(matches!(
+            oversized.embed_in_jpeg(&MINIMAL_JPEG),
+            Err(CameraError::Conversion(_))
+        ));
+
+        // 上限に収まる場合は、長さフィールドがセグメントの長さと一致する
+        let large = PhotoMetadata {
+            user_comment: Some("a".repeat(MAX_SEGMENT_PAYLOAD / 2)),
+            ..metadata()
+        };
+        let Ok(jpeg) = large.embed_in_jpeg(&MINIMAL_JPEG) else {
+            panic!("上限に収まるメタデータを書き込めません");
+        };
+        let exif = app1_payload(&jpeg, JPEG_SOI.len());
+        assert!(exif.len() <= MAX_SEGMENT_PAYLOAD);
+        assert_eq!(usize::from(be16(&jpeg, JPEG_SOI.len() + 2)), exif.len() + 2);
+    }
+
+    #[test]
+    fn rejects_non_jpeg() {
+        assert!(metadata().embed_in_jpeg(b"PNG").is_err());
+        // APP0の長さがデータを超えている
+        assert!(metadata()
+            .embed_in_jpeg(&[0xFF, 0xD8, 0xFF, 0xE0, 0x10, 0x00])
+            .is_err());
+    }
+}
@@ -0,0 +1,397 @@
+// 標準ライブラリのインポート
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+// サードパーティクレートのインポート
+use opencv::{
+    core::{self, Mat, Vector},
+    imgcodecs, imgproc,
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::capture::{IntervalTap, TimedFrame};
+use crate::error::CameraError;
+use crate::metadata::PhotoMetadata;
+
+/// JPEG品質のデフォルト値 (0〜100)
+pub const DEFAULT_JPEG_QUALITY: i32 = 95;
+/// JPEG品質の上限
+pub const MAX_JPEG_QUALITY: i32 = 100;
+/// PNG圧縮レベルのデフォルト値 (0〜9、可逆圧縮のため画質は変わらない)
+pub const DEFAULT_PNG_COMPRESSION: i32 = 3;
+/// PNG圧縮レベルの上限 (大きいほど小さく・遅くなる)
+pub const MAX_PNG_COMPRESSION: i32 = 9;
+/// WebP品質のデフォルト値 (1〜100)
+pub const DEFAULT_WEBP_QUALITY: i32 = 90;
+/// WebP品質の下限 (OpenCVでは100を超えると可逆圧縮になるため上限は100)
+pub const MIN_WEBP_QUALITY: i32 = 1;
+/// WebP品質の上限
+pub const MAX_WEBP_QUALITY: i32 = 100;
+/// ベストショット選択で比べるフレーム数の上限 (撮影ボタンを押してから保存までの待ち時間を抑える)
+pub const MAX_BEST_OF_FRAMES: u32 = 10;
+/// ベストショットの候補として次のフレームを待つ最大時間
+const PHOTO_FRAME_TIMEOUT: Duration = Duration::from_secs(2);
+/// 8bitの値を16bitに広げる倍率 (255 × 257 = 65535)
+const EIGHT_TO_SIXTEEN_BIT: f64 = 257.0;
+
+/// 写真の保存形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PhotoFormat {
+    Jpeg, // JPEG (非可逆、ファイルが小さい)
+    Png,  // PNG (可逆)
+    Webp, // WebP (非可逆、JPEGより小さい)
+    Tiff, // 16bit TIFF (可逆、現像・解析用)
+}
+
+impl PhotoFormat {
+    /// UIに表示する順序
+    pub const ALL: [PhotoFormat; 4] = [
+        PhotoFormat::Jpeg,
+        PhotoFormat::Png,
+        PhotoFormat::Webp,
+        PhotoFormat::Tiff,
+    ];
+
+    /// ファイルの拡張子
+    pub fn extension(self) -> &'static str {
+        match self {
+            PhotoFormat::Jpeg => "jpg",
+            PhotoFormat::Png => "png",
+            PhotoFormat::Webp => "webp",
+            PhotoFormat::Tiff => "tiff",
+        }
+    }
+
+    /// UIに表示するラベル
+    pub fn label(self) -> &'static str {
+        match self {
+            PhotoFormat::Jpeg => "JPEG",
+            PhotoFormat::Png => "PNG",
+            PhotoFormat::Webp => "WebP",
+            PhotoFormat::Tiff => "TIFF (16bit)",
+        }
+    }
+}
+
+/// JPEGの色差サブサンプリング
+///
+/// 4:4:4は色の情報を間引かない (文字や細い線がにじまない)。
+/// 4:2:0は色の情報を縦横半分にする (ファイルが小さい、一般的なJPEG)。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChromaSubsampling {
+    #[serde(rename = "444")]
+    S444,
+    #[serde(rename = "422")]
+    S422,
+    #[serde(rename = "420")]
+    S420,
+}
+
+impl ChromaSubsampling {
+    /// UIに表示する順序
+    pub const ALL: [ChromaSubsampling; 3] = [
+        ChromaSubsampling::S444,
+        ChromaSubsampling::S422,
+        ChromaSubsampling::S420,
+    ];
+
+    /// UIに表示するラベル
+    pub fn label(self) -> &'static str {
+        match self {
+            ChromaSubsampling::S444 => "4:4:4",
+            ChromaSubsampling::S422 => "4:2:2",
+            ChromaSubsampling::S420 => "4:2:0",
+        }
+    }
+
+    /// OpenCVのパラメータ値
+    fn factor(self) -> i32 {
+        match self {
+            ChromaSubsampling::S444 => imgcodecs::IMWRITE_JPEG_SAMPLING_FACTOR_444,
+            ChromaSubsampling::S422 => imgcodecs::IMWRITE_JPEG_SAMPLING_FACTOR_422,
+            ChromaSubsampling::S420 => imgcodecs::IMWRITE_JPEG_SAMPLING_FACTOR_420,
+        }
+    }
+}
+
+/// 写真の保存形式と、形式ごとの画質設定
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PhotoOptions {
+    /// 保存形式
+    #[serde(rename = "photo_format")]
+    pub format: PhotoFormat,
+    /// JPEG品質 (0〜100)
+    pub jpeg_quality: i32,
+    /// プログレッシブJPEGにするかどうか (読み込み途中から粗く表示できる)
+    pub jpeg_progressive: bool,
+    /// JPEGの色差サブサンプリング
+    pub jpeg_subsampling: ChromaSubsampling,
+    /// PNG圧縮レベル (0〜9)
+    pub png_compression: i32,
+    /// WebP品質 (1〜100)
+    pub webp_quality: i32,
+    /// 表示中のフレームを含めて何枚のフレームから最もシャープなものを選ぶか (1は表示中のフレームのみ)
+    pub best_of: u32,
+}
+
+impl Default for PhotoOptions {
+    fn default() -> Self {
+        Self {
+            format: PhotoFormat::Jpeg,
+            jpeg_quality: DEFAULT_JPEG_QUALITY,
+            jpeg_progressive: false,
+            jpeg_subsampling: ChromaSubsampling::S420,
+            png_compression: DEFAULT_PNG_COMPRESSION,
+            webp_quality: DEFAULT_WEBP_QUALITY,
+            best_of: 1,
+        }
+    }
+}
+
+impl PhotoOptions {
+    /// 範囲外の値を上下限に収める
+    pub fn clamp(&mut self) {
+        self.jpeg_quality = self.jpeg_quality.clamp(0, MAX_JPEG_QUALITY);
+        self.png_compression = self.png_compression.clamp(0, MAX_PNG_COMPRESSION);
+        self.webp_quality = self.webp_quality.clamp(MIN_WEBP_QUALITY, MAX_WEBP_QUALITY);
+        self.best_of = self.best_of.clamp(1, MAX_BEST_OF_FRAMES);
+    }
+
+    /// 保存形式に対応するOpenCVのエンコードパラメータ (キーと値の組を並べたもの)
+    fn encode_params(&self) -> Vector<i32> {
+        let params = match self.format {
+            PhotoFormat::Jpeg => vec![
+                imgcodecs::IMWRITE_JPEG_QUALITY,
+                self.jpeg_quality,
+                imgcodecs::IMWRITE_JPEG_PROGRESSIVE,
+                i32::from(self.jpeg_progressive),
+                imgcodecs::IMWRITE_JPEG_SAMPLING_FACTOR,
+                self.jpeg_subsampling.factor(),
+            ],
+            PhotoFormat::Png => vec![imgcodecs::IMWRITE_PNG_COMPRESSION, self.png_compression],
+            PhotoFormat::Webp => vec![imgcodecs::IMWRITE_WEBP_QUALITY, self.webp_quality],
+            // TIFFは可逆圧縮 (OpenCVのデフォルト: LZW) のため画質の設定は無い
+            PhotoFormat::Tiff => Vec::new(),
+        };
+        Vector::from_slice(&params)
+    }
+}
+
+/// フレームを指定した形式で保存
+///
+/// OpenCVのimencode関数でメモリ上にエンコードしてから書き込む。
+/// imwriteは書き込みの失敗理由を返さないため、容量不足等を区別できるようにしている。
+/// 既存のファイルは上書きしない (同名のファイルがある場合は`Err(CameraError::Io)`)。
+/// メタデータ (EXIF・XMP) はJPEGの場合のみ書き込む。
+///
+/// # 引数
+/// * `path` - 保存先のファイルパス (拡張子は`options.format`に合わせること)
+/// * `frame` - 保存するフレーム (BGR形式、8bit)
+/// * `options` - 保存形式と画質設定
+/// * `metadata` - 埋め込むメタデータ (Noneの場合は書き込まない)
+///
+/// # 戻り値
+/// 成功時は`Ok(())`、エンコード・書き込みに失敗した場合は`Err(CameraError)`
+pub fn save_photo(
+    path: &Path,
+    frame: &Mat,
+    options: &PhotoOptions,
+    metadata: Option<&PhotoMetadata>,
+) -> Result<(), CameraError> {
+    let conversion = |e: opencv::Error| CameraError::Conversion(e.to_string());
+
+    // 16bit TIFFは値を0〜65535に広げてから保存
+    let widened;
+    let image = if options.format == PhotoFormat::Tiff && frame.depth() == core::CV_8U {
+        let mut converted = Mat::default();
+        frame
+            .convert_to(&mut converted, core::CV_16U, EIGHT_TO_SIXTEEN_BIT, 0.0)
+            .map_err(conversion)?;
+        widened = converted;
+        &widened
+    } else {
+        frame
+    };
+
+    let extension = format!(".{}", options.format.extension());
+    let mut buffer = Vector::<u8>::new();
+    match imgcodecs::imencode(&extension, image, &mut buffer, &options.encode_params()) {
+        Ok(true) => {}
+        Ok(false) => {
+            return Err(CameraError::Conversion(format!(
+                "{}に変換できません",
+                options.format.label()
+            )))
+        }
+        Err(e) => return Err(conversion(e)),
+    }
+
+    // JPEGにはEXIF・XMPのセグメントを挿入
+    let embedded;
+    let bytes = match metadata {
+        Some(metadata) if options.format == PhotoFormat::Jpeg => {
+            embedded = metadata.embed_in_jpeg(buffer.as_slice())?;
+            embedded.as_slice()
+        }
+        _ => buffer.as_slice(),
+    };
+
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(bytes))
+        .map_err(|e| CameraError::io(path, e))
+}
+
+/// フレームのシャープさ (ラプラシアンの分散)
+///
+/// エッジが鮮明なほど大きくなる。手ぶれ・ピンぼけしたフレームは小さくなるため、
+/// 同じ被写体を写した連続フレームの比較に使う (異なる被写体の間では比較できない)。
+///
+/// # 戻り値
+/// 成功時はシャープさ、変換に失敗した場合は`Err(CameraError::Conversion)`
+pub fn sharpness(frame: &Mat) -> Result<f64, CameraError> {
+    let conversion = |e: opencv::Error| CameraError::Conversion(e.to_string());
+
+    let mut gray = Mat::default();
+    imgproc::cvt_color(frame, &mut gray, imgproc::COLOR_BGR2GRAY, 0).map_err(conversion)?;
+    let mut laplacian = Mat::default();
+    imgproc::laplacian(
+        &gray,
+        &mut laplacian,
+        core::CV_64F,
+        1,
+        1.0,
+        0.0,
+        core::BORDER_DEFAULT,
+    )
+    .map_err(conversion)?;
+
+    let mut mean = Vector::<f64>::new();
+    let mut stddev = Vector::<f64>::new();
+    core::mean_std_dev(&laplacian, &mut mean, &mut stddev, &core::no_array())
+        .map_err(conversion)?;
+    let stddev = stddev.get(0).map_err(conversion)?;
+    Ok(stddev * stddev)
+}
+
+/// 写真の保存セッション: 専用スレッドでベストショットを選んで保存する
+///
+/// 候補のフレームを待つ間も、シャープさの計算・エンコード・書き込みの間も
+/// UIスレッドを止めない。
+pub struct PhotoSession {
+    /// 保存スレッドのハンドル (終了時に保存したファイルのパスを返す)
+    handle: Option<JoinHandle<Result<PathBuf, CameraError>>>,
+}
+
+impl PhotoSession {
+    /// 保存スレッドを起動
+    ///
+    /// # 引数
+    /// * `path` - 保存先のパス (既存のファイルと重ならないこと)
+    /// * `displayed` - 表示中のフレーム (まだ無い場合はNone、次に取得したフレームを使う)
+    /// * `options` - 保存形式・画質と、比べるフレーム数
+    /// * `metadata` - 埋め込むメタデータ (撮影日時は選んだフレームの取得時刻で置き換える)
+    ///
+    /// # 戻り値
+    /// 写真の保存セッションと、キャプチャスレッドに渡すフレームの選択器 (続くフレームを候補として送る)
+    pub fn start(
+        path: PathBuf,
+        displayed: Option<TimedFrame>,
+        options: PhotoOptions,
+        metadata: Option<PhotoMetadata>,
+    ) -> (Self, IntervalTap) {
+        let best_of = options.best_of.max(1);
+        let needed = best_of - u32::from(displayed.is_some());
+        let (sender, receiver) = mpsc::sync_channel(best_of as usize);
+
+        let handle = thread::spawn(move || {
+            let timed = select_photo_frame(displayed, receiver, best_of)?;
+            // 撮影日時は保存した時刻ではなくフレームを取得した時刻にする
+            let metadata = metadata.map(|metadata| metadata.taken_at_instant(timed.captured_at));
+            save_photo(&path, &timed.frame, &options, metadata.as_ref())?;
+            log::info!("写真を保存しました: {:?}", path);
+            Ok(path)
+        });
+
+        (
+            Self {
+                handle: Some(handle),
+            },
+            IntervalTap::new(sender, Duration::ZERO, needed),
+        )
+    }
+
+    /// 保存し終えたかどうか
+    pub fn is_finished(&self) -> bool {
+        self.handle
+            .as_ref()
+            .is_none_or(|handle| handle.is_finished())
+    }
+
+    /// 保存スレッドの終了を待ち、保存したファイルのパスを返す
+    ///
+    /// # 戻り値
+    /// 保存スレッドの結果 (保存スレッドが異常終了した場合はNone)
+    pub fn finish(mut self) -> Option<Result<PathBuf, CameraError>> {
+        self.handle.take().and_then(|handle| handle.join().ok())
+    }
+}
+
+/// 写真として保存するフレームを選ぶ
+///
+/// 表示中のフレームと、その後に取得したフレームを合わせて`best_of`枚比べ、
+/// 最もシャープなものを返す (`best_of`が1の場合は表示中のフレーム)。
+/// 途中でフレームが届かなくなった場合は、それまでのフレームから選ぶ。
+/// 選び終えた時点で`receiver`を破棄し、キャプチャスレッドからの供給を止める。
+fn select_photo_frame(
+    displayed: Option<TimedFrame>,
+    receiver: Receiver<TimedFrame>,
+    best_of: u32,
+) -> Result<TimedFrame, CameraError> {
+    let mut candidates: Vec<TimedFrame> = displayed.into_iter().collect();
+    while candidates.len() < best_of as usize {
+        match receiver.recv_timeout(PHOTO_FRAME_TIMEOUT) {
+            Ok(timed) => candidates.push(timed),
+            Err(_) => break,
+        }
+    }
+    drop(receiver);
+    if candidates.len() <= 1 {
+        return candidates.pop().ok_or(CameraError::FrameTimeout);
+    }
+
+    // シャープさを計算できなかったフレームは候補から外す
+    let total = candidates.len();
+    let mut best: Option<(usize, f64)> = None;
+    for (index, timed) in candidates.iter().enumerate() {
+        match sharpness(&timed.frame) {
+            Ok(score) if best.is_none_or(|(_, best_score)| score > best_score) => {
+                best = Some((index, score));
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("シャープさを計算できませんでした: {}", e),
+        }
+    }
+    let index = match best {
+        Some((index, score)) => {
+            log::debug!(
+                "ベストショット: {}枚中{}枚目を選択 (シャープさ: {:.1})",
+                total,
+                index + 1,
+                score
+            );
+            index
+        }
+        None => 0,
+    };
+    Ok(candidates.swap_remove(index))
+}
@@ -0,0 +1,253 @@
+// 標準ライブラリのインポート
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+// サードパーティクレートのインポート
+use opencv::{
+    core::{Mat, Vector},
+    imgcodecs,
+    prelude::*,
+};
+
+use crate::capture::TimedFrame;
+
+/// 録画開始前に保持する時間の上限 (秒)
+pub const MAX_PRE_RECORD_SECS: u32 = 30;
+/// 保持するフレームのJPEG品質 (録画時に再度圧縮されるため高めにする)
+const PRE_RECORD_JPEG_QUALITY: i32 = 90;
+/// 圧縮待ちのキュー長 (満杯の場合はそのフレームを保持しない)
+const PRE_RECORD_QUEUE_CAPACITY: usize = 4;
+
+/// 録画開始前のフレーム (JPEGに圧縮して保持)
+pub struct EncodedFrame {
+    /// JPEGのデータ
+    data: Vec<u8>,
+    /// ソースから取得した時刻
+    pub captured_at: Instant,
+    /// キャプチャ開始からの連番
+    pub sequence: u64,
+}
+
+impl EncodedFrame {
+    /// 圧縮したフレームを復元
+    ///
+    /// # 戻り値
+    /// 復元できない場合はNone
+    pub fn decode(&self) -> Option<Mat> {
+        let buffer = Vector::<u8>::from_slice(&self.data);
+        imgcodecs::imdecode(&buffer, imgcodecs::IMREAD_COLOR)
+            .ok()
+            .filter(|frame| !frame.empty())
+    }
+}
+
+/// 録画開始前のフレームの受け渡し
+pub(crate) enum PreRecordMessage {
+    /// 取得したフレーム (圧縮して保持する)
+    Frame(TimedFrame),
+    /// 保持しているフレームをすべて取り出す (古い順)
+    Drain(Sender<Vec<EncodedFrame>>),
+}
+
+/// 最新のフレームから一定時間以内のフレームを古い順に保持する
+struct FrameWindow {
+    /// 保持する時間
+    duration: Duration,
+    /// 保持しているフレーム (古い順)
+    frames: VecDeque<EncodedFrame>,
+}
+
+impl FrameWindow {
+    /// 指定した時間分のフレームを保持する
+    fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            frames: VecDeque::new(),
+        }
+    }
+
+    /// フレームを追加し、追加したフレームから`duration`より古いものを破棄
+    fn push(&mut self, frame: EncodedFrame) {
+        let newest = frame.captured_at;
+        self.frames.push_back(frame);
+        while self
+            .frames
+            .front()
+            .is_some_and(|oldest| newest.duration_since(oldest.captured_at) > self.duration)
+        {
+            self.frames.pop_front();
+        }
+    }
+
+    /// 保持しているフレームをすべて古い順に取り出す
+    fn take_all(&mut self) -> Vec<EncodedFrame> {
+        self.frames.drain(..).collect()
+    }
+}
+
+/// 直近の一定時間のフレームを圧縮して保持するリングバッファ
+///
+/// 専用スレッドでJPEGに圧縮するため、キャプチャスレッドは圧縮を待たない。
+/// 録画を開始すると保持していたフレームを録画スレッドに渡し、
+/// ボタンを押す前の数秒間も録画ファイルの先頭に含める。
+pub struct PreRecordBuffer {
+    /// 保持する時間
+    duration: Duration,
+    /// 圧縮スレッドへの送信口
+    sender: Option<SyncSender<PreRecordMessage>>,
+    /// 圧縮スレッドのハンドル
+    handle: Option<JoinHandle<()>>,
+}
+
+impl PreRecordBuffer {
+    /// 指定した時間分のフレームを保持する圧縮スレッドを起動
+    pub fn spawn(duration: Duration) -> Self {
+        let (sender, receiver) = mpsc::sync_channel(PRE_RECORD_QUEUE_CAPACITY);
+        let handle = thread::spawn(move || pre_record_loop(receiver, duration));
+        Self {
+            duration,
+            sender: Some(sender),
+            handle: Some(handle),
+        }
+    }
+
+    /// 保持する時間
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// 取得したフレームを渡す (圧縮が追いついていない場合は保持しない)
+    ///
+    /// # 戻り値
+    /// 圧縮スレッドが終了している場合はfalse
+    pub(crate) fn offer(&self, timed: &TimedFrame) -> bool {
+        let Some(sender) = self.sender.as_ref() else {
+            return false;
+        };
+        let Ok(copy) = timed.try_clone() else {
+            return true;
+        };
+        !matches!(
+            sender.try_send(PreRecordMessage::Frame(copy)),
+            Err(mpsc::TrySendError::Disconnected(_))
+        )
+    }
+
+    /// 保持しているフレームをすべて取り出すよう要求
+    ///
+    /// 先に渡したフレームの圧縮が終わってから、古い順に`reply`へ送られる。
+    pub(crate) fn drain(&self, reply: Sender<Vec<EncodedFrame>>) {
+        if let Some(sender) = self.sender.as_ref() {
+            let _ = sender.send(PreRecordMessage::Drain(reply));
+        }
+    }
+}
+
+impl Drop for PreRecordBuffer {
+    fn drop(&mut self) {
+        // 送信口を破棄すると圧縮スレッドは終了する
+        self.sender = None;
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// 圧縮スレッドの本体
+///
+/// フレームをJPEGに圧縮して保持し、最新のフレームから`duration`より古いものを破棄する。
+fn pre_record_loop(receiver: Receiver<PreRecordMessage>, duration: Duration) {
+    let params = Vector::from_slice(&[imgcodecs::IMWRITE_JPEG_QUALITY, PRE_RECORD_JPEG_QUALITY]);
+    let mut window = FrameWindow::new(duration);
+
+    for message in receiver {
+        match message {
+            PreRecordMessage::Frame(timed) => {
+                let mut buffer = Vector::<u8>::new();
+                if !matches!(
+                    imgcodecs::imencode(".jpg", &timed.frame, &mut buffer, &params),
+                    Ok(true)
+                ) {
+                    continue;
+                }
+                window.push(EncodedFrame {
+                    data: buffer.to_vec(),
+                    captured_at: timed.captured_at,
+                    sequence: timed.sequence,
+                });
+            }
+            PreRecordMessage::Drain(reply) => {
+                let _ = reply.send(window.take_all());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 基準時刻から`millis`ミリ秒後に取得した (中身の無い) フレーム
+    fn frame(start: Instant, millis: u64, sequence: u64) -> EncodedFrame {
+        EncodedFrame {
+            data: Vec::new(),
+            captured_at: start + Duration::from_millis(millis),
+            sequence,
+        }
+    }
+
+    /// 保持しているフレームの連番 (古い順)
+    fn sequences(window: &FrameWindow) -> Vec<u64> {
+        window.frames.iter().map(|frame| frame.sequence).collect()
+    }
+
+    #[test]
+    fn keeps_frames_within_duration_of_newest() {
+        let start = Instant::now();
+        let mut window = FrameWindow::new(Duration::from_secs(1));
+        for (sequence, millis) in [(1, 0), (2, 400), (3, 800), (4, 1_000)] {
+            window.push(frame(start, millis, sequence));
+        }
+        // ちょうど1秒前のフレームは残す
+        assert_eq!(sequences(&window), [1, 2, 3, 4]);
+
+        window.push(frame(start, 1_500, 5));
+        assert_eq!(sequences(&window), [3, 4, 5]);
+
+        // 長く途切れた後は、それまでのフレームをすべて破棄する
+        window.push(frame(start, 10_000, 6));
+        assert_eq!(sequences(&window), [6]);
+    }
+
+    #[test]
+    fn zero_duration_keeps_only_latest() {
+        let start = Instant::now();
+        let mut window = FrameWindow::new(Duration::ZERO);
+        window.push(frame(start, 0, 1));
+        window.push(frame(start, 33, 2));
+        assert_eq!(sequences(&window), [2]);
+    }
+
+    #[test]
+    fn take_all_returns_oldest_first_and_empties() {
+        let start = Instant::now();
+        let mut window = FrameWindow::new(Duration::from_secs(5));
+        for sequence in 1..=4 {
+            window.push(frame(start, sequence * 100, sequence));
+        }
+
+        let taken = window.take_all();
+        let order: Vec<u64> = taken.iter().map(|frame| frame.sequence).collect();
+        assert_eq!(order, [1, 2, 3, 4]);
+        assert!(taken
+            .windows(2)
+            .all(|pair| pair[0].captured_at <= pair[1].captured_at));
+        assert!(window.take_all().is_empty());
+
+        // 取り出した後も新しいフレームを保持し続ける
+        window.push(frame(start, 1_000, 5));
+        assert_eq!(sequences(&window), [5]);
+    }
+}
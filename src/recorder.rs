@@ -0,0 +1,668 @@
+// 標準ライブラリのインポート
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+// サードパーティクレートのインポート
+use opencv::{
+    core::{Mat, Size},
+    prelude::*,
+    videoio::VideoWriter,
+};
+
+use crate::capture::TimedFrame;
+use crate::error::CameraError;
+use crate::prerecord::EncodedFrame;
+use crate::segment::{self, SegmentEntry, SegmentOptions, SegmentPlan};
+use crate::source::FourCc;
+
+/// 録画スレッドのキュー長 (書き込みが一時的に遅れた場合のバッファ)
+const RECORDER_QUEUE_CAPACITY: usize = 32;
+/// 録画開始前のフレームの復元・書き込みが実時間の何倍以上の速さで進むとみなすか
+///
+/// 復元している間に届いたフレームはキューで待たせるため、その分だけキューを長くする。
+const PRE_RECORD_CATCH_UP_RATIO: f64 = 4.0;
+/// 分割録画でファイルサイズを確認する間隔 (フレーム数)
+const SEGMENT_SIZE_CHECK_FRAMES: u64 = 30;
+/// 空いたスロットを直前のフレームの複製で埋める上限
+///
+/// これより長くフレームが途切れた場合 (スリープからの復帰等) は、この分だけ埋めて以降の位置を詰める。
+const MAX_FILL_DURATION: Duration = Duration::from_secs(1);
+
+/// 録画終了時の集計結果
+#[derive(Debug, Default)]
+pub struct RecordingSummary {
+    /// ファイルに書き込んだフレーム数 (複製分・録画開始前の分を含む)
+    pub frames_written: u64,
+    /// 録画開始前のバッファから書き込んだフレーム数
+    pub frames_prerolled: u64,
+    /// フレームの間隔が空いたため、直前のフレームを複製して埋めた数
+    pub frames_duplicated: u64,
+    /// 同じ時刻枠に複数届いたため破棄したフレーム数
+    pub frames_dropped: u64,
+    /// 録画キューが満杯で録画スレッドに届かなかったフレーム数
+    pub frames_missed: u64,
+    /// VideoWriterへの書き込みに失敗したフレーム数
+    pub frames_failed: u64,
+    /// 書き込んだファイル (録画順、分割しない場合は1つ)
+    pub segments: Vec<PathBuf>,
+    /// 次のファイルを開けず、分割をやめた場合のエラー (以降は同じファイルに書き続ける)
+    pub split_error: Option<CameraError>,
+}
+
+/// フレームの取得時刻から、動画内の書き込み位置 (スロット) を決めるペーサー
+///
+/// OpenCVのVideoWriterは固定フレームレートでしか書き込めないため、
+/// 録画開始からの経過時間 × 宣言FPS で各フレームの位置を求め、
+/// 空いたスロットは複製で埋め、重なったフレームは破棄する。
+/// これにより、再生時間が実際の経過時間と一致する。
+/// ただし`MAX_FILL_DURATION`より長く途切れた場合は、基準を取り直して間隔を詰める。
+struct TimestampPacer {
+    /// 動画に宣言したFPS
+    fps: f64,
+    /// 基準のフレームの取得時刻 (最初のフレーム、または基準を取り直したフレーム)
+    started_at: Option<Instant>,
+    /// 基準のフレームのスロット番号
+    base_slot: u64,
+}
+
+impl TimestampPacer {
+    /// 指定したFPSのペーサーを作成
+    fn new(fps: f64) -> Self {
+        Self {
+            fps,
+            started_at: None,
+            base_slot: 0,
+        }
+    }
+
+    /// 取得時刻に対応するスロット番号
+    fn slot_for(&mut self, captured_at: Instant) -> u64 {
+        let started_at = *self.started_at.get_or_insert(captured_at);
+        self.base_slot
+            + (captured_at.duration_since(started_at).as_secs_f64() * self.fps).round() as u64
+    }
+
+    /// 指定した時間に含まれるスロット数
+    fn slots_in(&self, duration: Duration) -> u64 {
+        (duration.as_secs_f64() * self.fps).round() as u64
+    }
+
+    /// 取得時刻`captured_at`のフレームを`slot`に置くように基準を取り直す
+    fn reanchor(&mut self, captured_at: Instant, slot: u64) {
+        self.started_at = Some(captured_at);
+        self.base_slot = slot;
+    }
+
+    /// `written`スロットまで書き込み済みのとき、取得時刻`captured_at`のフレームを置くスロット
+    ///
+    /// `MAX_FILL_DURATION`より長く空いた場合は、その分だけ空けた位置に置き、
+    /// このフレームから基準を取り直す。
+    ///
+    /// # 戻り値
+    /// 置くスロット (`written`との差が複製で埋める数)、書き込み済みのスロットの場合はNone
+    fn place(&mut self, captured_at: Instant, written: u64) -> Option<u64> {
+        let slot = self.slot_for(captured_at);
+        if slot < written {
+            return None;
+        }
+
+        // 長く途切れた場合は上限まで埋めて、このフレームから基準を取り直す
+        let max_fill = self.slots_in(MAX_FILL_DURATION);
+        if slot - written <= max_fill {
+            return Some(slot);
+        }
+        log::warn!(
+            "フレームが{}スロット分途切れたため、{}スロット分だけ埋めて録画を続けます",
+            slot - written,
+            max_fill
+        );
+        let slot = written + max_fill;
+        self.reanchor(captured_at, slot);
+        Some(slot)
+    }
+}
+
+/// 録画セッション: VideoWriterを所有し、専用スレッドでフレームを書き込む
+///
+/// キャプチャスレッドからチャネル経由でフレームを受け取るため、
+/// 書き込みの遅延がプレビューやキャプチャを止めることはない。
+pub struct RecordingSession {
+    /// 録画スレッドのハンドル (終了時に集計結果を返す)
+    handle: Option<JoinHandle<RecordingSummary>>,
+    /// 出力ファイルのパス (分割録画の場合は最初のファイル)
+    path: PathBuf,
+    /// 実際に開けたコーデック (指定したコーデックで開けずMJPGで開いた場合はMJPG)
+    codec: FourCc,
+    /// 書き込み中のファイルが何番目か (1始まり、進捗表示用)
+    segment_index: Arc<AtomicU32>,
+}
+
+impl RecordingSession {
+    /// VideoWriterを開いて録画スレッドを起動
+    ///
+    /// 指定されたコーデック (デフォルトはmp4v) を試み、開けない場合はMJPGにフォールバック。
+    /// フレームは取得時刻に基づいて`fps`の間隔に揃えて書き込まれる。
+    /// `preroll`に録画開始前のフレームが届いた場合は、それらを先に書き込む
+    /// (送信口が破棄された場合は録画開始後のフレームのみ書き込む)。
+    /// 分割録画が有効な場合は`{stem}_001.mp4`から書き始め、設定した時間・サイズを
+    /// 超えると録画スレッド内で次のファイルに切り替える (切り替え中のフレームはキューで待たせる)。
+    ///
+    /// # 引数
+    /// * `segments` - 分割録画の設定
+    /// * `pre_record` - 録画開始前に保持している時間 (キューの長さの見積もりに使う)
+    /// * `preroll` - 録画開始前のフレームの受信口
+    ///
+    /// # 戻り値
+    /// 成功時は録画セッションと、フレームを送るための送信口。
+    /// 失敗時は`Err(CameraError::WriterOpen)`
+    pub fn start(
+        path: &Path,
+        fps: f64,
+        frame_size: Size,
+        codec: FourCc,
+        segments: SegmentOptions,
+        pre_record: Duration,
+        preroll: Receiver<Vec<EncodedFrame>>,
+    ) -> Result<(Self, SyncSender<TimedFrame>), CameraError> {
+        let plan = segments
+            .is_enabled()
+            .then(|| SegmentPlan::new(path, segments));
+        let path = plan
+            .as_ref()
+            .map_or_else(|| path.to_path_buf(), |plan| plan.segment_path(1));
+        let (writer, codec) = open_writer_with_fallback(&path, codec, fps, frame_size)?;
+        let segment_index = Arc::new(AtomicU32::new(1));
+        let output = SegmentedOutput {
+            writer,
+            path: path.clone(),
+            codec,
+            fps,
+            frame_size,
+            plan,
+            frames_in_segment: 0,
+            closed: Vec::new(),
+            segment_index: segment_index.clone(),
+        };
+
+        // 録画開始前のフレームを書き込んでいる間に届くフレームの分だけキューを長くする
+        let catch_up = (pre_record.as_secs_f64() * fps / PRE_RECORD_CATCH_UP_RATIO) as usize;
+        let (sender, receiver) = mpsc::sync_channel(RECORDER_QUEUE_CAPACITY + catch_up);
+        let handle = thread::spawn(move || record_loop(output, fps, frame_size, preroll, receiver));
+
+        Ok((
+            Self {
+                handle: Some(handle),
+                path,
+                codec,
+                segment_index,
+            },
+            sender,
+        ))
+    }
+
+    /// 出力ファイルのパス (分割録画の場合は最初のファイル)
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 実際に開けたコーデック
+    pub fn codec(&self) -> FourCc {
+        self.codec
+    }
+
+    /// 書き込み中のファイルが何番目か (1始まり)
+    pub fn segment_index(&self) -> u32 {
+        self.segment_index.load(Ordering::Relaxed)
+    }
+
+    /// 録画スレッドの終了を待ち、集計結果を返す
+    ///
+    /// 送信口がすべて破棄されてから呼ぶこと (キューに残ったフレームは書き込まれる)。
+    pub fn finish(mut self) -> Option<RecordingSummary> {
+        self.handle.take().and_then(|handle| handle.join().ok())
+    }
+}
+
+/// 指定したコーデックでVideoWriterを開き、開けない場合はMJPGで再試行する
+///
+/// # 戻り値
+/// 成功時は開いたVideoWriterと実際に使ったコーデック、
+/// どちらでも開けなかった場合は`Err(CameraError::WriterOpen)`
+pub(crate) fn open_writer_with_fallback(
+    path: &Path,
+    codec: FourCc,
+    fps: f64,
+    frame_size: Size,
+) -> Result<(VideoWriter, FourCc), CameraError> {
+    // fourcc: Four Character Code (動画コーデック識別子)
+    // mp4v: MPEG-4 Part 2 (互換性が高い)
+    // MJPG: Motion JPEG (フォールバック用)
+    match open_writer(path, codec, fps, frame_size) {
+        Ok(writer) => Ok((writer, codec)),
+        Err(e) if codec != FourCc::MJPG => {
+            log::warn!("{}: MJPGで再試行します", e);
+            open_writer(path, FourCc::MJPG, fps, frame_size).map(|writer| (writer, FourCc::MJPG))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// 指定したコーデックでVideoWriterを開く
+///
+/// 開けなかった場合は、VideoWriterが作成した空のファイルを削除する
+/// (`path`には既存のファイルと重ならないパスを渡すこと)。
+///
+/// # 戻り値
+/// 成功時は開いたVideoWriter、作成に失敗した場合や開けなかった場合は`Err(CameraError)`
+pub(crate) fn open_writer(
+    path: &Path,
+    codec: FourCc,
+    fps: f64,
+    frame_size: Size,
+) -> Result<VideoWriter, CameraError> {
+    // VideoWriterを作成
+    let writer = match VideoWriter::new(
+        path.to_str().unwrap_or("video.mp4"),
+        codec.0,
+        fps,
+        frame_size,
+        true,
+    ) {
+        Ok(writer) => writer,
+        Err(e) => {
+            remove_partial_file(path);
+            return Err(CameraError::WriterOpen {
+                codec,
+                reason: e.to_string(),
+            });
+        }
+    };
+
+    // VideoWriterが正常に開けたか確認
+    if !writer.is_opened().unwrap_or(false) {
+        // ファイルを閉じてから削除
+        drop(writer);
+        remove_partial_file(path);
+        return Err(CameraError::WriterOpen {
+            codec,
+            reason: "コーデックに対応していないか、保存先に書き込めません".to_string(),
+        });
+    }
+    Ok(writer)
+}
+
+/// 開けなかったVideoWriterが残したファイルを削除
+fn remove_partial_file(path: &Path) {
+    if path.exists() {
+        if let Err(e) = fs::remove_file(path) {
+            log::warn!(
+                "録画できなかったファイルを削除できませんでした: {:?} ({})",
+                path,
+                e
+            );
+        }
+    }
+}
+
+/// 1フレームを書き込む (失敗時は集計して継続)
+pub(crate) fn write_frame(writer: &mut VideoWriter, frame: &Mat, summary: &mut RecordingSummary) {
+    if let Err(e) = writer.write(frame) {
+        // 最初の1回だけ詳細を出力 (以降は件数のみ集計)
+        if summary.frames_failed == 0 {
+            log::error!("フレームの書き込みに失敗しました: {}", e);
+        }
+        summary.frames_failed += 1;
+    }
+}
+
+/// 録画の書き込み先 (分割録画の場合は時間・サイズを超えるたびに次のファイルへ切り替える)
+struct SegmentedOutput {
+    /// 書き込み中のファイルのVideoWriter
+    writer: VideoWriter,
+    /// 書き込み中のファイルのパス
+    path: PathBuf,
+    /// 実際に開けたコーデック (次のファイルも同じコーデックで開く)
+    codec: FourCc,
+    /// 動画に宣言したFPS
+    fps: f64,
+    /// フレームサイズ
+    frame_size: Size,
+    /// 分割録画のファイル名の割り当て (分割しない場合や分割をやめた場合はNone)
+    plan: Option<SegmentPlan>,
+    /// 書き込み中のファイルに書いたフレーム数
+    frames_in_segment: u64,
+    /// 書き終えたファイル
+    closed: Vec<SegmentEntry>,
+    /// 書き込み中のファイルが何番目か (1始まり)
+    segment_index: Arc<AtomicU32>,
+}
+
+impl SegmentedOutput {
+    /// 1フレームを書き込む (必要なら先に次のファイルへ切り替える)
+    fn write(&mut self, frame: &Mat, summary: &mut RecordingSummary) {
+        if self.should_split() {
+            self.split(summary);
+        }
+        write_frame(&mut self.writer, frame, summary);
+        self.frames_in_segment += 1;
+    }
+
+    /// 書き込み中のファイルの録画時間
+    fn segment_duration(&self) -> Duration {
+        Duration::from_secs_f64(self.frames_in_segment as f64 / self.fps)
+    }
+
+    /// 書き込み中のファイルが分割の条件に達したかどうか
+    ///
+    /// サイズはVideoWriterの書き込みが遅れて反映されるため目安とし、
+    /// 一定フレームごとにのみ確認する。
+    fn should_split(&self) -> bool {
+        let Some(options) = self.plan.as_ref().map(SegmentPlan::options) else {
+            return false;
+        };
+        if self.frames_in_segment == 0 {
+            return false;
+        }
+        if options
+            .max_duration()
+            .is_some_and(|max| self.segment_duration() >= max)
+        {
+            return true;
+        }
+        options.max_bytes().is_some_and(|max| {
+            self.frames_in_segment
+                .is_multiple_of(SEGMENT_SIZE_CHECK_FRAMES)
+                && fs::metadata(&self.path).is_ok_and(|metadata| metadata.len() >= max)
+        })
+    }
+
+    /// 書き込み中のファイルを閉じて次のファイルを開く
+    ///
+    /// 次のファイルを開けない場合は分割をやめ、同じファイルに書き続ける。
+    fn split(&mut self, summary: &mut RecordingSummary) {
+        let Some(plan) = self.plan.as_ref() else {
+            return;
+        };
+        let index = self.segment_index.load(Ordering::Relaxed) + 1;
+        let path = plan.segment_path(index);
+        let writer = match open_writer(&path, self.codec, self.fps, self.frame_size) {
+            Ok(writer) => writer,
+            Err(e) => {
+                log::error!("次の録画ファイルを開けないため分割をやめます: {}", e);
+                summary.split_error = Some(e);
+                self.plan = None;
+                return;
+            }
+        };
+
+        // 古いVideoWriterを解放してファイルをクローズ
+        drop(std::mem::replace(&mut self.writer, writer));
+        let closed = std::mem::replace(&mut self.path, path);
+        log::info!(
+            "録画ファイルを切り替えました: {:?} → {:?}",
+            closed,
+            self.path
+        );
+        self.closed.push(SegmentEntry {
+            path: closed,
+            duration: Some(self.segment_duration()),
+        });
+        self.frames_in_segment = 0;
+        self.segment_index.store(index, Ordering::Relaxed);
+        self.write_manifest(false);
+    }
+
+    /// 分割したファイルの一覧を書き出す (設定で無効な場合は何もしない)
+    ///
+    /// 録画中に異常終了しても書き終えたファイルが分かるように、切り替えのたびに更新する。
+    fn write_manifest(&self, finished: bool) {
+        let Some(plan) = self.plan.as_ref().filter(|plan| plan.options().manifest) else {
+            return;
+        };
+        let mut entries = self.closed.clone();
+        entries.push(SegmentEntry {
+            path: self.path.clone(),
+            duration: finished.then(|| self.segment_duration()),
+        });
+        if let Err(e) = segment::write_manifest(plan.manifest_path(), &entries) {
+            log::warn!("録画ファイルの一覧を書き出せませんでした: {}", e);
+        }
+    }
+
+    /// VideoWriterを解放 (ファイルをクローズ) し、書き込んだファイルを録画順に返す
+    fn finish(self) -> Vec<PathBuf> {
+        self.write_manifest(true);
+        let Self {
+            writer,
+            path,
+            closed,
+            ..
+        } = self;
+        drop(writer);
+        closed
+            .into_iter()
+            .map(|entry| entry.path)
+            .chain(std::iter::once(path))
+            .collect()
+    }
+}
+
+/// ペーサーに従ってVideoWriterへ書き込む (空いたスロットは直前のフレームの複製で埋める)
+struct PacedWriter {
+    /// 書き込み先
+    output: SegmentedOutput,
+    /// 書き込み位置を決めるペーサー
+    pacer: TimestampPacer,
+    /// 空いたスロットを埋めるための直前のフレーム
+    previous: Option<Mat>,
+    /// 集計結果
+    summary: RecordingSummary,
+}
+
+impl PacedWriter {
+    /// 指定したFPSで書き込む
+    fn new(output: SegmentedOutput, fps: f64) -> Self {
+        Self {
+            output,
+            pacer: TimestampPacer::new(fps),
+            previous: None,
+            summary: RecordingSummary::default(),
+        }
+    }
+
+    /// 取得時刻に対応するスロットにフレームを書き込む
+    ///
+    /// # 戻り値
+    /// 書き込んだ場合はtrue、スロットが書き込み済みのため破棄した場合はfalse
+    fn push(&mut self, frame: Mat, captured_at: Instant) -> bool {
+        let Some(slot) = self.pacer.place(captured_at, self.summary.frames_written) else {
+            // このスロットは書き込み済み: 早すぎるフレームは破棄
+            self.summary.frames_dropped += 1;
+            return false;
+        };
+
+        // 前のフレームから間隔が空いた分は、直前のフレームを複製して埋める
+        if let Some(previous) = self.previous.as_ref() {
+            while self.summary.frames_written < slot {
+                self.output.write(previous, &mut self.summary);
+                self.summary.frames_written += 1;
+                self.summary.frames_duplicated += 1;
+            }
+        }
+
+        self.output.write(&frame, &mut self.summary);
+        self.summary.frames_written += 1;
+        self.previous = Some(frame);
+        true
+    }
+
+    /// VideoWriterを即座に解放 (ファイルをクローズ) し、集計結果を返す
+    fn finish(self) -> RecordingSummary {
+        let mut summary = self.summary;
+        summary.segments = self.output.finish();
+        summary
+    }
+}
+
+/// 録画スレッドの本体
+///
+/// 録画開始前のフレームを先に書き込み、その後は送信口が破棄されるまで、
+/// 取得時刻に合わせてフレームを複製・破棄しながら書き込む。最後にVideoWriterを解放する。
+fn record_loop(
+    output: SegmentedOutput,
+    fps: f64,
+    frame_size: Size,
+    preroll: Receiver<Vec<EncodedFrame>>,
+    receiver: Receiver<TimedFrame>,
+) -> RecordingSummary {
+    let mut paced = PacedWriter::new(output, fps);
+    let mut last_sequence: Option<u64> = None;
+
+    // 録画開始前のフレーム (解像度が録画と異なるもの・復元できないものは飛ばす)
+    for encoded in preroll.recv().unwrap_or_default() {
+        let Some(frame) = encoded
+            .decode()
+            .filter(|frame| frame.size().is_ok_and(|size| size == frame_size))
+        else {
+            continue;
+        };
+        // バッファ内の連番の欠けは圧縮が追いつかなかったもので、録画の取りこぼしには数えない
+        last_sequence = Some(encoded.sequence);
+        if paced.push(frame, encoded.captured_at) {
+            paced.summary.frames_prerolled += 1;
+        }
+    }
+
+    for timed in receiver {
+        // 連番の欠けはキャプチャスレッド側で破棄されたフレーム
+        if let Some(last) = last_sequence {
+            paced.summary.frames_missed += timed.sequence.saturating_sub(last + 1);
+        }
+        last_sequence = Some(timed.sequence);
+        paced.push(timed.frame, timed.captured_at);
+    }
+
+    paced.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    // 標準ライブラリのインポート
+    use std::time::Duration;
+
+    use super::*;
+
+    /// テストに使うFPS (1スロット = 100ms)
+    const FPS: f64 = 10.0;
+
+    /// 基準時刻から`millis`ミリ秒後
+    fn at(start: Instant, millis: u64) -> Instant {
+        start + Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn first_frame_is_slot_zero() {
+        let start = Instant::now();
+        let mut pacer = TimestampPacer::new(FPS);
+        assert_eq!(pacer.slot_for(at(start, 500)), 0);
+        assert_eq!(pacer.slot_for(at(start, 600)), 1);
+    }
+
+    #[test]
+    fn frames_within_same_slot_share_it() {
+        // 宣言FPSより速く届いたフレームは同じスロットになる (書き込み時に破棄される)
+        let start = Instant::now();
+        let mut pacer = TimestampPacer::new(FPS);
+        let slots: Vec<u64> = [0, 30, 100, 140, 160]
+            .into_iter()
+            .map(|millis| pacer.slot_for(at(start, millis)))
+            .collect();
+        assert_eq!(slots, [0, 0, 1, 1, 2]);
+    }
+
+    #[test]
+    fn gaps_skip_slots() {
+        // 間隔が空いた場合は間のスロットを飛ばす (書き込み時に複製で埋める)
+        let start = Instant::now();
+        let mut pacer = TimestampPacer::new(FPS);
+        assert_eq!(pacer.slot_for(start), 0);
+        assert_eq!(pacer.slot_for(at(start, 450)), 5);
+        assert_eq!(pacer.slot_for(at(start, 1_000)), 10);
+    }
+
+    #[test]
+    fn frames_before_start_clamp_to_slot_zero() {
+        let start = Instant::now();
+        let mut pacer = TimestampPacer::new(FPS);
+        assert_eq!(pacer.slot_for(at(start, 200)), 0);
+        assert_eq!(pacer.slot_for(start), 0);
+    }
+
+    /// 書き込み済みのスロット数を進めながら、各フレームの配置を求める
+    ///
+    /// # 戻り値
+    /// フレームごとの、複製で埋める数 (破棄する場合はNone)
+    fn place_all(pacer: &mut TimestampPacer, start: Instant, millis: &[u64]) -> Vec<Option<u64>> {
+        let mut written = 0;
+        millis
+            .iter()
+            .map(|&millis| {
+                let slot = pacer.place(at(start, millis), written)?;
+                let duplicates = slot - written;
+                written = slot + 1;
+                Some(duplicates)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn early_frames_are_dropped() {
+        let start = Instant::now();
+        let mut pacer = TimestampPacer::new(FPS);
+        assert_eq!(
+            place_all(&mut pacer, start, &[0, 30, 100, 140, 160]),
+            [Some(0), None, Some(0), None, Some(0)]
+        );
+    }
+
+    #[test]
+    fn gaps_are_filled_with_duplicates() {
+        let start = Instant::now();
+        let mut pacer = TimestampPacer::new(FPS);
+        assert_eq!(
+            place_all(&mut pacer, start, &[0, 100, 400, 500]),
+            [Some(0), Some(0), Some(2), Some(0)]
+        );
+    }
+
+    #[test]
+    fn long_gaps_are_filled_up_to_max_fill_duration() {
+        let start = Instant::now();
+        let mut pacer = TimestampPacer::new(FPS);
+        let max_fill = pacer.slots_in(MAX_FILL_DURATION);
+        // 5秒途切れても、埋めるのは上限の1秒分だけ
+        assert_eq!(
+            place_all(&mut pacer, start, &[0, 5_000]),
+            [Some(0), Some(max_fill)]
+        );
+    }
+
+    #[test]
+    fn pacer_reanchors_after_long_stall() {
+        let start = Instant::now();
+        let mut pacer = TimestampPacer::new(FPS);
+        let max_fill = pacer.slots_in(MAX_FILL_DURATION);
+        assert_eq!(pacer.place(start, 0), Some(0));
+        // 途切れた後のフレームは上限分だけ空けた位置に置く
+        assert_eq!(pacer.place(at(start, 5_000), 1), Some(1 + max_fill));
+        // 以降は途切れた後のフレームを基準に位置を決める
+        let written = max_fill + 2;
+        assert_eq!(pacer.place(at(start, 5_100), written), Some(written));
+        assert_eq!(pacer.place(at(start, 5_130), written + 1), None);
+    }
+}
@@ -0,0 +1,267 @@
+// 標準ライブラリのインポート
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// サードパーティクレートのインポート
+use serde::{Deserialize, Serialize};
+
+use crate::error::CameraError;
+use crate::filename;
+
+/// 1ファイルの録画時間の上限 (分、24時間)
+pub const MAX_SEGMENT_MINUTES: u32 = 24 * 60;
+/// 1ファイルのサイズの上限 (MB、FAT32の4GB制限に収まるように)
+pub const MAX_SEGMENT_SIZE_MB: u32 = 4000;
+/// 1MBのバイト数
+const BYTES_PER_MB: u64 = 1024 * 1024;
+/// 1分の秒数
+const SECS_PER_MINUTE: u64 = 60;
+/// 連番の桁数 (ゼロ埋め)
+const SEGMENT_INDEX_DIGITS: usize = 3;
+/// 一覧ファイルの拡張子 (M3Uプレイリスト)
+const MANIFEST_EXTENSION: &str = "m3u";
+/// 書き込み途中の一覧ファイルの拡張子 (書き込み後に置き換える)
+const MANIFEST_TEMP_EXTENSION: &str = "m3u.tmp";
+
+/// 分割録画の設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SegmentOptions {
+    /// 1ファイルの録画時間 (分、0は時間で分割しない)
+    #[serde(rename = "segment_minutes")]
+    pub max_minutes: u32,
+    /// 1ファイルのサイズ (MB、0はサイズで分割しない)
+    #[serde(rename = "segment_size_mb")]
+    pub max_size_mb: u32,
+    /// 分割したファイルの一覧 (M3Uプレイリスト) を書き出すかどうか
+    #[serde(rename = "segment_manifest")]
+    pub manifest: bool,
+}
+
+impl Default for SegmentOptions {
+    fn default() -> Self {
+        Self {
+            max_minutes: 0,
+            max_size_mb: 0,
+            manifest: true,
+        }
+    }
+}
+
+impl SegmentOptions {
+    /// 範囲外の値を上限に収める
+    pub fn clamp(&mut self) {
+        self.max_minutes = self.max_minutes.min(MAX_SEGMENT_MINUTES);
+        self.max_size_mb = self.max_size_mb.min(MAX_SEGMENT_SIZE_MB);
+    }
+
+    /// 分割録画が有効かどうか (時間・サイズのどちらかが設定されている)
+    pub fn is_enabled(&self) -> bool {
+        self.max_minutes > 0 || self.max_size_mb > 0
+    }
+
+    /// 1ファイルの録画時間 (時間で分割しない場合はNone)
+    pub fn max_duration(&self) -> Option<Duration> {
+        (self.max_minutes > 0)
+            .then(|| Duration::from_secs(u64::from(self.max_minutes) * SECS_PER_MINUTE))
+    }
+
+    /// 1ファイルのサイズ (バイト、サイズで分割しない場合はNone)
+    pub fn max_bytes(&self) -> Option<u64> {
+        (self.max_size_mb > 0).then(|| u64::from(self.max_size_mb) * BYTES_PER_MB)
+    }
+}
+
+/// 分割録画のファイル名の割り当て
+///
+/// 録画のファイル名 (`{stem}.mp4`) から、`{stem}_001.mp4`, `{stem}_002.mp4` ... の
+/// 連番のファイル名と、一覧ファイル`{stem}.m3u`のパスを決める。
+/// 一覧ファイルも既存のファイルとは重ならないように、同名のファイルがある場合は`{stem}_1.m3u`とする。
+#[derive(Debug, Clone)]
+pub struct SegmentPlan {
+    /// 保存先ディレクトリ
+    dir: PathBuf,
+    /// ファイル名の共通部分
+    stem: String,
+    /// 拡張子
+    extension: String,
+    /// 分割の条件
+    options: SegmentOptions,
+    /// 一覧ファイルのパス (録画中に何度も書き直すため、最初に決めておく)
+    manifest: PathBuf,
+}
+
+impl SegmentPlan {
+    /// 録画のファイル名から分割録画のファイル名を決める
+    pub fn new(path: &Path, options: SegmentOptions) -> Self {
+        let name = |part: Option<&std::ffi::OsStr>| {
+            part.map(|part| part.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        };
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let stem = name(path.file_stem());
+        let manifest = filename::unique_path(&dir, &stem, MANIFEST_EXTENSION);
+        Self {
+            dir,
+            stem,
+            extension: name(path.extension()),
+            options,
+            manifest,
+        }
+    }
+
+    /// 分割の条件
+    pub fn options(&self) -> &SegmentOptions {
+        &self.options
+    }
+
+    /// `index`番目 (1始まり) のファイルのパス (既存のファイルとは重ならない)
+    pub fn segment_path(&self, index: u32) -> PathBuf {
+        let name = format!(
+            "{}_{:0width$}",
+            self.stem,
+            index,
+            width = SEGMENT_INDEX_DIGITS
+        );
+        filename::unique_path(&self.dir, &name, &self.extension)
+    }
+
+    /// 一覧ファイルのパス (既存のファイルとは重ならない)
+    pub fn manifest_path(&self) -> &Path {
+        &self.manifest
+    }
+}
+
+/// 一覧ファイルに載せる1ファイル分の情報
+#[derive(Debug, Clone)]
+pub struct SegmentEntry {
+    /// ファイルのパス
+    pub path: PathBuf,
+    /// 録画時間 (録画中のファイルはNone)
+    pub duration: Option<Duration>,
+}
+
+/// 分割したファイルの一覧をM3Uプレイリストとして書き出す
+///
+/// ファイル名は一覧ファイルからの相対パス (同じフォルダ) で書くため、
+/// フォルダごと移動しても再生できる。
+/// 書き込み途中で終了してもファイルが壊れないように、一時ファイルに書き込んでから置き換える。
+pub fn write_manifest(path: &Path, entries: &[SegmentEntry]) -> Result<(), CameraError> {
+    let mut text = String::from("#EXTM3U\n");
+    for entry in entries {
+        let name = entry
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        // 録画時間が分からない (録画中の) ファイルは-1にする
+        let seconds = entry.duration.map_or_else(
+            || "-1".to_string(),
+            |duration| format!("{:.3}", duration.as_secs_f64()),
+        );
+        let _ = writeln!(text, "#EXTINF:{},{}", seconds, name);
+        let _ = writeln!(text, "{}", name);
+    }
+
+    let temp_path = path.with_extension(MANIFEST_TEMP_EXTENSION);
+    fs::write(&temp_path, text).map_err(|e| CameraError::io(&temp_path, e))?;
+    fs::rename(&temp_path, path).map_err(|e| CameraError::io(path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thresholds_follow_options() {
+        let disabled = SegmentOptions::default();
+        assert!(!disabled.is_enabled());
+        assert_eq!(disabled.max_duration(), None);
+        assert_eq!(disabled.max_bytes(), None);
+
+        let by_time = SegmentOptions {
+            max_minutes: 5,
+            ..SegmentOptions::default()
+        };
+        assert!(by_time.is_enabled());
+        assert_eq!(by_time.max_duration(), Some(Duration::from_secs(300)));
+        assert_eq!(by_time.max_bytes(), None);
+
+        let by_size = SegmentOptions {
+            max_size_mb: 100,
+            ..SegmentOptions::default()
+        };
+        assert!(by_size.is_enabled());
+        assert_eq!(by_size.max_duration(), None);
+        assert_eq!(by_size.max_bytes(), Some(100 * 1024 * 1024));
+    }
+
+    #[test]
+    fn clamp_limits_out_of_range_values() {
+        let mut options = SegmentOptions {
+            max_minutes: MAX_SEGMENT_MINUTES + 1,
+            max_size_mb: u32::MAX,
+            manifest: true,
+        };
+        options.clamp();
+        assert_eq!(options.max_minutes, MAX_SEGMENT_MINUTES);
+        assert_eq!(options.max_size_mb, MAX_SEGMENT_SIZE_MB);
+    }
+
+    #[test]
+    fn plan_numbers_segments_and_skips_existing_files() -> Result<(), String> {
+        let temp = tempfile::tempdir().map_err(|e| e.to_string())?;
+        let dir = temp.path();
+        let plan = SegmentPlan::new(&dir.join("video.mp4"), SegmentOptions::default());
+        assert_eq!(plan.segment_path(1), dir.join("video_001.mp4"));
+        assert_eq!(plan.segment_path(12), dir.join("video_012.mp4"));
+        assert_eq!(plan.manifest_path(), dir.join("video.m3u"));
+
+        fs::write(dir.join("video_002.mp4"), b"").map_err(|e| e.to_string())?;
+        assert_eq!(plan.segment_path(2), dir.join("video_002_1.mp4"));
+        Ok(())
+    }
+
+    #[test]
+    fn manifest_lists_segments_with_durations() -> Result<(), String> {
+        let temp = tempfile::tempdir().map_err(|e| e.to_string())?;
+        let dir = temp.path();
+        let path = dir.join("video.m3u");
+        let entries = [
+            SegmentEntry {
+                path: dir.join("video_001.mp4"),
+                duration: Some(Duration::from_millis(60_500)),
+            },
+            SegmentEntry {
+                path: dir.join("video_002.mp4"),
+                duration: None,
+            },
+        ];
+        write_manifest(&path, &entries).map_err(|e| e.to_string())?;
+
+        let text = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+        assert_eq!(
+            text,
+            "#EXTM3U\n\
+             #EXTINF:60.500,video_001.mp4\n\
+             video_001.mp4\n\
+             #EXTINF:-1,video_002.mp4\n\
+             video_002.mp4\n"
+        );
+        // 一時ファイルは残らない
+        assert!(!path.with_extension(MANIFEST_TEMP_EXTENSION).exists());
+        Ok(())
+    }
+
+    #[test]
+    fn manifest_path_does_not_overwrite_existing_manifest() -> Result<(), String> {
+        let temp = tempfile::tempdir().map_err(|e| e.to_string())?;
+        let dir = temp.path();
+        fs::write(dir.join("video.m3u"), b"#EXTM3U\n").map_err(|e| e.to_string())?;
+        let plan = SegmentPlan::new(&dir.join("video.mp4"), SegmentOptions::default());
+        assert_eq!(plan.manifest_path(), dir.join("video_1.m3u"));
+        Ok(())
+    }
+}
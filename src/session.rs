@@ -0,0 +1,1247 @@
+// 標準ライブラリのインポート
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// サードパーティクレートのインポート
+use chrono::Local;
+use opencv::core::Size;
+
+use crate::burst::{BurstOptions, BurstProgress, BurstSession, BurstSummary};
+use crate::capture::{CaptureThread, IntervalTap, TimedFrame};
+use crate::codec::{self, VideoContainer, VideoFormat, VideoSupport};
+use crate::error::CameraError;
+use crate::filename::{self, FilenameContext, FilenameTemplate};
+use crate::metadata::{PhotoMetadata, ORIENTATION_NORMAL, SOFTWARE_NAME};
+use crate::photo::{PhotoOptions, PhotoSession};
+use crate::prerecord::{EncodedFrame, MAX_PRE_RECORD_SECS};
+use crate::recorder::{RecordingSession, RecordingSummary};
+use crate::segment::SegmentOptions;
+use crate::settings::{RoleDevices, Settings, DEFAULT_VIDEO_CODEC, MAX_SELF_TIMER_SECS};
+use crate::source::{
+    candidate_modes, default_backends, parse_backends, CameraDevice, CameraSource, FourCc,
+    FrameSource, ModeProbe, SourceKind, SourceProperties, SyntheticSource, TestPatternConfig,
+    VideoMode,
+};
+use crate::storage;
+use crate::timelapse::{
+    self, TimelapseOptions, TimelapseProgress, TimelapseSession, TimelapseState, TimelapseSummary,
+    UnfinishedTimelapse,
+};
+use crate::{CameraPosition, CaptureMode};
+
+/// 録画FPSのデフォルト値 (ソースから取得できない場合に使用)
+const DEFAULT_FPS: f64 = 30.0;
+/// 録画FPSとして許容する下限 (この値以下は不正とみなす)
+pub const MIN_FPS: f64 = 0.0;
+/// 録画FPSとして許容する上限
+pub const MAX_FPS: f64 = 120.0;
+
+/// 開始した録画セッション、フレームの送信口、録画開始前のフレームの送信口
+type OpenedRecording = (
+    RecordingSession,
+    SyncSender<TimedFrame>,
+    Sender<Vec<EncodedFrame>>,
+);
+
+/// カメラセッション: フレームソースの選択・撮影・録画をまとめて管理する
+///
+/// UIに依存しないため、eGuiのフロントエンドとヘッドレスモードの両方から使用する。
+/// プレビュー画像はキャプチャスレッドがeGui用に変換して格納する。
+pub struct CameraSession {
+    /// キャプチャスレッド (フレームソースを所有し、UIとは独立してフレームを取得)
+    capture: Option<CaptureThread>,
+    /// 録画セッション (録画中のみ使用)
+    recording: Option<RecordingSession>,
+    /// 現在のカメラフレーム (キャプチャスレッドがeGui描画用に変換して格納)
+    current_frame: Arc<Mutex<Option<egui::ColorImage>>>,
+    /// 現在のキャプチャモード (写真/動画)
+    capture_mode: CaptureMode,
+    /// 現在のカメラポジション (フロント/リア/外部)
+    camera_position: CameraPosition,
+    /// 役割ごとに割り当てた物理デバイス (カメラインデックス)
+    role_devices: RoleDevices,
+    /// 録画中かどうか (ロックフリーなアトミック変数で管理)
+    is_recording: Arc<AtomicBool>,
+    /// 使用するフレームソースの種類 (カメラ/テストパターン)
+    source_kind: SourceKind,
+    /// 現在のカメラデバイスのインデックス (役割の割り当てに従う)
+    camera_index: i32,
+    /// カメラを開く際に試すバックエンド (先頭から順に試行)
+    camera_backends: Vec<i32>,
+    /// フレームの幅 (ピクセル)
+    frame_width: i32,
+    /// フレームの高さ (ピクセル)
+    frame_height: i32,
+    /// 写真・動画の保存先ディレクトリ
+    output_dir: PathBuf,
+    /// 写真・動画のファイル名テンプレート
+    filename_template: FilenameTemplate,
+    /// 最後に付けたファイルの連番 (`&self`の撮影からも進められるようにアトミック変数で管理)
+    file_sequence: AtomicU64,
+    /// 現在開いているフレームソースの表示名
+    source_name: String,
+    /// フレームソースが申告するFPS (録画FPSに使用)
+    source_fps: f64,
+    /// カメラに要求するFPS (Noneの場合はドライバーのデフォルト)
+    requested_fps: Option<f64>,
+    /// カメラに要求するピクセルフォーマット (Noneの場合はドライバーのデフォルト)
+    pixel_format: Option<FourCc>,
+    /// フレームソースが申告するピクセルフォーマット
+    source_fourcc: Option<FourCc>,
+    /// 検出済みの動作モード (モードピッカー表示用)
+    mode_probes: Vec<ModeProbe>,
+    /// 実行中のモード検出 (検出中のみSome)
+    pending_mode_probe: Option<Receiver<Vec<ModeProbe>>>,
+    /// 実行中のモード変更 (要求したモードと結果の受信口、変更中のみSome)
+    pending_mode_change: Option<(VideoMode, Receiver<Result<SourceProperties, CameraError>>)>,
+    /// バックグラウンドの処理 (モード変更・録画) で発生し、まだUIに渡していないエラー
+    errors: Vec<CameraError>,
+    /// 写真の保存形式と画質設定
+    photo_options: PhotoOptions,
+    /// 保存中の写真 (保存が終わって`poll_photo`で取り出すまでSome)
+    photo: Option<PhotoSession>,
+    /// 連写の枚数と間隔
+    burst_options: BurstOptions,
+    /// 実行中の連写 (保存が終わって`poll_burst`で取り出すまでSome)
+    burst: Option<BurstSession>,
+    /// タイムラプスの撮影間隔・終了条件・動画の設定
+    timelapse_options: TimelapseOptions,
+    /// 実行中のタイムラプス (書き込みスレッドが終わって`poll_timelapse`で取り出すまでSome)
+    timelapse: Option<TimelapseSession>,
+    /// ソースを開き直す間に預かるタイムラプスの選択器
+    /// (開き直せなかった場合は、次にソースを開くまで撮影を一時停止する)
+    suspended_timelapse: Option<IntervalTap>,
+    /// 録画コーデック
+    video_codec: FourCc,
+    /// 録画ファイルのコンテナ形式
+    video_container: VideoContainer,
+    /// この環境で書き込めた・書き込めなかった録画形式
+    video_support: VideoSupport,
+    /// 実行中の録画形式の検出 (検出中のみSome)
+    pending_format_probe: Option<Receiver<VideoSupport>>,
+    /// 分割録画の設定
+    segment_options: SegmentOptions,
+    /// セルフタイマーの秒数 (0は無効、カウントダウン自体はUIが行う)
+    self_timer_secs: u32,
+    /// 録画開始前の何秒間を録画に含めるか (0は無効、動画モードの間だけフレームを保持する)
+    pre_record_secs: u32,
+    /// 設定ファイルから読み込んだ設定 (変更しない項目はこの値のまま保存する)
+    settings: Settings,
+}
+
+impl Default for CameraSession {
+    /// デフォルト設定のセッションを構築 (リアカメラ、写真モード、640x480)
+    fn default() -> Self {
+        Self::new(Settings::default())
+    }
+}
+
+impl CameraSession {
+    /// 設定からセッションを構築 (カメラはまだ開かない)
+    ///
+    /// 解釈できない値 (不明なバックエンド名・FOURCC) は警告を出してデフォルト値を使用する。
+    pub fn new(settings: Settings) -> Self {
+        let camera_backends = if settings.camera.backends.is_empty() {
+            default_backends()
+        } else {
+            parse_backends(&settings.camera.backends.join(",")).unwrap_or_else(|e| {
+                log::warn!("設定ファイルのバックエンドを無視します: {}", e);
+                default_backends()
+            })
+        };
+        let pixel_format = settings.camera.pixel_format.as_deref().and_then(|text| {
+            FourCc::parse(text)
+                .map_err(|e| log::warn!("設定ファイルのピクセルフォーマットを無視します: {}", e))
+                .ok()
+        });
+        let filename_template = FilenameTemplate::parse(&settings.output.filename_template)
+            .unwrap_or_else(|e| {
+                log::warn!("設定ファイルのファイル名テンプレートを無視します: {}", e);
+                FilenameTemplate::default()
+            });
+        let video_codec = FourCc::parse(&settings.capture.video_codec).unwrap_or_else(|e| {
+            log::warn!("設定ファイルの録画コーデックを無視します: {}", e);
+            // 設定ファイルに書かなかった場合と同じコーデックにする
+            FourCc::parse(DEFAULT_VIDEO_CODEC).unwrap_or(FourCc::MP4V)
+        });
+
+        Self {
+            capture: None,
+            recording: None,
+            current_frame: Arc::new(Mutex::new(None)),
+            capture_mode: settings.capture.mode,
+            camera_position: settings.camera.position,
+            role_devices: settings.camera.roles,
+            is_recording: Arc::new(AtomicBool::new(false)),
+            source_kind: SourceKind::Camera,
+            camera_index: settings.camera.roles.get(settings.camera.position),
+            camera_backends,
+            frame_width: settings.camera.width,
+            frame_height: settings.camera.height,
+            output_dir: settings.output.dir.clone(),
+            filename_template,
+            file_sequence: AtomicU64::new(0),
+            source_name: String::new(),
+            source_fps: 0.0,
+            requested_fps: settings.camera.fps,
+            pixel_format,
+            source_fourcc: None,
+            mode_probes: Vec::new(),
+            pending_mode_probe: None,
+            pending_mode_change: None,
+            errors: Vec::new(),
+            photo_options: settings.capture.photo,
+            photo: None,
+            burst_options: settings.capture.burst,
+            burst: None,
+            timelapse_options: settings.capture.timelapse,
+            timelapse: None,
+            suspended_timelapse: None,
+            video_codec,
+            video_container: settings.capture.video_container,
+            video_support: settings.video_support.clone(),
+            pending_format_probe: None,
+            segment_options: settings.capture.segment,
+            self_timer_secs: settings.capture.self_timer_secs,
+            pre_record_secs: settings.capture.pre_record_secs,
+            settings,
+        }
+    }
+
+    /// 環境変数の指定を反映 (設定ファイルの内容より優先)
+    ///
+    /// - `CAMERA_APP_TEST_PATTERN=1280x720@60`: カメラの代わりにテストパターンを使用
+    ///   (空文字列の場合はデフォルト設定)
+    /// - `CAMERA_APP_BACKENDS=v4l2,gstreamer,any`: バックエンドの試行順
+    pub fn apply_env_overrides(&mut self) {
+        if let Ok(spec) = std::env::var("CAMERA_APP_TEST_PATTERN") {
+            let config = if spec.trim().is_empty() {
+                Ok(TestPatternConfig::default())
+            } else {
+                TestPatternConfig::parse(&spec)
+            };
+            match config {
+                Ok(config) => self.source_kind = SourceKind::TestPattern(config),
+                Err(e) => log::warn!("CAMERA_APP_TEST_PATTERNを無視します: {}", e),
+            }
+        }
+
+        if let Ok(spec) = std::env::var("CAMERA_APP_BACKENDS") {
+            match parse_backends(&spec) {
+                Ok(backends) => self.camera_backends = backends,
+                Err(e) => log::warn!("CAMERA_APP_BACKENDSを無視します: {}", e),
+            }
+        }
+    }
+
+    /// 現在の状態を設定として取り出す
+    ///
+    /// カメラ以外のソース (テストパターン・ファイル再生) を使用中は、
+    /// その解像度をカメラの設定として保存しない。
+    pub fn current_settings(&self) -> Settings {
+        let mut settings = self.settings.clone();
+        settings.capture.mode = self.capture_mode;
+        settings.camera.position = self.camera_position;
+        settings.camera.roles = self.role_devices;
+        settings.capture.photo = self.photo_options;
+        settings.capture.burst = self.burst_options;
+        settings.capture.timelapse = self.timelapse_options;
+        settings.capture.video_codec = self.video_codec.to_string();
+        settings.capture.video_container = self.video_container;
+        settings.capture.segment = self.segment_options;
+        settings.video_support = self.video_support.clone();
+        settings.capture.self_timer_secs = self.self_timer_secs;
+        settings.capture.pre_record_secs = self.pre_record_secs;
+        if matches!(self.source_kind, SourceKind::Camera | SourceKind::Device(_)) {
+            settings.camera.width = self.frame_width;
+            settings.camera.height = self.frame_height;
+            settings.camera.fps = self.requested_fps;
+            settings.camera.pixel_format = self.pixel_format.map(|fourcc| fourcc.to_string());
+        }
+        settings
+    }
+
+    /// 開く前にソースの種類を設定 (開き直す場合は`switch_source`を使用)
+    pub fn set_source_kind(&mut self, kind: SourceKind) {
+        self.source_kind = kind;
+    }
+
+    /// 開く前にカメラインデックスを現在の役割に割り当て、カメラをソースにする
+    pub fn set_camera_index(&mut self, index: i32) {
+        self.role_devices.set(self.camera_position, index);
+        self.camera_index = index;
+        self.source_kind = SourceKind::Camera;
+    }
+
+    /// 開く前に要求する解像度を設定
+    pub fn set_resolution(&mut self, width: i32, height: i32) {
+        self.frame_width = width;
+        self.frame_height = height;
+    }
+
+    /// 開く前に要求するFPSを設定
+    pub fn set_requested_fps(&mut self, fps: Option<f64>) {
+        self.requested_fps = fps;
+    }
+
+    /// 写真・動画の保存先ディレクトリを設定
+    pub fn set_output_dir(&mut self, dir: PathBuf) {
+        self.output_dir = dir;
+    }
+
+    /// 写真の保存形式と画質設定を変更 (範囲外の値は上下限に収める)
+    pub fn set_photo_options(&mut self, mut options: PhotoOptions) {
+        options.clamp();
+        self.photo_options = options;
+    }
+
+    /// 連写の枚数と間隔を変更 (範囲外の値は上下限に収める)
+    pub fn set_burst_options(&mut self, mut options: BurstOptions) {
+        options.clamp();
+        self.burst_options = options;
+    }
+
+    /// タイムラプスの設定を変更 (範囲外の値は上下限に収める、撮影中のタイムラプスには反映しない)
+    pub fn set_timelapse_options(&mut self, mut options: TimelapseOptions) {
+        options.clamp();
+        self.timelapse_options = options;
+    }
+
+    /// 録画形式 (コーデック・コンテナ) を変更 (録画中の録画には反映しない)
+    ///
+    /// 書き込めないと分かっている形式も選べるが、録画開始時に代わりの形式を使う。
+    pub fn set_video_format(&mut self, format: VideoFormat) {
+        self.video_codec = format.codec;
+        self.video_container = format.container;
+    }
+
+    /// すべての録画形式を書き込めるかどうかバックグラウンドで検出 (結果は`poll_format_probe`で反映)
+    pub fn probe_video_formats(&mut self) {
+        if self.pending_format_probe.is_none() {
+            self.pending_format_probe = Some(codec::probe());
+        }
+    }
+
+    /// 録画形式の検出が終わっていれば結果を反映
+    ///
+    /// # 戻り値
+    /// 検出が終わった場合はtrue
+    pub fn poll_format_probe(&mut self) -> bool {
+        let Some(receiver) = self.pending_format_probe.as_ref() else {
+            return false;
+        };
+        match receiver.try_recv() {
+            Ok(support) => {
+                self.pending_format_probe = None;
+                log::info!(
+                    "録画形式を検出しました (対応: {}/{})",
+                    support.supported.len(),
+                    support.supported.len() + support.unsupported.len()
+                );
+                self.video_support = support;
+                true
+            }
+            Err(TryRecvError::Empty) => false,
+            Err(TryRecvError::Disconnected) => {
+                self.pending_format_probe = None;
+                false
+            }
+        }
+    }
+
+    /// 分割録画の設定を変更 (範囲外の値は上限に収める、録画中の録画には反映しない)
+    pub fn set_segment_options(&mut self, mut options: SegmentOptions) {
+        options.clamp();
+        self.segment_options = options;
+    }
+
+    /// セルフタイマーの秒数を設定 (0で無効、上限を超える値は上限に収める)
+    pub fn set_self_timer_secs(&mut self, seconds: u32) {
+        self.self_timer_secs = seconds.min(MAX_SELF_TIMER_SECS);
+    }
+
+    /// 録画開始前の何秒間を録画に含めるかを設定 (0で無効、上限を超える値は上限に収める)
+    pub fn set_pre_record_secs(&mut self, seconds: u32) {
+        self.pre_record_secs = seconds.min(MAX_PRE_RECORD_SECS);
+        self.update_pre_record();
+    }
+
+    /// 録画開始前のフレームを保持する時間 (動画モードで有効な場合のみSome)
+    fn pre_record_duration(&self) -> Option<Duration> {
+        (self.capture_mode == CaptureMode::Video && self.pre_record_secs > 0)
+            .then(|| Duration::from_secs(u64::from(self.pre_record_secs)))
+    }
+
+    /// 録画開始前のバッファの有無・時間をキャプチャスレッドに反映
+    fn update_pre_record(&self) {
+        if let Some(capture) = self.capture.as_ref() {
+            capture.set_pre_record(self.pre_record_duration());
+        }
+    }
+
+    /// キャプチャモードを切り替える
+    ///
+    /// 動画以外のモードへの切り替え時は録画を停止し、
+    /// タイムラプス以外のモードへの切り替え時はタイムラプスを仕上げる。
+    pub fn set_capture_mode(&mut self, mode: CaptureMode) {
+        if mode != CaptureMode::Video && self.is_recording() {
+            self.stop_recording();
+        }
+        if mode != CaptureMode::Timelapse {
+            self.stop_timelapse();
+        }
+        self.capture_mode = mode;
+        self.update_pre_record();
+    }
+
+    /// フレームソースを開く
+    ///
+    /// 現在のソース種類に応じてフレームソースを作成して開き、
+    /// 実際に適用された解像度を保存してキャプチャスレッドを起動する。
+    /// カメラの場合は現在のカメラインデックスと動作モードを使用する。
+    ///
+    /// # 戻り値
+    /// 成功時は`Ok(())`、失敗時は`Err(CameraError)`
+    pub fn open(&mut self) -> Result<(), CameraError> {
+        let mode = self.requested_mode();
+        let mut source: Box<dyn FrameSource> = match &self.source_kind {
+            SourceKind::Camera => Box::new(CameraSource::new(
+                CameraDevice::Index(self.camera_index),
+                self.camera_backends.clone(),
+                mode,
+            )),
+            SourceKind::Device(path) => Box::new(CameraSource::new(
+                CameraDevice::Path(path.clone()),
+                self.camera_backends.clone(),
+                mode,
+            )),
+            SourceKind::TestPattern(config) => Box::new(SyntheticSource::new(*config)),
+            SourceKind::Playback(config) => config.create_source(),
+        };
+
+        source.open()?;
+        // 実際に設定された解像度を取得 (デバイスによっては異なる場合がある)
+        self.apply_properties(source.properties());
+        self.source_name = source.name();
+
+        // ソースの所有権をキャプチャスレッドに移す
+        let capture = CaptureThread::spawn(source, self.current_frame.clone());
+        // ソースの切り替えで一時停止していたタイムラプスを新しいソースで続ける
+        if let Some(tap) = self.suspended_timelapse.take() {
+            log::info!("タイムラプスを新しいソースで続けます");
+            capture.set_timelapse(Some(tap));
+        }
+        self.capture = Some(capture);
+        self.update_pre_record();
+        Ok(())
+    }
+
+    /// カメラを切り替える (フロント/リア/外部)
+    ///
+    /// 指定された役割に割り当てられたデバイスを、カメラソースとして開き直す。
+    pub fn switch_camera(&mut self, position: CameraPosition) -> Result<(), CameraError> {
+        self.camera_position = position;
+        self.camera_index = self.role_devices.get(position);
+
+        // 新しいカメラインデックスで再初期化
+        self.switch_source(SourceKind::Camera)
+    }
+
+    /// 選択したデバイスを現在の役割に割り当てて開く
+    pub fn select_device(&mut self, index: i32) -> Result<(), CameraError> {
+        self.role_devices.set(self.camera_position, index);
+        self.camera_index = index;
+        self.switch_source(SourceKind::Camera)
+    }
+
+    /// フレームソースを切り替える
+    ///
+    /// 録画中の場合は先に停止し、現在のソースを解放してから
+    /// 指定された種類のソースで開き直す。
+    /// タイムラプスの撮影中は新しいソースで撮影を続ける (開けなかった場合は
+    /// 次にソースを開くまで一時停止し、`is_timelapse_suspended`がtrueになる)。
+    pub fn switch_source(&mut self, kind: SourceKind) -> Result<(), CameraError> {
+        // 録画中の場合は停止 (ソース切り替え時に録画を継続できないため)
+        if self.is_recording() {
+            self.stop_recording();
+        }
+
+        // キャプチャスレッドを停止して現在のソースを解放
+        // (タイムラプスの選択器は新しいソースを開くまで預かる)
+        if let Some(mut capture) = self.capture.take() {
+            if let Some(tap) = capture.take_timelapse() {
+                self.suspended_timelapse = Some(tap);
+            }
+            capture.stop();
+        }
+        // 前のソースのフレームが残らないように表示をクリア
+        if let Ok(mut frame_lock) = self.current_frame.lock() {
+            *frame_lock = None;
+        }
+        // 検出結果は前のソースのものなので破棄
+        self.mode_probes.clear();
+        self.pending_mode_probe = None;
+        self.pending_mode_change = None;
+
+        self.source_kind = kind;
+        self.open().inspect_err(|_| {
+            if self.suspended_timelapse.is_some() {
+                log::warn!("ソースを開けないため、タイムラプスを一時停止しました");
+            }
+        })
+    }
+
+    /// カメラに要求する動作モード (現在の解像度と、選択したFPS・ピクセルフォーマット)
+    fn requested_mode(&self) -> VideoMode {
+        VideoMode {
+            width: self.frame_width,
+            height: self.frame_height,
+            fps: self.requested_fps,
+            fourcc: self.pixel_format,
+        }
+    }
+
+    /// ソースから取得したプロパティを反映 (録画の解像度・FPSもこの値を使用)
+    fn apply_properties(&mut self, props: SourceProperties) {
+        self.frame_width = props.width;
+        self.frame_height = props.height;
+        self.source_fps = props.fps;
+        self.source_fourcc = props.fourcc;
+    }
+
+    /// 動作モード (解像度・FPS・ピクセルフォーマット) を開いたまま変更
+    ///
+    /// 録画中の場合は先に停止する (録画中に解像度が変わるとVideoWriterに書き込めないため)。
+    /// 結果は`poll_mode_requests`で反映し、失敗した場合は`take_errors`で取り出せる。
+    pub fn change_mode(&mut self, mode: VideoMode) {
+        if self.is_recording() {
+            self.stop_recording();
+        }
+        if let Some(capture) = self.capture.as_ref() {
+            self.pending_mode_change = Some((mode, capture.apply_mode(mode)));
+        }
+    }
+
+    /// 現在のソースで候補のモードを検出 (結果は`poll_mode_requests`で反映)
+    pub fn probe_modes(&mut self) {
+        if self.is_recording() {
+            self.stop_recording();
+        }
+        if let Some(capture) = self.capture.as_ref() {
+            self.pending_mode_probe = Some(capture.probe_modes(candidate_modes()));
+        }
+    }
+
+    /// モード変更・モード検出が終わっていれば結果を反映
+    pub fn poll_mode_requests(&mut self) {
+        if let Some((mode, receiver)) = self.pending_mode_change.as_ref() {
+            let mode = *mode;
+            match receiver.try_recv() {
+                Ok(Ok(props)) => {
+                    self.pending_mode_change = None;
+                    // 開き直した場合も同じFPS・ピクセルフォーマットを要求する
+                    self.requested_fps = mode.fps;
+                    self.pixel_format = mode.fourcc;
+                    self.apply_properties(props);
+                    if mode.is_satisfied_by(&props) {
+                        log::info!("動作モードを変更しました: {}", mode.label());
+                    } else {
+                        log::warn!(
+                            "動作モードを変更しました: {} (要求: {})",
+                            VideoMode::from(props).label(),
+                            mode.label()
+                        );
+                    }
+                }
+                Ok(Err(e)) => {
+                    self.pending_mode_change = None;
+                    self.errors.push(e);
+                }
+                Err(TryRecvError::Disconnected) => self.pending_mode_change = None,
+                Err(TryRecvError::Empty) => {}
+            }
+        }
+
+        if let Some(receiver) = self.pending_mode_probe.as_ref() {
+            match receiver.try_recv() {
+                Ok(probes) => {
+                    self.pending_mode_probe = None;
+                    let supported = probes.iter().filter(|p| p.is_supported()).count();
+                    log::info!(
+                        "動作モードを検出しました: {}/{}件が要求どおりに適用されました",
+                        supported,
+                        probes.len()
+                    );
+                    self.mode_probes = probes;
+                }
+                Err(TryRecvError::Disconnected) => self.pending_mode_probe = None,
+                Err(TryRecvError::Empty) => {}
+            }
+        }
+    }
+
+    /// 写真の撮影を開始
+    ///
+    /// ボタンを押した時点でプレビューに表示中のフレーム (フル解像度) を、
+    /// ファイル名テンプレートに従った名前で、設定された形式 (JPEG/PNG/WebP/TIFF) で保存。
+    /// ベストショット選択が有効な場合は、続けて取得したフレームを含めて最もシャープなものを保存する。
+    /// ファイル名形式 (デフォルト): photo_YYYYMMDD_HHMMSS_mmm.jpg
+    /// 候補の選択と保存は専用スレッドで行い、完了は`poll_photo`で確認する。
+    ///
+    /// # 戻り値
+    /// 成功時は`Ok(())`、失敗時は`Err(CameraError)`
+    pub fn start_photo(&mut self) -> Result<(), CameraError> {
+        let Some(capture) = self.capture.as_ref() else {
+            return Err(CameraError::NotOpen);
+        };
+        if self.photo.is_some() {
+            return Err(CameraError::Unsupported(
+                "前の写真の保存が終わるまでお待ちください".to_string(),
+            ));
+        }
+
+        // テンプレートからファイル名を生成 (既存のファイルとは重ならない)
+        let extension = self.photo_options.format.extension();
+        let path = self.next_output_path(CaptureMode::Photo, extension)?;
+        let metadata = self.photo_metadata(self.frame_width, self.frame_height);
+
+        // 表示中のフレーム (起動直後でまだ無い場合は次のフレームを使う)
+        let displayed = capture.latest_frame();
+        let (session, tap) = PhotoSession::start(path, displayed, self.photo_options, metadata);
+        capture.set_photo(Some(tap));
+        self.photo = Some(session);
+        Ok(())
+    }
+
+    /// 写真の保存が終わっていれば結果を取り出す (UIの更新ごとに呼ぶ)
+    ///
+    /// # 戻り値
+    /// 保存が終わった場合は保存したファイルのパスまたはエラー、撮影していない場合や保存中の場合はNone
+    pub fn poll_photo(&mut self) -> Option<Result<PathBuf, CameraError>> {
+        if !self.photo.as_ref()?.is_finished() {
+            return None;
+        }
+        self.finish_photo()
+    }
+
+    /// 写真を保存し終えるまで待ち、結果を取り出す
+    ///
+    /// # 戻り値
+    /// 保存したファイルのパスまたはエラー (撮影していなかった場合や保存スレッドが異常終了した場合はNone)
+    pub fn finish_photo(&mut self) -> Option<Result<PathBuf, CameraError>> {
+        let result = self.photo.take()?.finish();
+        if result.is_none() {
+            log::error!("写真の保存スレッドが異常終了しました");
+        }
+        result
+    }
+
+    /// 写真を撮影し、保存し終えるまで待つ (ヘッドレスモード用)
+    ///
+    /// # 戻り値
+    /// 成功時は保存したファイルのパス、失敗時は`Err(CameraError)`
+    pub fn capture_photo(&mut self) -> Result<PathBuf, CameraError> {
+        self.start_photo()?;
+        self.finish_photo().unwrap_or_else(|| {
+            Err(CameraError::Conversion(
+                "写真の保存スレッドが異常終了しました".to_string(),
+            ))
+        })
+    }
+
+    /// 写真を保存中かどうか
+    pub fn is_saving_photo(&self) -> bool {
+        self.photo.is_some()
+    }
+
+    /// 連写を開始
+    ///
+    /// キャプチャスレッドが取得したフレームを撮影間隔ごとに選び、
+    /// 専用スレッドで`{ファイル名テンプレート}_001.jpg`からの連番で保存する。
+    /// 保存の完了は`poll_burst`で確認する。
+    ///
+    /// # 戻り値
+    /// 成功時は`Ok(())`、失敗時は`Err(CameraError)`
+    pub fn start_burst(&mut self) -> Result<(), CameraError> {
+        let Some(capture) = self.capture.as_ref() else {
+            return Err(CameraError::NotOpen);
+        };
+        if self.burst.is_some() {
+            return Err(CameraError::Unsupported(
+                "前の連写の保存が終わるまでお待ちください".to_string(),
+            ));
+        }
+
+        let stem = self.next_output_stem(CaptureMode::Burst)?;
+        let metadata = self.photo_metadata(self.frame_width, self.frame_height);
+        let (session, tap) = BurstSession::start(
+            &self.output_dir,
+            stem,
+            self.burst_options,
+            self.photo_options,
+            metadata,
+        );
+        capture.set_burst(Some(tap));
+        self.burst = Some(session);
+        log::info!(
+            "連写を開始しました ({}枚, 間隔: {}ms)",
+            self.burst_options.count,
+            self.burst_options.interval_ms
+        );
+        Ok(())
+    }
+
+    /// 連写の保存が終わっていれば集計結果を取り出す (UIの更新ごとに呼ぶ)
+    ///
+    /// # 戻り値
+    /// 保存が終わった場合は集計結果、連写していない場合や保存中の場合はNone
+    pub fn poll_burst(&mut self) -> Option<BurstSummary> {
+        if !self.burst.as_ref()?.is_finished() {
+            return None;
+        }
+        let summary = self.burst.take()?.finish();
+        match &summary {
+            Some(summary) => log::info!(
+                "連写を保存しました: {}枚 (失敗: {}枚)",
+                summary.paths.len(),
+                summary.errors.len()
+            ),
+            None => log::error!("連写の書き込みスレッドが異常終了しました"),
+        }
+        summary
+    }
+
+    /// 連写を打ち切り、選ばれたフレームを保存し終えるまで待つ
+    ///
+    /// # 戻り値
+    /// 集計結果 (連写していなかった場合や書き込みスレッドが異常終了した場合はNone)
+    pub fn finish_burst(&mut self) -> Option<BurstSummary> {
+        if let Some(capture) = self.capture.as_ref() {
+            capture.set_burst(None);
+        }
+        self.burst.take()?.finish()
+    }
+
+    /// タイムラプスを開始
+    ///
+    /// キャプチャスレッドが取得したフレームを撮影間隔ごとに選び、専用スレッドで
+    /// `{保存先}/{ファイル名テンプレート}/`のフォルダに連番で保存する。
+    /// 最後まで撮影すると (または`stop_timelapse`で打ち切ると) 動画を作成する。
+    /// 完了は`poll_timelapse`で確認する。
+    ///
+    /// # 戻り値
+    /// 成功時は`Ok(())`、失敗時は`Err(CameraError)`
+    pub fn start_timelapse(&mut self) -> Result<(), CameraError> {
+        // 同じ名前のフォルダ (再開できるタイムラプス等) を上書きしないように名前を変える
+        let stem = filename::unique_stem(
+            &self.output_dir,
+            &self.next_output_stem(CaptureMode::Timelapse)?,
+        );
+        let state = TimelapseState::new(
+            stem,
+            self.timelapse_options,
+            self.photo_options,
+            self.resolved_video_format(),
+        );
+        let output_dir = self.output_dir.clone();
+        self.run_timelapse(&output_dir, state, false)
+    }
+
+    /// 前回の起動で終わらなかったタイムラプスを続きから再開
+    ///
+    /// # 引数
+    /// * `dir` - フレームと途中経過を置いたフォルダ (`unfinished_timelapses`で取得)
+    pub fn resume_timelapse(&mut self, dir: &Path) -> Result<(), CameraError> {
+        let state = TimelapseState::load(dir)?;
+        self.run_timelapse(unfinished_output_dir(dir), state, false)
+    }
+
+    /// 前回の起動で終わらなかったタイムラプスを、撮影済みのフレームだけで仕上げる
+    ///
+    /// # 引数
+    /// * `dir` - フレームと途中経過を置いたフォルダ (`unfinished_timelapses`で取得)
+    pub fn finish_unfinished_timelapse(&mut self, dir: &Path) -> Result<(), CameraError> {
+        let state = TimelapseState::load(dir)?;
+        self.run_timelapse(unfinished_output_dir(dir), state, true)
+    }
+
+    /// 途中経過からタイムラプスの書き込みスレッドを起動
+    ///
+    /// `finish_now`がtrueの場合は撮影せずにすぐ仕上げる。
+    fn run_timelapse(
+        &mut self,
+        output_dir: &Path,
+        state: TimelapseState,
+        finish_now: bool,
+    ) -> Result<(), CameraError> {
+        if self.timelapse.is_some() {
+            return Err(CameraError::Unsupported(
+                "タイムラプスの撮影中・動画の作成中です".to_string(),
+            ));
+        }
+        let capture = match self.capture.as_ref() {
+            Some(capture) => Some(capture),
+            // 仕上げるだけの場合はカメラが開いていなくてよい
+            None if finish_now => None,
+            None => return Err(CameraError::NotOpen),
+        };
+
+        let metadata = self.photo_metadata(self.frame_width, self.frame_height);
+        let (captured, total) = (state.captured, state.total);
+        let interval_secs = state.options.interval_secs;
+        let (session, tap) = TimelapseSession::start(output_dir, state, metadata)?;
+        if finish_now {
+            session.request_finish();
+            log::info!("タイムラプスを仕上げます ({}/{}枚)", captured, total);
+        } else if let Some(capture) = capture {
+            capture.set_timelapse(Some(tap));
+            log::info!(
+                "タイムラプスを開始しました ({}/{}枚, 間隔: {}秒)",
+                captured,
+                total,
+                interval_secs
+            );
+        }
+        self.timelapse = Some(session);
+        Ok(())
+    }
+
+    /// タイムラプスを打ち切って仕上げる (動画の作成は書き込みスレッドで行い、`poll_timelapse`で確認する)
+    pub fn stop_timelapse(&mut self) {
+        let Some(session) = self.timelapse.as_ref() else {
+            return;
+        };
+        session.request_finish();
+        if let Some(capture) = self.capture.as_ref() {
+            capture.set_timelapse(None);
+        }
+        self.suspended_timelapse = None;
+    }
+
+    /// タイムラプスの書き込みスレッドが終わっていれば結果を取り出す (UIの更新ごとに呼ぶ)
+    ///
+    /// # 戻り値
+    /// 終わった場合は結果、タイムラプスを撮影していない場合や撮影・作成中の場合はNone
+    pub fn poll_timelapse(&mut self) -> Option<TimelapseSummary> {
+        if !self.timelapse.as_ref()?.is_finished() {
+            return None;
+        }
+        let summary = self.timelapse.take()?.finish();
+        if summary.is_none() {
+            log::error!("タイムラプスの書き込みスレッドが異常終了しました");
+        }
+        summary
+    }
+
+    /// 保存先にある、前回の起動で終わらなかったタイムラプス
+    pub fn unfinished_timelapses(&self) -> Vec<UnfinishedTimelapse> {
+        timelapse::find_unfinished(&self.output_dir)
+    }
+
+    /// 写真に埋め込むメタデータ (設定で無効にされている場合はNone)
+    fn photo_metadata(&self, width: i32, height: i32) -> Option<PhotoMetadata> {
+        let settings = &self.settings.metadata;
+        if !settings.embed {
+            return None;
+        }
+        Some(PhotoMetadata {
+            taken_at: Local::now(),
+            camera: self.source_name.clone(),
+            width: width.max(0) as u32,
+            height: height.max(0) as u32,
+            orientation: ORIENTATION_NORMAL,
+            software: SOFTWARE_NAME.to_string(),
+            gps: settings.gps(),
+            location: settings.location().map(str::to_string),
+            user_comment: settings.user_comment().map(str::to_string),
+        })
+    }
+
+    /// 保存先に、既存のファイルと重ならない新しいファイルのパスを決める
+    ///
+    /// 保存先ディレクトリが無い場合は作成し、連番を1つ進める。
+    fn next_output_path(&self, mode: CaptureMode, extension: &str) -> Result<PathBuf, CameraError> {
+        let stem = self.next_output_stem(mode)?;
+        Ok(filename::unique_path(&self.output_dir, &stem, extension))
+    }
+
+    /// ファイル名テンプレートから拡張子を除くファイル名を生成
+    ///
+    /// 保存先ディレクトリが無い場合は作成し、連番を1つ進める。
+    fn next_output_stem(&self, mode: CaptureMode) -> Result<String, CameraError> {
+        storage::ensure_dir(&self.output_dir)?;
+        let context = FilenameContext {
+            mode,
+            camera: &self.source_name,
+            width: self.frame_width,
+            height: self.frame_height,
+            sequence: self.file_sequence.fetch_add(1, Ordering::Relaxed) + 1,
+        };
+        Ok(self.filename_template.render(&context, Local::now()))
+    }
+
+    /// 動画録画を開始
+    ///
+    /// 録画セッションを作成し、選択した録画形式 (コーデック・コンテナ) で録画を開始する。
+    /// 録画スレッドはキャプチャスレッドから直接フレームを受け取るため、
+    /// 録画のフレームレートはUIの再描画頻度に依存しない。
+    /// 録画開始前のバッファが有効な場合は、保持していた直前の数秒間を先頭に書き込む。
+    /// 分割録画が有効な場合は、設定した時間・サイズごとに連番のファイルへ切り替える。
+    /// FPSはソースから取得し、不正な値の場合は30fpsをデフォルトとする。
+    ///
+    /// # 戻り値
+    /// 成功時は録画ファイル (分割録画の場合は最初のファイル) のパス、失敗時は`Err(CameraError)`
+    pub fn start_recording(&mut self) -> Result<PathBuf, CameraError> {
+        if self.capture.is_none() {
+            return Err(CameraError::NotOpen);
+        }
+        // モード変更中は解像度が確定していないため録画を開始しない
+        if self.pending_mode_change.is_some() || self.pending_mode_probe.is_some() {
+            return Err(CameraError::ModeChangeInProgress);
+        }
+
+        // ソースのFPSを取得 (不正な値の場合は30fpsをデフォルト)
+        let fps = if self.source_fps > MIN_FPS && self.source_fps <= MAX_FPS {
+            self.source_fps
+        } else {
+            DEFAULT_FPS
+        };
+        let frame_size = Size::new(self.frame_width, self.frame_height);
+
+        let pre_record = self.pre_record_duration().unwrap_or_default();
+        let (session, sender, preroll_sender) = self.open_recording(fps, frame_size, pre_record)?;
+        let Some(capture) = self.capture.as_ref() else {
+            return Err(CameraError::NotOpen);
+        };
+        // キャプチャスレッドから録画スレッドへのフレーム供給を開始し、
+        // それまでバッファに渡していたフレームを録画スレッドへ送る
+        capture.set_recorder(Some(sender));
+        capture.drain_pre_record(preroll_sender);
+        let path = session.path().to_path_buf();
+        self.recording = Some(session);
+        // 録画中フラグを立てる (アトミック操作)
+        self.is_recording.store(true, Ordering::Relaxed);
+        log::info!("録画を開始しました: {:?} ({}fps)", path, fps);
+        Ok(path)
+    }
+
+    /// 書き込める録画形式で録画セッションを作成
+    ///
+    /// 書き込めないと分かっている形式は使わず、代わりの形式を選ぶ (`VideoSupport::resolve`)。
+    /// 開けなかった形式は記録し、代わりの形式で開き直す。
+    ///
+    /// # 戻り値
+    /// 成功時は録画セッション、フレームの送信口、録画開始前のフレームの送信口。
+    /// どの形式でも開けなかった場合は最後のエラー
+    fn open_recording(
+        &mut self,
+        fps: f64,
+        frame_size: Size,
+        pre_record: Duration,
+    ) -> Result<OpenedRecording, CameraError> {
+        let preferred = self.video_format();
+        // テンプレートからファイル名を生成 (形式を変えて開き直す場合も連番は進めない)
+        let stem = self.next_output_stem(CaptureMode::Video)?;
+        let mut tried = Vec::new();
+        loop {
+            let format = self.video_support.resolve(preferred);
+            // 拡張子だけ形式に合わせる (既存のファイルとは重ならない)
+            let filename =
+                filename::unique_path(&self.output_dir, &stem, format.container.extension());
+            let (preroll_sender, preroll) = mpsc::channel();
+            match RecordingSession::start(
+                &filename,
+                fps,
+                frame_size,
+                format.codec,
+                self.segment_options,
+                pre_record,
+                preroll,
+            ) {
+                Ok((session, sender)) => {
+                    // 指定したコーデックで開けずMJPGで開いた場合は、両方の結果を記録
+                    let opened = VideoFormat {
+                        codec: session.codec(),
+                        container: format.container,
+                    };
+                    if opened != format {
+                        self.video_support.record(format, false);
+                    }
+                    self.video_support.record(opened, true);
+                    if opened != preferred {
+                        log::warn!(
+                            "録画形式 {} で書き込めないため {} で録画します",
+                            preferred,
+                            opened
+                        );
+                    }
+                    return Ok((session, sender, preroll_sender));
+                }
+                Err(e @ CameraError::WriterOpen { .. }) => {
+                    // 指定したコーデックもMJPGも開けなかった
+                    self.video_support.record(format, false);
+                    self.video_support.record(
+                        VideoFormat {
+                            codec: FourCc::MJPG,
+                            container: format.container,
+                        },
+                        false,
+                    );
+                    tried.push(format);
+                    if tried.contains(&self.video_support.resolve(preferred)) {
+                        return Err(e);
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// 動画録画を停止
+    ///
+    /// 録画スレッドへのフレーム供給を止め、残りのフレームの書き込みと
+    /// ファイルのクローズが終わるまで待つ。
+    /// 書き込みに失敗したフレームがあった場合や録画スレッドが異常終了した場合は、
+    /// エラーを記録する (`take_errors`で取り出せる)。
+    ///
+    /// # 戻り値
+    /// 録画の集計結果 (録画していなかった場合や録画スレッドが異常終了した場合はNone)
+    pub fn stop_recording(&mut self) -> Option<RecordingSummary> {
+        // 送信口を破棄すると録画スレッドはキューを書き切って終了する
+        if let Some(capture) = self.capture.as_ref() {
+            capture.set_recorder(None);
+        }
+
+        let session = self.recording.take()?;
+        let path = session.path().to_path_buf();
+        let mut summary = session.finish();
+        // 録画中フラグを下ろす (アトミック操作)
+        self.is_recording.store(false, Ordering::Relaxed);
+        match summary.as_mut() {
+            Some(summary) => {
+                log::info!(
+                    "録画を停止しました: {:?} ({}ファイル, {}フレーム, 録画開始前: {}, 複製: {}, 破棄: {}, 取りこぼし: {})",
+                    path,
+                    summary.segments.len(),
+                    summary.frames_written,
+                    summary.frames_prerolled,
+                    summary.frames_duplicated,
+                    summary.frames_dropped,
+                    summary.frames_missed
+                );
+                if summary.frames_failed > 0 {
+                    self.errors.push(CameraError::FrameWrite {
+                        path,
+                        count: summary.frames_failed,
+                    });
+                }
+                if let Some(e) = summary.split_error.take() {
+                    self.errors.push(e);
+                }
+            }
+            None => self.errors.push(CameraError::RecorderFailed(path)),
+        }
+        summary
+    }
+
+    /// バックグラウンドで発生したエラーを取り出す (取り出したエラーは消える)
+    ///
+    /// モード変更・録画の停止・キャプチャスレッドでのフレーム変換の失敗が含まれる。
+    pub fn take_errors(&mut self) -> Vec<CameraError> {
+        let mut errors = std::mem::take(&mut self.errors);
+        if let Some(capture) = self.capture.as_ref() {
+            errors.extend(capture.take_errors());
+        }
+        errors
+    }
+
+    /// 最新のプレビュー画像 (キャプチャスレッドが格納したものの複製)
+    pub fn preview_frame(&self) -> Option<egui::ColorImage> {
+        self.current_frame.lock().ok().and_then(|lock| lock.clone())
+    }
+
+    /// フレームソースが開いているかどうか
+    pub fn is_open(&self) -> bool {
+        self.capture.is_some()
+    }
+
+    /// 録画中かどうか
+    pub fn is_recording(&self) -> bool {
+        self.is_recording.load(Ordering::Relaxed)
+    }
+
+    /// 書き込み中の録画ファイルが何番目か (1始まり、録画していない場合はNone)
+    pub fn recording_segment(&self) -> Option<u32> {
+        self.recording.as_ref().map(RecordingSession::segment_index)
+    }
+
+    /// 選択した録画形式
+    pub fn video_format(&self) -> VideoFormat {
+        VideoFormat {
+            codec: self.video_codec,
+            container: self.video_container,
+        }
+    }
+
+    /// 録画開始時に実際に使う録画形式 (選択した形式が書き込めない場合は代わりの形式)
+    pub fn resolved_video_format(&self) -> VideoFormat {
+        self.video_support.resolve(self.video_format())
+    }
+
+    /// この環境で書き込めた・書き込めなかった録画形式
+    pub fn video_support(&self) -> &VideoSupport {
+        &self.video_support
+    }
+
+    /// 録画形式を検出中かどうか
+    pub fn is_probing_video_formats(&self) -> bool {
+        self.pending_format_probe.is_some()
+    }
+
+    /// 分割録画の設定
+    pub fn segment_options(&self) -> &SegmentOptions {
+        &self.segment_options
+    }
+
+    /// 現在のキャプチャモード
+    pub fn capture_mode(&self) -> CaptureMode {
+        self.capture_mode
+    }
+
+    /// 現在のカメラポジション
+    pub fn camera_position(&self) -> CameraPosition {
+        self.camera_position
+    }
+
+    /// 現在のカメラデバイスのインデックス
+    pub fn camera_index(&self) -> i32 {
+        self.camera_index
+    }
+
+    /// カメラを開く際に試すバックエンド
+    pub fn camera_backends(&self) -> &[i32] {
+        &self.camera_backends
+    }
+
+    /// 使用中のフレームソースの種類
+    pub fn source_kind(&self) -> &SourceKind {
+        &self.source_kind
+    }
+
+    /// 現在のフレームサイズ (幅, 高さ)
+    pub fn frame_size(&self) -> (i32, i32) {
+        (self.frame_width, self.frame_height)
+    }
+
+    /// 現在開いているフレームソースの表示名
+    pub fn source_name(&self) -> &str {
+        &self.source_name
+    }
+
+    /// フレームソースが申告するプロパティ (解像度・FPS・ピクセルフォーマット)
+    pub fn source_properties(&self) -> SourceProperties {
+        SourceProperties {
+            width: self.frame_width,
+            height: self.frame_height,
+            fps: self.source_fps,
+            fourcc: self.source_fourcc,
+        }
+    }
+
+    /// 計測したキャプチャFPS
+    pub fn capture_fps(&self) -> f64 {
+        self.capture.as_ref().map_or(0.0, |c| c.capture_fps())
+    }
+
+    /// 写真・動画の保存先ディレクトリ
+    pub fn output_dir(&self) -> &Path {
+        &self.output_dir
+    }
+
+    /// 写真の保存形式と画質設定
+    pub fn photo_options(&self) -> &PhotoOptions {
+        &self.photo_options
+    }
+
+    /// 連写の枚数と間隔
+    pub fn burst_options(&self) -> &BurstOptions {
+        &self.burst_options
+    }
+
+    /// 連写の進捗、連写していない場合はNone
+    pub fn burst_progress(&self) -> Option<BurstProgress> {
+        self.burst.as_ref().map(|burst| burst.progress())
+    }
+
+    /// タイムラプスの撮影間隔・終了条件・動画の設定
+    pub fn timelapse_options(&self) -> &TimelapseOptions {
+        &self.timelapse_options
+    }
+
+    /// ソースを開けないため、タイムラプスの撮影を一時停止しているかどうか
+    pub fn is_timelapse_suspended(&self) -> bool {
+        self.suspended_timelapse.is_some()
+    }
+
+    /// タイムラプスの進捗、タイムラプスを撮影していない場合はNone
+    pub fn timelapse_progress(&self) -> Option<TimelapseProgress> {
+        self.timelapse
+            .as_ref()
+            .map(|timelapse| timelapse.progress())
+    }
+
+    /// 録画開始前の何秒間を録画に含めるか (0は無効)
+    pub fn pre_record_secs(&self) -> u32 {
+        self.pre_record_secs
+    }
+
+    /// セルフタイマーの秒数 (0は無効)
+    pub fn self_timer_secs(&self) -> u32 {
+        self.self_timer_secs
+    }
+
+    /// 検出済みの動作モード
+    pub fn mode_probes(&self) -> &[ModeProbe] {
+        &self.mode_probes
+    }
+
+    /// モード検出中かどうか
+    pub fn is_probing_modes(&self) -> bool {
+        self.pending_mode_probe.is_some()
+    }
+
+    /// モード変更中かどうか
+    pub fn is_changing_mode(&self) -> bool {
+        self.pending_mode_change.is_some()
+    }
+}
+
+/// 終わらなかったタイムラプスのフォルダから、元の保存先を求める
+///
+/// 保存先の設定が変わっていても、動画は撮影を始めたときの保存先に作成する。
+fn unfinished_output_dir(dir: &Path) -> &Path {
+    dir.parent().unwrap_or(Path::new("."))
+}
+
+impl Drop for CameraSession {
+    fn drop(&mut self) {
+        // 録画中の場合は停止 (ファイルを正常にクローズするため)
+        if self.is_recording() {
+            self.stop_recording();
+        }
+        // 保存中の写真・連写は保存し終えるまで待つ
+        self.finish_photo();
+        self.finish_burst();
+        // タイムラプスの撮影中の場合は途中経過を残して中断する (次回の起動で再開できる)
+        if let Some(capture) = self.capture.as_ref() {
+            capture.set_timelapse(None);
+        }
+        self.suspended_timelapse = None;
+        if let Some(timelapse) = self.timelapse.take() {
+            timelapse.finish();
+        }
+    }
+}
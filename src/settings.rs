@@ -0,0 +1,585 @@
+// 標準ライブラリのインポート
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+// サードパーティクレートのインポート
+use log::LevelFilter;
+use serde::{Deserialize, Serialize};
+
+use crate::burst::BurstOptions;
+use crate::codec::{VideoContainer, VideoSupport};
+use crate::filename::{FilenameTemplate, DEFAULT_FILENAME_TEMPLATE};
+use crate::logging::{self, DEFAULT_LOG_LEVEL};
+use crate::metadata::GpsPosition;
+use crate::photo::PhotoOptions;
+use crate::prerecord::MAX_PRE_RECORD_SECS;
+use crate::segment::SegmentOptions;
+use crate::timelapse::TimelapseOptions;
+use crate::{CameraPosition, CaptureMode};
+
+/// 設定ファイルのスキーマバージョン (フィールドの意味を変えた場合に上げる)
+pub const SETTINGS_VERSION: u32 = 1;
+/// 設定ファイルを置くディレクトリ名 (プラットフォームの設定ディレクトリ配下)
+const APP_DIR_NAME: &str = "camera_app";
+/// 設定ファイル名
+const SETTINGS_FILE_NAME: &str = "settings.toml";
+/// 読み込めなかった設定ファイルを退避する際の拡張子
+const CORRUPT_EXTENSION: &str = "toml.corrupt";
+/// 書き込み途中のファイルの拡張子 (書き込み後に置き換える)
+const TEMP_EXTENSION: &str = "toml.tmp";
+
+/// 録画コーデックのデフォルト値 (FOURCC)
+pub const DEFAULT_VIDEO_CODEC: &str = "mp4v";
+/// 保存先ディレクトリのデフォルト値
+pub const DEFAULT_OUTPUT_DIR: &str = "camera_output";
+/// 解像度のデフォルト値 (640x480は互換性が高い)
+pub const DEFAULT_FRAME_WIDTH: i32 = 640;
+/// 解像度のデフォルト値 (高さ)
+pub const DEFAULT_FRAME_HEIGHT: i32 = 480;
+/// セルフタイマーの上限 (秒)
+pub const MAX_SELF_TIMER_SECS: u32 = 60;
+/// 写真に埋め込む撮影場所の説明・コメントの最大文字数 (JPEGのセグメントの上限に収めるため)
+const MAX_METADATA_TEXT_CHARS: usize = 1000;
+
+/// 永続化するアプリケーション設定
+///
+/// 欠けているフィールドはデフォルト値で補う (古い設定ファイルも読み込めるように)。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// スキーマバージョン
+    pub version: u32,
+    /// カメラの設定
+    pub camera: CameraSettings,
+    /// 撮影・録画の設定
+    pub capture: CaptureSettings,
+    /// 保存先の設定
+    pub output: OutputSettings,
+    /// 写真に埋め込むメタデータの設定
+    pub metadata: MetadataSettings,
+    /// ログの設定
+    pub log: LogSettings,
+    /// この環境で書き込めた・書き込めなかった録画形式 (自動で記録する)
+    pub video_support: VideoSupport,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            camera: CameraSettings::default(),
+            capture: CaptureSettings::default(),
+            output: OutputSettings::default(),
+            metadata: MetadataSettings::default(),
+            log: LogSettings::default(),
+            video_support: VideoSupport::default(),
+        }
+    }
+}
+
+/// カメラの設定
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraSettings {
+    /// 使用するカメラの役割 (リア/フロント/外部)
+    pub position: CameraPosition,
+    /// 役割ごとに割り当てたカメラインデックス
+    pub roles: RoleDevices,
+    /// 試行するバックエンド名 (空の場合はプラットフォームのデフォルト)
+    pub backends: Vec<String>,
+    /// 要求する解像度 (幅)
+    pub width: i32,
+    /// 要求する解像度 (高さ)
+    pub height: i32,
+    /// 要求するFPS (未指定の場合はドライバーのデフォルト)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fps: Option<f64>,
+    /// 要求するピクセルフォーマット (例: `MJPG`、未指定の場合はドライバーのデフォルト)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pixel_format: Option<String>,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            position: CameraPosition::Rear,
+            roles: RoleDevices::default(),
+            backends: Vec::new(),
+            width: DEFAULT_FRAME_WIDTH,
+            height: DEFAULT_FRAME_HEIGHT,
+            fps: None,
+            pixel_format: None,
+        }
+    }
+}
+
+/// 役割ごとに割り当てたカメラインデックス
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoleDevices {
+    /// リアカメラ
+    pub rear: i32,
+    /// フロントカメラ
+    pub front: i32,
+    /// 外付けカメラ
+    pub external: i32,
+}
+
+impl RoleDevices {
+    /// 役割に割り当てたカメラインデックス
+    pub fn get(&self, position: CameraPosition) -> i32 {
+        match position {
+            CameraPosition::Rear => self.rear,
+            CameraPosition::Front => self.front,
+            CameraPosition::External => self.external,
+        }
+    }
+
+    /// 役割にカメラインデックスを割り当てる
+    pub fn set(&mut self, position: CameraPosition, index: i32) {
+        match position {
+            CameraPosition::Rear => self.rear = index,
+            CameraPosition::Front => self.front = index,
+            CameraPosition::External => self.external = index,
+        }
+    }
+}
+
+impl Default for RoleDevices {
+    fn default() -> Self {
+        Self {
+            rear: CameraPosition::Rear.default_index(),
+            front: CameraPosition::Front.default_index(),
+            external: CameraPosition::External.default_index(),
+        }
+    }
+}
+
+/// 撮影・録画の設定
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptureSettings {
+    /// 起動時のキャプチャモード
+    pub mode: CaptureMode,
+    /// 写真の保存形式と画質設定 (`photo_format`, `jpeg_quality`等を`[capture]`に直接書く)
+    #[serde(flatten)]
+    pub photo: PhotoOptions,
+    /// 連写の枚数と間隔 (`burst_count`, `burst_interval_ms`を`[capture]`に直接書く)
+    #[serde(flatten)]
+    pub burst: BurstOptions,
+    /// タイムラプスの撮影間隔・終了条件・動画の設定 (`timelapse_*`を`[capture]`に直接書く)
+    #[serde(flatten)]
+    pub timelapse: TimelapseOptions,
+    /// 録画コーデック (FOURCC、例: `mp4v`, `H264`, `XVID`, `MJPG`)
+    pub video_codec: String,
+    /// 録画ファイルのコンテナ形式 (`mp4`, `avi`, `mkv`)
+    pub video_container: VideoContainer,
+    /// 分割録画の時間・サイズと一覧ファイル (`segment_*`を`[capture]`に直接書く)
+    #[serde(flatten)]
+    pub segment: SegmentOptions,
+    /// セルフタイマーの秒数 (0は無効)
+    pub self_timer_secs: u32,
+    /// 録画開始前の何秒間を録画に含めるか (0は無効)
+    pub pre_record_secs: u32,
+}
+
+impl Default for CaptureSettings {
+    fn default() -> Self {
+        Self {
+            mode: CaptureMode::Photo,
+            photo: PhotoOptions::default(),
+            burst: BurstOptions::default(),
+            timelapse: TimelapseOptions::default(),
+            video_codec: DEFAULT_VIDEO_CODEC.to_string(),
+            video_container: VideoContainer::default(),
+            segment: SegmentOptions::default(),
+            self_timer_secs: 0,
+            pre_record_secs: 0,
+        }
+    }
+}
+
+/// 保存先の設定
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputSettings {
+    /// 写真・動画の保存先ディレクトリ
+    pub dir: PathBuf,
+    /// ファイル名テンプレート (拡張子を除く、例: `{mode}_{date}_{time}_{ms}`)
+    pub filename_template: String,
+}
+
+impl Default for OutputSettings {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from(DEFAULT_OUTPUT_DIR),
+            filename_template: DEFAULT_FILENAME_TEMPLATE.to_string(),
+        }
+    }
+}
+
+/// 写真に埋め込むメタデータ (EXIF・XMP) の設定
+///
+/// 撮影日時・カメラ名・解像度等は自動で記録し、ここでは利用者が指定する項目を設定する。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetadataSettings {
+    /// 写真 (JPEG) にメタデータを書き込むかどうか
+    pub embed: bool,
+    /// 撮影場所の緯度 (経度と両方指定した場合のみ記録)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    /// 撮影場所の経度
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+    /// 撮影場所の説明 (空の場合は記録しない)
+    pub location: String,
+    /// コメント (空の場合は記録しない)
+    pub user_comment: String,
+}
+
+impl Default for MetadataSettings {
+    fn default() -> Self {
+        Self {
+            embed: true,
+            latitude: None,
+            longitude: None,
+            location: String::new(),
+            user_comment: String::new(),
+        }
+    }
+}
+
+impl MetadataSettings {
+    /// 撮影場所の緯度・経度 (両方指定されている場合のみ)
+    pub fn gps(&self) -> Option<GpsPosition> {
+        GpsPosition::new(self.latitude?, self.longitude?)
+    }
+
+    /// 撮影場所の説明 (空の場合はNone)
+    pub fn location(&self) -> Option<&str> {
+        Some(self.location.trim()).filter(|text| !text.is_empty())
+    }
+
+    /// コメント (空の場合はNone)
+    pub fn user_comment(&self) -> Option<&str> {
+        Some(self.user_comment.trim()).filter(|text| !text.is_empty())
+    }
+}
+
+/// ログの設定
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogSettings {
+    /// 出力するログレベル (`off`, `error`, `warn`, `info`, `debug`, `trace`)
+    pub level: String,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        Self {
+            level: DEFAULT_LOG_LEVEL.to_string().to_lowercase(),
+        }
+    }
+}
+
+impl LogSettings {
+    /// ログレベル (不正な値の場合はデフォルト値)
+    pub fn level_filter(&self) -> LevelFilter {
+        logging::parse_level(&self.level).unwrap_or(DEFAULT_LOG_LEVEL)
+    }
+}
+
+impl Settings {
+    /// 範囲外の値をデフォルト値・上下限に置き換える (手で編集された場合に備えて)
+    fn sanitize(&mut self) {
+        if self.camera.width <= 0 || self.camera.height <= 0 {
+            self.camera.width = DEFAULT_FRAME_WIDTH;
+            self.camera.height = DEFAULT_FRAME_HEIGHT;
+        }
+        self.camera.fps = self.camera.fps.filter(|fps| *fps > 0.0);
+        self.capture.photo.clamp();
+        self.capture.burst.clamp();
+        self.capture.timelapse.clamp();
+        self.capture.segment.clamp();
+        self.capture.self_timer_secs = self.capture.self_timer_secs.min(MAX_SELF_TIMER_SECS);
+        self.capture.pre_record_secs = self.capture.pre_record_secs.min(MAX_PRE_RECORD_SECS);
+        if self.output.dir.as_os_str().is_empty() {
+            self.output.dir = PathBuf::from(DEFAULT_OUTPUT_DIR);
+        }
+        if let Err(e) = FilenameTemplate::parse(&self.output.filename_template) {
+            log::warn!("設定ファイルのファイル名テンプレートを無視します: {}", e);
+            self.output.filename_template = DEFAULT_FILENAME_TEMPLATE.to_string();
+        }
+        let has_position = self.metadata.latitude.is_some() || self.metadata.longitude.is_some();
+        if has_position && self.metadata.gps().is_none() {
+            log::warn!(
+                "設定ファイルの撮影場所の緯度・経度を無視します (両方を範囲内で指定してください): {:?}, {:?}",
+                self.metadata.latitude,
+                self.metadata.longitude
+            );
+            self.metadata.latitude = None;
+            self.metadata.longitude = None;
+        }
+        for text in [&mut self.metadata.location, &mut self.metadata.user_comment] {
+            if text.chars().count() > MAX_METADATA_TEXT_CHARS {
+                log::warn!(
+                    "設定ファイルのメタデータが長すぎるため、{}文字に切り詰めます",
+                    MAX_METADATA_TEXT_CHARS
+                );
+                *text = text.chars().take(MAX_METADATA_TEXT_CHARS).collect();
+            }
+        }
+        if let Err(e) = logging::parse_level(&self.log.level) {
+            log::warn!("設定ファイルのログレベルを無視します: {}", e);
+            self.log = LogSettings::default();
+        }
+    }
+}
+
+/// 設定ファイルの読み書き
+///
+/// 保存は前回保存した内容から変化があった場合のみ行うため、頻繁に呼んでもよい。
+pub struct SettingsStore {
+    /// 設定ファイルのパス (設定ディレクトリが無い環境ではNone: 保存しない)
+    path: Option<PathBuf>,
+    /// 最後に読み込んだ・保存した内容
+    saved: Option<Settings>,
+    /// 保存してよいかどうか (新しいバージョンの設定ファイルは上書きしない)
+    writable: bool,
+}
+
+impl Default for SettingsStore {
+    /// 保存先を持たないストア (読み込み・保存を行わない)
+    fn default() -> Self {
+        Self {
+            path: None,
+            saved: None,
+            writable: false,
+        }
+    }
+}
+
+impl SettingsStore {
+    /// 指定したパスの設定ファイルを使用
+    pub fn at(path: PathBuf) -> Self {
+        Self {
+            path: Some(path),
+            saved: None,
+            writable: true,
+        }
+    }
+
+    /// プラットフォームの設定ディレクトリの設定ファイルを使用
+    ///
+    /// Windows: `%APPDATA%\camera_app\settings.toml`、
+    /// Linux: `~/.config/camera_app/settings.toml`、
+    /// macOS: `~/Library/Application Support/camera_app/settings.toml`
+    pub fn open_default() -> Self {
+        match dirs::config_dir() {
+            Some(dir) => Self::at(dir.join(APP_DIR_NAME).join(SETTINGS_FILE_NAME)),
+            None => Self::default(),
+        }
+    }
+
+    /// 以降の保存を行わない (一時的な上書きを設定ファイルに残さないため)
+    pub fn disable_saving(&mut self) {
+        self.writable = false;
+    }
+
+    /// 設定ファイルのパス
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// 設定を読み込む
+    ///
+    /// ファイルが無い場合はデフォルト設定を返す。
+    /// 壊れている場合は`settings.toml.corrupt`に退避してデフォルト設定を返す
+    /// (次回の保存で正しい設定ファイルが作られる)。
+    pub fn load(&mut self) -> Settings {
+        let Some(path) = self.path.clone() else {
+            return Settings::default();
+        };
+
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Settings::default(),
+            Err(e) => {
+                log::error!("設定ファイルを読み込めませんでした: {:?} ({})", path, e);
+                // 読めないファイルを上書きしないように保存を止める
+                self.writable = false;
+                return Settings::default();
+            }
+        };
+
+        let mut settings = match toml::from_str::<Settings>(&text) {
+            Ok(settings) => settings,
+            Err(e) => {
+                let backup = path.with_extension(CORRUPT_EXTENSION);
+                log::warn!(
+                    "設定ファイルが壊れているため、デフォルト設定を使用します: {:?} ({})",
+                    path,
+                    e
+                );
+                match fs::rename(&path, &backup) {
+                    Ok(()) => log::info!("壊れた設定ファイルを退避しました: {:?}", backup),
+                    Err(e) => {
+                        log::error!("設定ファイルを退避できませんでした: {}", e);
+                        self.writable = false;
+                    }
+                }
+                return Settings::default();
+            }
+        };
+
+        if settings.version > SETTINGS_VERSION {
+            // 新しいバージョンのアプリで保存された設定: 読める項目だけ使い、上書きはしない
+            log::warn!(
+                "設定ファイルのバージョン ({}) がこのアプリ ({}) より新しいため、保存しません",
+                settings.version,
+                SETTINGS_VERSION
+            );
+            self.writable = false;
+        }
+        settings.version = SETTINGS_VERSION;
+        settings.sanitize();
+
+        self.saved = Some(settings.clone());
+        settings
+    }
+
+    /// 前回から変化があれば設定を保存
+    ///
+    /// 書き込み途中で終了してもファイルが壊れないように、
+    /// 一時ファイルに書き込んでから置き換える。
+    pub fn save(&mut self, settings: &Settings) -> Result<(), String> {
+        let Some(path) = self.path.as_ref() else {
+            return Ok(());
+        };
+        if !self.writable || self.saved.as_ref() == Some(settings) {
+            return Ok(());
+        }
+
+        let text = toml::to_string_pretty(settings)
+            .map_err(|e| format!("設定の変換に失敗しました: {}", e))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("設定ディレクトリを作成できませんでした: {}", e))?;
+        }
+        let temp = path.with_extension(TEMP_EXTENSION);
+        fs::write(&temp, text).map_err(|e| format!("設定ファイルを書き込めませんでした: {}", e))?;
+        fs::rename(&temp, path)
+            .map_err(|e| format!("設定ファイルを置き換えられませんでした: {}", e))?;
+
+        self.saved = Some(settings.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // サードパーティクレートのインポート
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::photo::MAX_JPEG_QUALITY;
+
+    /// 一時ディレクトリの設定ファイルを使うストア
+    fn store() -> Result<(TempDir, SettingsStore), String> {
+        let dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+        let store = SettingsStore::at(dir.path().join(SETTINGS_FILE_NAME));
+        Ok((dir, store))
+    }
+
+    #[test]
+    fn missing_file_loads_defaults() -> Result<(), String> {
+        let (_dir, mut store) = store()?;
+        assert_eq!(store.load(), Settings::default());
+        Ok(())
+    }
+
+    #[test]
+    fn corrupt_file_loads_defaults_and_is_kept_aside() -> Result<(), String> {
+        let (dir, mut store) = store()?;
+        let path = dir.path().join(SETTINGS_FILE_NAME);
+        fs::write(&path, "[camera\nwidth = ").map_err(|e| e.to_string())?;
+
+        assert_eq!(store.load(), Settings::default());
+        assert!(!path.exists());
+        let backup = path.with_extension(CORRUPT_EXTENSION);
+        assert_eq!(
+            fs::read_to_string(backup).map_err(|e| e.to_string())?,
+            "[camera\nwidth = "
+        );
+
+        // 次回の保存で正しい設定ファイルが作られる
+        let mut settings = Settings::default();
+        settings.capture.photo.jpeg_quality = 80;
+        store.save(&settings)?;
+        assert!(path.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn newer_version_is_loaded_but_not_overwritten() -> Result<(), String> {
+        let (dir, mut store) = store()?;
+        let path = dir.path().join(SETTINGS_FILE_NAME);
+        let text = format!(
+            "version = {}\nfuture_option = true\n\n[camera]\nwidth = 1280\nheight = 720\n",
+            SETTINGS_VERSION + 1
+        );
+        fs::write(&path, &text).map_err(|e| e.to_string())?;
+
+        let mut settings = store.load();
+        assert_eq!((settings.camera.width, settings.camera.height), (1280, 720));
+
+        settings.camera.width = 1920;
+        settings.camera.height = 1080;
+        store.save(&settings)?;
+        assert_eq!(fs::read_to_string(&path).map_err(|e| e.to_string())?, text);
+        Ok(())
+    }
+
+    #[test]
+    fn out_of_range_values_are_sanitized() -> Result<(), String> {
+        let (dir, mut store) = store()?;
+        fs::write(
+            dir.path().join(SETTINGS_FILE_NAME),
+            "[camera]\nwidth = -1\nheight = 720\nfps = -30.0\n\n\
+             [capture]\njpeg_quality = 250\n\n[output]\ndir = \"\"\n",
+        )
+        .map_err(|e| e.to_string())?;
+
+        let settings = store.load();
+        assert_eq!(
+            (settings.camera.width, settings.camera.height),
+            (DEFAULT_FRAME_WIDTH, DEFAULT_FRAME_HEIGHT)
+        );
+        assert_eq!(settings.camera.fps, None);
+        assert_eq!(settings.capture.photo.jpeg_quality, MAX_JPEG_QUALITY);
+        assert_eq!(settings.output.dir, PathBuf::from(DEFAULT_OUTPUT_DIR));
+        Ok(())
+    }
+
+    #[test]
+    fn save_and_load_round_trip() -> Result<(), String> {
+        let (dir, mut store) = store()?;
+        let mut settings = Settings::default();
+        settings.camera.position = CameraPosition::Front;
+        settings.camera.fps = Some(30.0);
+        settings.camera.pixel_format = Some("MJPG".to_string());
+        settings.capture.photo.jpeg_quality = 70;
+        settings.output.dir = PathBuf::from("photos");
+        store.save(&settings)?;
+
+        // 一時ファイルは残らない
+        let path = dir.path().join(SETTINGS_FILE_NAME);
+        assert!(!path.with_extension(TEMP_EXTENSION).exists());
+
+        let mut reopened = SettingsStore::at(path);
+        assert_eq!(reopened.load(), settings);
+        Ok(())
+    }
+}
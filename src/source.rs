@@ -0,0 +1,101 @@
+// サードパーティクレートのインポート
+use opencv::core::Mat;
+
+use crate::error::CameraError;
+
+mod camera;
+mod mode;
+mod pacing;
+mod playback;
+mod synthetic;
+
+pub use camera::{backend_name, default_backends, parse_backends, CameraDevice, CameraSource};
+pub use mode::{candidate_modes, FourCc, ModeProbe, VideoMode};
+pub use playback::{PlaybackConfig, PlaybackMode};
+pub use synthetic::{SyntheticSource, TestPatternConfig};
+
+/// 使用するフレームソースの種類
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourceKind {
+    /// 実カメラ (デバイスインデックスは別途指定)
+    Camera,
+    /// デバイスパス (`/dev/videoN`) またはGStreamerパイプラインで指定した実カメラ
+    Device(String),
+    /// 合成テストパターン (カメラ無しの環境向け)
+    TestPattern(TestPatternConfig),
+    /// 動画ファイルまたは画像フォルダの再生
+    Playback(PlaybackConfig),
+}
+
+/// フレームソースのプロパティ (実際に適用された値)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SourceProperties {
+    /// フレームの幅 (ピクセル)
+    pub width: i32,
+    /// フレームの高さ (ピクセル)
+    pub height: i32,
+    /// フレームレート (取得できない場合は0.0)
+    pub fps: f64,
+    /// ピクセルフォーマット (取得できない場合はNone)
+    pub fourcc: Option<FourCc>,
+}
+
+/// フレームソース: カメラや動画ファイルなど、フレームを供給するものの共通インターフェース
+///
+/// CameraAppはこのトレイトを通してのみフレームを取得するため、
+/// 実カメラ以外のソース (動画ファイル、合成映像等) に差し替えることができる。
+/// キャプチャスレッドへ渡せるように`Send`を要求する。
+pub trait FrameSource: Send {
+    /// ソースを開く
+    ///
+    /// # 戻り値
+    /// 成功時は`Ok(())`、失敗時は`Err(CameraError)`
+    fn open(&mut self) -> Result<(), CameraError>;
+
+    /// 1フレームを読み取る (BGR形式)
+    ///
+    /// # 戻り値
+    /// フレームを取得できた場合は`Ok(true)`、
+    /// 返すフレームが無い場合 (開いていない・再生が終了した) は`Ok(false)`、
+    /// 読み取りに失敗した場合は`Err(CameraError::FrameRead)`
+    fn read(&mut self, frame: &mut Mat) -> Result<bool, CameraError>;
+
+    /// 現在のプロパティ (解像度・FPS) を取得
+    fn properties(&self) -> SourceProperties;
+
+    /// 動作モード (解像度・FPS・ピクセルフォーマット) を開いたまま変更
+    ///
+    /// # 戻り値
+    /// 成功時は実際に適用されたプロパティ。
+    /// モード変更に対応していないソースは`Err(CameraError::Unsupported)`
+    fn apply_mode(&mut self, _mode: &VideoMode) -> Result<SourceProperties, CameraError> {
+        Err(CameraError::Unsupported(format!(
+            "{}はモード変更に対応していません",
+            self.name()
+        )))
+    }
+
+    /// 候補のモードを順に適用し、実際に適用された値を記録する
+    ///
+    /// 検出後は元のモードに戻す。モード変更に対応していないソースは空を返す。
+    fn probe_modes(&mut self, candidates: &[VideoMode]) -> Vec<ModeProbe> {
+        let original = VideoMode::from(self.properties());
+        let probes = candidates
+            .iter()
+            .filter_map(|mode| {
+                self.apply_mode(mode).ok().map(|actual| ModeProbe {
+                    requested: *mode,
+                    actual,
+                })
+            })
+            .collect();
+        let _ = self.apply_mode(&original);
+        probes
+    }
+
+    /// ソースを閉じてリソースを解放
+    fn close(&mut self);
+
+    /// 表示用の名前
+    fn name(&self) -> String;
+}
@@ -0,0 +1,336 @@
+// 標準ライブラリのインポート
+use std::fmt;
+
+// サードパーティクレートのインポート
+use opencv::{
+    core::Mat,
+    prelude::*,
+    videoio::{self, VideoCapture},
+};
+
+use super::{FourCc, FrameSource, SourceProperties, VideoMode};
+use crate::error::CameraError;
+
+/// バックエンド名と定数の対応表 (設定ファイル・環境変数での指定用)
+const BACKEND_NAMES: [(&str, i32); 7] = [
+    ("msmf", videoio::CAP_MSMF),
+    ("dshow", videoio::CAP_DSHOW),
+    ("v4l2", videoio::CAP_V4L2),
+    ("gstreamer", videoio::CAP_GSTREAMER),
+    ("ffmpeg", videoio::CAP_FFMPEG),
+    ("avfoundation", videoio::CAP_AVFOUNDATION),
+    ("any", videoio::CAP_ANY),
+];
+
+/// プラットフォームごとのデフォルトのバックエンド (先頭から順に試行)
+#[cfg(target_os = "windows")]
+pub fn default_backends() -> Vec<i32> {
+    vec![
+        videoio::CAP_MSMF,  // Windows Media Foundation (Windows 10/11推奨)
+        videoio::CAP_DSHOW, // DirectShow (古いデバイス対応)
+        videoio::CAP_ANY,   // 自動選択
+    ]
+}
+
+/// プラットフォームごとのデフォルトのバックエンド (先頭から順に試行)
+#[cfg(target_os = "linux")]
+pub fn default_backends() -> Vec<i32> {
+    vec![
+        videoio::CAP_V4L2,      // Video4Linux2 (USBカメラ・内蔵カメラ)
+        videoio::CAP_GSTREAMER, // GStreamer (ネットワークカメラ・特殊デバイス)
+        videoio::CAP_FFMPEG,    // FFmpeg
+        videoio::CAP_ANY,       // 自動選択
+    ]
+}
+
+/// プラットフォームごとのデフォルトのバックエンド (先頭から順に試行)
+#[cfg(target_os = "macos")]
+pub fn default_backends() -> Vec<i32> {
+    vec![
+        videoio::CAP_AVFOUNDATION, // AVFoundation
+        videoio::CAP_ANY,          // 自動選択
+    ]
+}
+
+/// プラットフォームごとのデフォルトのバックエンド (先頭から順に試行)
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+pub fn default_backends() -> Vec<i32> {
+    vec![videoio::CAP_ANY]
+}
+
+/// バックエンドの表示名
+pub fn backend_name(backend: i32) -> String {
+    BACKEND_NAMES
+        .iter()
+        .find(|(_, value)| *value == backend)
+        .map(|(name, _)| name.to_uppercase())
+        .unwrap_or_else(|| backend.to_string())
+}
+
+/// カンマ区切りのバックエンド名 (例: `v4l2,gstreamer,any`) を解析
+///
+/// # 戻り値
+/// 成功時はバックエンドのリスト、不明な名前が含まれる場合は`Err(String)`
+pub fn parse_backends(text: &str) -> Result<Vec<i32>, String> {
+    let backends = text
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            let lower = name.to_ascii_lowercase();
+            BACKEND_NAMES
+                .iter()
+                .find(|(known, _)| *known == lower)
+                .map(|(_, value)| *value)
+                .ok_or_else(|| format!("不明なバックエンドです: {}", name))
+        })
+        .collect::<Result<Vec<i32>, String>>()?;
+
+    if backends.is_empty() {
+        return Err("バックエンドが指定されていません".to_string());
+    }
+    Ok(backends)
+}
+
+/// 開くカメラデバイスの指定方法
+#[derive(Debug, Clone, PartialEq)]
+pub enum CameraDevice {
+    /// デバイスインデックス (0, 1, 2...)
+    Index(i32),
+    /// デバイスパス (`/dev/videoN`) またはGStreamerパイプライン文字列
+    Path(String),
+}
+
+impl CameraDevice {
+    /// GStreamerパイプライン文字列かどうか (`!`で要素を連結している)
+    pub fn is_pipeline(&self) -> bool {
+        matches!(self, CameraDevice::Path(path) if path.contains('!'))
+    }
+
+    /// 文字列がカメラデバイスの指定 (デバイスパスまたはパイプライン) に見えるか
+    pub fn looks_like_device(text: &str) -> bool {
+        text.starts_with("/dev/video") || text.contains('!')
+    }
+}
+
+impl fmt::Display for CameraDevice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CameraDevice::Index(index) => write!(f, "カメラ {}", index),
+            CameraDevice::Path(path) if self.is_pipeline() => write!(f, "GStreamer {}", path),
+            CameraDevice::Path(path) => write!(f, "{}", path),
+        }
+    }
+}
+
+/// OpenCVのVideoCaptureを使用した実カメラのフレームソース
+pub struct CameraSource {
+    /// 開くカメラデバイス
+    device: CameraDevice,
+    /// 試行するバックエンド (先頭から順に試行)
+    backends: Vec<i32>,
+    /// 要求する動作モード (解像度・FPS・ピクセルフォーマット)
+    requested: VideoMode,
+    /// 開いているカメラデバイス
+    capture: Option<VideoCapture>,
+    /// 実際に適用されたプロパティ
+    properties: SourceProperties,
+}
+
+impl CameraSource {
+    /// カメラソースを作成 (まだ開かない)
+    pub fn new(device: CameraDevice, backends: Vec<i32>, mode: VideoMode) -> Self {
+        Self {
+            device,
+            backends,
+            requested: mode,
+            capture: None,
+            properties: SourceProperties {
+                width: mode.width,
+                height: mode.height,
+                fps: 0.0,
+                fourcc: None,
+            },
+        }
+    }
+
+    /// カメラに動作モードを要求する
+    ///
+    /// ドライバーによってはピクセルフォーマットを先に設定しないと
+    /// 高解像度を受け付けないため、FOURCC → 解像度 → FPS の順に設定する。
+    fn configure(cam: &mut VideoCapture, mode: &VideoMode) {
+        if let Some(fourcc) = mode.fourcc {
+            let _ = cam.set(videoio::CAP_PROP_FOURCC, fourcc.0 as f64);
+        }
+        let _ = cam.set(videoio::CAP_PROP_FRAME_WIDTH, mode.width as f64);
+        let _ = cam.set(videoio::CAP_PROP_FRAME_HEIGHT, mode.height as f64);
+        if let Some(fps) = mode.fps {
+            let _ = cam.set(videoio::CAP_PROP_FPS, fps);
+        }
+    }
+
+    /// 実際に適用された値を取得 (デバイスによっては要求と異なる場合がある)
+    fn read_properties(cam: &VideoCapture, current: SourceProperties) -> SourceProperties {
+        let mut props = current;
+        if let Ok(width) = cam.get(videoio::CAP_PROP_FRAME_WIDTH) {
+            props.width = width as i32;
+        }
+        if let Ok(height) = cam.get(videoio::CAP_PROP_FRAME_HEIGHT) {
+            props.height = height as i32;
+        }
+        props.fps = cam.get(videoio::CAP_PROP_FPS).unwrap_or(0.0);
+        props.fourcc = cam
+            .get(videoio::CAP_PROP_FOURCC)
+            .ok()
+            .and_then(FourCc::from_property);
+        props
+    }
+
+    /// 指定したバックエンドでデバイスを開く
+    fn open_with(&self, backend: i32) -> opencv::Result<VideoCapture> {
+        match &self.device {
+            CameraDevice::Index(index) => VideoCapture::new(*index, backend),
+            CameraDevice::Path(path) => VideoCapture::from_file(path, backend),
+        }
+    }
+}
+
+impl FrameSource for CameraSource {
+    /// カメラデバイスを開く
+    ///
+    /// 指定されたデバイスでVideoCaptureを開き、解像度を設定する。
+    /// 設定した解像度が実際に適用されたかを確認し、実際の値を保存する。
+    /// バックエンドは設定された順に試行する (Windowsのデフォルト: MSMF → DirectShow → Any)。
+    /// GStreamerパイプラインの場合はGStreamerバックエンドのみを使用し、
+    /// 解像度はパイプライン側の指定に従う。
+    fn open(&mut self) -> Result<(), CameraError> {
+        let backends = if self.device.is_pipeline() {
+            vec![videoio::CAP_GSTREAMER]
+        } else {
+            self.backends.clone()
+        };
+
+        // 失敗理由を記録しておき、全滅した場合にまとめて返す
+        let mut failures = Vec::new();
+        log::info!(
+            "カメラを開きます: {} (バックエンド: {})",
+            self.device,
+            backends
+                .iter()
+                .map(|backend| backend_name(*backend))
+                .collect::<Vec<_>>()
+                .join(" → ")
+        );
+
+        // 複数のバックエンドを順番に試す
+        for backend in backends {
+            log::debug!("{}で開いています: {}", backend_name(backend), self.device);
+            let mut cam = match self.open_with(backend) {
+                Ok(cam) => cam,
+                Err(e) => {
+                    log::warn!(
+                        "{}で開けませんでした: {} ({})",
+                        backend_name(backend),
+                        self.device,
+                        e
+                    );
+                    failures.push(format!("{}: {}", backend_name(backend), e));
+                    continue;
+                }
+            };
+            if !cam.is_opened().unwrap_or(false) {
+                log::warn!(
+                    "{}で開けませんでした: {} (デバイスを開けません)",
+                    backend_name(backend),
+                    self.device
+                );
+                failures.push(format!("{}: デバイスを開けません", backend_name(backend)));
+                continue;
+            }
+
+            if !self.device.is_pipeline() {
+                // カメラの動作モードを設定 (リクエスト)
+                Self::configure(&mut cam, &self.requested);
+            }
+
+            // 実際に設定された値を取得 (デバイスによっては異なる場合がある)
+            self.properties = Self::read_properties(&cam, self.properties);
+            log::debug!(
+                "動作モード: 要求 {} / 実際 {}",
+                self.requested.label(),
+                VideoMode::from(self.properties).label()
+            );
+
+            self.capture = Some(cam);
+            log::info!(
+                "カメラを初期化しました: {} ({}x{}) - バックエンド: {}",
+                self.device,
+                self.properties.width,
+                self.properties.height,
+                backend_name(backend)
+            );
+            return Ok(());
+        }
+
+        log::error!(
+            "すべてのバックエンドで失敗しました: {} ({})",
+            self.device,
+            failures.join(", ")
+        );
+        Err(CameraError::DeviceOpen {
+            device: self.device.to_string(),
+            reason: format!(
+                "すべてのバックエンドで失敗しました ({})",
+                failures.join(", ")
+            ),
+        })
+    }
+
+    fn read(&mut self, frame: &mut Mat) -> Result<bool, CameraError> {
+        let Some(cam) = self.capture.as_mut() else {
+            return Ok(false);
+        };
+        let read_error = |reason: String| CameraError::FrameRead {
+            device: self.device.to_string(),
+            reason,
+        };
+        match cam.read(frame) {
+            Ok(true) if !frame.empty() => Ok(true),
+            Ok(_) => Err(read_error("空のフレームが返されました".to_string())),
+            Err(e) => Err(read_error(e.to_string())),
+        }
+    }
+
+    fn properties(&self) -> SourceProperties {
+        self.properties
+    }
+
+    /// カメラを開いたまま動作モードを変更し、実際に適用された値を返す
+    fn apply_mode(&mut self, mode: &VideoMode) -> Result<SourceProperties, CameraError> {
+        if self.device.is_pipeline() {
+            return Err(CameraError::Unsupported(
+                "GStreamerパイプラインの解像度はパイプライン側で指定してください".to_string(),
+            ));
+        }
+        let Some(cam) = self.capture.as_mut() else {
+            return Err(CameraError::NotOpen);
+        };
+
+        Self::configure(cam, mode);
+        self.properties = Self::read_properties(cam, self.properties);
+        // 開き直した場合も同じモードを要求する
+        self.requested = *mode;
+        Ok(self.properties)
+    }
+
+    fn close(&mut self) {
+        // VideoCaptureを即座に解放
+        if let Some(cam) = self.capture.take() {
+            drop(cam);
+        }
+    }
+
+    fn name(&self) -> String {
+        self.device.to_string()
+    }
+}
@@ -0,0 +1,177 @@
+// 標準ライブラリのインポート
+use std::fmt;
+
+use super::SourceProperties;
+
+/// 検出時に試す解像度 (一般的なUSBカメラ・内蔵カメラの対応解像度)
+const CANDIDATE_RESOLUTIONS: [(i32, i32); 6] = [
+    (640, 480),
+    (800, 600),
+    (1280, 720),
+    (1920, 1080),
+    (2560, 1440),
+    (3840, 2160),
+];
+/// 検出時に試すFPS
+const CANDIDATE_FPS: [f64; 2] = [30.0, 60.0];
+/// FPSが一致しているとみなす誤差 (ドライバーは29.97等を返すことがある)
+const FPS_TOLERANCE: f64 = 1.0;
+
+/// ピクセルフォーマット・録画コーデック (FOURCC: Four Character Code)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FourCc(pub i32);
+
+impl FourCc {
+    /// Motion JPEG (高解像度・高FPSでもUSB帯域に収まりやすい)
+    pub const MJPG: FourCc = FourCc::from_bytes(*b"MJPG");
+    /// YUYV 4:2:2 (非圧縮、高解像度では低FPSになりやすい)
+    pub const YUYV: FourCc = FourCc::from_bytes(*b"YUYV");
+    /// MPEG-4 Part 2 (録画コーデック、互換性が高い)
+    pub const MP4V: FourCc = FourCc::from_bytes(*b"mp4v");
+    /// H.264 (録画コーデック、高画質だがエンコーダーが無い環境がある)
+    pub const H264: FourCc = FourCc::from_bytes(*b"H264");
+    /// Xvid (録画コーデック、MPEG-4 Part 2のAVI向け実装)
+    pub const XVID: FourCc = FourCc::from_bytes(*b"XVID");
+
+    /// 4文字からFOURCCを作成 (VideoWriter::fourccと同じバイト順)
+    pub const fn from_bytes(bytes: [u8; 4]) -> Self {
+        FourCc(i32::from_le_bytes(bytes))
+    }
+
+    /// 4文字の文字列 (例: `MJPG`, `mp4v`) から作成
+    ///
+    /// # 戻り値
+    /// 成功時は`FourCc`、4文字のASCII文字列でない場合は`Err(String)`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let bytes: [u8; 4] = text
+            .as_bytes()
+            .try_into()
+            .ok()
+            .filter(|bytes: &[u8; 4]| bytes.iter().all(u8::is_ascii_graphic))
+            .ok_or_else(|| format!("FOURCCは4文字の英数字で指定してください: {}", text))?;
+        Ok(Self::from_bytes(bytes))
+    }
+
+    /// `CAP_PROP_FOURCC`の値から作成 (0以下は不明とみなす)
+    pub fn from_property(value: f64) -> Option<Self> {
+        if value > 0.0 {
+            Some(FourCc(value as i64 as i32))
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for FourCc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text: String = self
+            .0
+            .to_le_bytes()
+            .iter()
+            .map(|&b| if b.is_ascii_graphic() { b as char } else { '?' })
+            .collect();
+        write!(f, "{}", text)
+    }
+}
+
+/// カメラに要求する動作モード (解像度・FPS・ピクセルフォーマット)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VideoMode {
+    /// フレームの幅 (ピクセル)
+    pub width: i32,
+    /// フレームの高さ (ピクセル)
+    pub height: i32,
+    /// フレームレート (Noneの場合はドライバーのデフォルト)
+    pub fps: Option<f64>,
+    /// ピクセルフォーマット (Noneの場合はドライバーのデフォルト)
+    pub fourcc: Option<FourCc>,
+}
+
+impl VideoMode {
+    /// UIに表示するラベル (例: `1280x720 @30fps MJPG`)
+    pub fn label(&self) -> String {
+        let mut label = format!("{}x{}", self.width, self.height);
+        if let Some(fps) = self.fps {
+            label.push_str(&format!(" @{:.0}fps", fps));
+        }
+        if let Some(fourcc) = self.fourcc {
+            label.push_str(&format!(" {}", fourcc));
+        }
+        label
+    }
+
+    /// 実際に適用された値が、このモードの要求どおりかどうか
+    ///
+    /// FPS・ピクセルフォーマットは、要求していない場合や取得できない場合は比較しない。
+    pub fn is_satisfied_by(&self, actual: &SourceProperties) -> bool {
+        let size_ok = actual.width == self.width && actual.height == self.height;
+        let fps_ok = match self.fps {
+            Some(fps) if actual.fps > 0.0 => (actual.fps - fps).abs() < FPS_TOLERANCE,
+            _ => true,
+        };
+        let fourcc_ok = match (self.fourcc, actual.fourcc) {
+            (Some(requested), Some(actual)) => requested == actual,
+            _ => true,
+        };
+        size_ok && fps_ok && fourcc_ok
+    }
+}
+
+impl From<SourceProperties> for VideoMode {
+    /// 現在のプロパティと同じモード (検出後に元の設定へ戻すため)
+    fn from(props: SourceProperties) -> Self {
+        Self {
+            width: props.width,
+            height: props.height,
+            fps: (props.fps > 0.0).then_some(props.fps),
+            fourcc: props.fourcc,
+        }
+    }
+}
+
+/// 検出時に試すモードの一覧 (解像度 × FPS × ピクセルフォーマット)
+pub fn candidate_modes() -> Vec<VideoMode> {
+    let mut modes = Vec::new();
+    for (width, height) in CANDIDATE_RESOLUTIONS {
+        for fps in CANDIDATE_FPS {
+            for fourcc in [FourCc::MJPG, FourCc::YUYV] {
+                modes.push(VideoMode {
+                    width,
+                    height,
+                    fps: Some(fps),
+                    fourcc: Some(fourcc),
+                });
+            }
+        }
+    }
+    modes
+}
+
+/// モード検出の結果 (要求した値と実際に適用された値)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModeProbe {
+    /// 要求したモード
+    pub requested: VideoMode,
+    /// 実際に適用された値
+    pub actual: SourceProperties,
+}
+
+impl ModeProbe {
+    /// 要求どおりに適用されたかどうか
+    pub fn is_supported(&self) -> bool {
+        self.requested.is_satisfied_by(&self.actual)
+    }
+
+    /// UIに表示するラベル (例: `1280x720 @60fps MJPG ✔`)
+    pub fn label(&self) -> String {
+        if self.is_supported() {
+            format!("{} ✔", self.requested.label())
+        } else {
+            format!(
+                "{} ✖ (→ {})",
+                self.requested.label(),
+                VideoMode::from(self.actual).label()
+            )
+        }
+    }
+}
@@ -0,0 +1,47 @@
+// 標準ライブラリのインポート
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// フレームの供給間隔を実時間に合わせるためのペーサー
+///
+/// 合成映像やファイル再生など、読み取りが一瞬で終わるソースで
+/// 実カメラと同じように`read()`をFPSに合わせてブロックさせる。
+/// 基準時刻からの経過で予定時刻を計算するため、誤差が蓄積しない。
+pub struct FramePacer {
+    /// フレームレート
+    fps: f64,
+    /// 最初のフレームを供給した時刻
+    started_at: Option<Instant>,
+    /// 供給したフレーム数
+    frames: u64,
+}
+
+impl FramePacer {
+    /// 指定したFPSのペーサーを作成
+    pub fn new(fps: f64) -> Self {
+        Self {
+            fps,
+            started_at: None,
+            frames: 0,
+        }
+    }
+
+    /// 次のフレームの予定時刻まで待機する
+    pub fn wait(&mut self) {
+        let started_at = *self.started_at.get_or_insert_with(Instant::now);
+        if self.fps > 0.0 {
+            let due = started_at + Duration::from_secs_f64(self.frames as f64 / self.fps);
+            let now = Instant::now();
+            if due > now {
+                thread::sleep(due - now);
+            }
+        }
+        self.frames += 1;
+    }
+
+    /// 基準時刻をリセット (ソースを開き直したとき)
+    pub fn reset(&mut self) {
+        self.started_at = None;
+        self.frames = 0;
+    }
+}
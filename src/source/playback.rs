@@ -0,0 +1,325 @@
+// 標準ライブラリのインポート
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// サードパーティクレートのインポート
+use opencv::{
+    core::Mat,
+    imgcodecs,
+    prelude::*,
+    videoio::{self, VideoCapture},
+};
+
+use super::pacing::FramePacer;
+use super::{FrameSource, SourceProperties};
+use crate::error::CameraError;
+
+/// FPSが取得できない場合、または画像フォルダ再生時のデフォルトFPS
+const DEFAULT_PLAYBACK_FPS: f64 = 30.0;
+
+/// 画像フォルダ再生で読み込む拡張子 (小文字)
+const IMAGE_EXTENSIONS: [&str; 3] = ["jpg", "jpeg", "png"];
+
+/// 再生終了時の動作
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlaybackMode {
+    Loop, // 先頭に戻って繰り返し再生
+    Once, // 最後のフレームで停止
+}
+
+/// ファイル再生の設定
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaybackConfig {
+    /// 動画ファイル (MP4/AVI等) または画像フォルダのパス
+    pub path: PathBuf,
+    /// 再生終了時の動作
+    pub mode: PlaybackMode,
+    /// 再生FPS (Noneの場合は動画ファイルのFPS、画像フォルダは30fps)
+    pub fps: Option<f64>,
+}
+
+impl PlaybackConfig {
+    /// 設定に応じたフレームソースを作成
+    ///
+    /// パスがディレクトリの場合は画像フォルダ、それ以外は動画ファイルとして扱う。
+    pub fn create_source(&self) -> Box<dyn FrameSource> {
+        if self.path.is_dir() {
+            Box::new(ImageSequenceSource::new(self.clone()))
+        } else {
+            Box::new(VideoFileSource::new(self.clone()))
+        }
+    }
+}
+
+/// パスからファイル名部分を取り出す (表示用)
+fn display_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// 動画ファイルをカメラの代わりに再生するフレームソース
+///
+/// ファイルのFPSに合わせて実時間でフレームを供給する。
+pub struct VideoFileSource {
+    /// 再生設定
+    config: PlaybackConfig,
+    /// 開いている動画ファイル
+    capture: Option<VideoCapture>,
+    /// 動画ファイルのプロパティ
+    properties: SourceProperties,
+    /// 再生FPSに合わせて供給間隔を調整する
+    pacer: FramePacer,
+}
+
+impl VideoFileSource {
+    /// 動画ファイルソースを作成 (まだ開かない)
+    pub fn new(config: PlaybackConfig) -> Self {
+        Self {
+            config,
+            capture: None,
+            properties: SourceProperties {
+                width: 0,
+                height: 0,
+                fps: 0.0,
+                fourcc: None,
+            },
+            pacer: FramePacer::new(DEFAULT_PLAYBACK_FPS),
+        }
+    }
+}
+
+impl FrameSource for VideoFileSource {
+    fn open(&mut self) -> Result<(), CameraError> {
+        let path = self.config.path.to_str().ok_or_else(|| {
+            CameraError::InvalidConfig(format!("パスが不正です: {}", self.config.path.display()))
+        })?;
+
+        let open_error = |reason: String| CameraError::DeviceOpen {
+            device: format!("動画ファイル {}", path),
+            reason,
+        };
+        let cam = VideoCapture::from_file(path, videoio::CAP_ANY)
+            .map_err(|e| open_error(e.to_string()))?;
+        if !cam.is_opened().unwrap_or(false) {
+            return Err(open_error(
+                "対応していない形式か、ファイルが存在しません".to_string(),
+            ));
+        }
+
+        // ファイルのFPS (不正な値の場合はデフォルト) を再生速度として使用
+        let file_fps = cam.get(videoio::CAP_PROP_FPS).unwrap_or(0.0);
+        let fps = self.config.fps.unwrap_or(if file_fps > 0.0 {
+            file_fps
+        } else {
+            DEFAULT_PLAYBACK_FPS
+        });
+
+        self.properties = SourceProperties {
+            width: cam.get(videoio::CAP_PROP_FRAME_WIDTH).unwrap_or(0.0) as i32,
+            height: cam.get(videoio::CAP_PROP_FRAME_HEIGHT).unwrap_or(0.0) as i32,
+            fps,
+            fourcc: None,
+        };
+        self.pacer = FramePacer::new(fps);
+        self.capture = Some(cam);
+        log::info!(
+            "動画ファイルを開きました: {} ({}x{} @ {}fps)",
+            path,
+            self.properties.width,
+            self.properties.height,
+            fps
+        );
+        Ok(())
+    }
+
+    fn read(&mut self, frame: &mut Mat) -> Result<bool, CameraError> {
+        let Some(cam) = self.capture.as_mut() else {
+            return Ok(false);
+        };
+        let read_error = |e: opencv::Error| CameraError::FrameRead {
+            device: format!("動画ファイル {}", display_name(&self.config.path)),
+            reason: e.to_string(),
+        };
+
+        self.pacer.wait();
+        if cam.read(frame).map_err(read_error)? && !frame.empty() {
+            return Ok(true);
+        }
+
+        // ファイル末尾に到達: ループ再生の場合は先頭に戻って読み直す
+        if self.config.mode == PlaybackMode::Loop
+            && cam
+                .set(videoio::CAP_PROP_POS_FRAMES, 0.0)
+                .map_err(read_error)?
+        {
+            return Ok(cam.read(frame).map_err(read_error)? && !frame.empty());
+        }
+        Ok(false)
+    }
+
+    fn properties(&self) -> SourceProperties {
+        self.properties
+    }
+
+    fn close(&mut self) {
+        // VideoCaptureを即座に解放
+        if let Some(cam) = self.capture.take() {
+            drop(cam);
+        }
+    }
+
+    fn name(&self) -> String {
+        format!("動画ファイル {}", display_name(&self.config.path))
+    }
+}
+
+/// 画像フォルダ (JPEG/PNG) を連番フレームとして再生するフレームソース
+///
+/// ファイル名順にソートして読み込むため、再生順序は常に同じになる。
+pub struct ImageSequenceSource {
+    /// 再生設定
+    config: PlaybackConfig,
+    /// 再生する画像ファイル (ファイル名順)
+    files: Vec<PathBuf>,
+    /// 次に読み込む画像の位置
+    position: usize,
+    /// 先頭画像から取得したプロパティ
+    properties: SourceProperties,
+    /// 再生FPSに合わせて供給間隔を調整する
+    pacer: FramePacer,
+}
+
+impl ImageSequenceSource {
+    /// 画像フォルダソースを作成 (まだ開かない)
+    pub fn new(config: PlaybackConfig) -> Self {
+        let fps = config.fps.unwrap_or(DEFAULT_PLAYBACK_FPS);
+        Self {
+            config,
+            files: Vec::new(),
+            position: 0,
+            properties: SourceProperties {
+                width: 0,
+                height: 0,
+                fps,
+                fourcc: None,
+            },
+            pacer: FramePacer::new(fps),
+        }
+    }
+
+    /// フォルダ内の画像ファイルを列挙 (ファイル名順)
+    fn list_images(dir: &Path) -> Result<Vec<PathBuf>, CameraError> {
+        let entries = fs::read_dir(dir).map_err(|e| CameraError::DeviceOpen {
+            device: format!("画像フォルダ {}", dir.display()),
+            reason: e.to_string(),
+        })?;
+
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .map(|ext| IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+                    .unwrap_or(false)
+            })
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+
+    /// 指定位置の画像を読み込む
+    fn load(&self, index: usize) -> Option<Mat> {
+        let path = self.files.get(index)?.to_str()?;
+        match imgcodecs::imread(path, imgcodecs::IMREAD_COLOR) {
+            Ok(image) if !image.empty() => Some(image),
+            Ok(_) => {
+                log::warn!("画像を読み込めませんでした: {}", path);
+                None
+            }
+            Err(e) => {
+                log::warn!("画像を読み込めませんでした: {} ({})", path, e);
+                None
+            }
+        }
+    }
+}
+
+impl FrameSource for ImageSequenceSource {
+    fn open(&mut self) -> Result<(), CameraError> {
+        self.files = Self::list_images(&self.config.path)?;
+        let open_error = |reason: String| CameraError::DeviceOpen {
+            device: format!("画像フォルダ {}", self.config.path.display()),
+            reason,
+        };
+        if self.files.is_empty() {
+            return Err(open_error("JPEG/PNG画像がありません".to_string()));
+        }
+
+        // 先頭画像の解像度をソースの解像度とする
+        let first = self.load(0).ok_or_else(|| {
+            open_error(format!(
+                "先頭の画像を読み込めませんでした: {}",
+                self.files[0].display()
+            ))
+        })?;
+        if let Ok(size) = first.size() {
+            self.properties.width = size.width;
+            self.properties.height = size.height;
+        }
+
+        self.position = 0;
+        self.pacer.reset();
+        log::info!(
+            "画像フォルダを開きました: {} ({}枚, {}x{} @ {}fps)",
+            self.config.path.display(),
+            self.files.len(),
+            self.properties.width,
+            self.properties.height,
+            self.properties.fps
+        );
+        Ok(())
+    }
+
+    fn read(&mut self, frame: &mut Mat) -> Result<bool, CameraError> {
+        if self.files.is_empty() {
+            return Ok(false);
+        }
+
+        // 末尾に到達: ループ再生の場合は先頭に戻る
+        if self.position >= self.files.len() {
+            match self.config.mode {
+                PlaybackMode::Loop => self.position = 0,
+                PlaybackMode::Once => return Ok(false),
+            }
+        }
+
+        self.pacer.wait();
+        let image = self.load(self.position);
+        self.position += 1;
+        match image {
+            Some(image) => {
+                *frame = image;
+                Ok(true)
+            }
+            // 読み込めない画像は飛ばす (続けて失敗する場合はキャプチャスレッドが報告する)
+            None => Err(CameraError::FrameRead {
+                device: self.name(),
+                reason: "画像を読み込めませんでした".to_string(),
+            }),
+        }
+    }
+
+    fn properties(&self) -> SourceProperties {
+        self.properties
+    }
+
+    fn close(&mut self) {
+        self.files.clear();
+        self.position = 0;
+    }
+
+    fn name(&self) -> String {
+        format!("画像フォルダ {}", display_name(&self.config.path))
+    }
+}
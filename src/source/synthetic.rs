@@ -0,0 +1,253 @@
+// サードパーティクレートのインポート
+use chrono::Local;
+use opencv::{
+    core::{self, Mat, Point, Scalar},
+    imgproc,
+    prelude::*,
+};
+
+use super::pacing::FramePacer;
+use super::{FrameSource, SourceProperties, VideoMode};
+use crate::error::CameraError;
+
+/// カラーバーの色 (BGR順): 白, 黄, シアン, 緑, マゼンタ, 赤, 青
+const COLOR_BARS: [[u8; 3]; 7] = [
+    [255, 255, 255],
+    [0, 255, 255],
+    [255, 255, 0],
+    [0, 255, 0],
+    [255, 0, 255],
+    [0, 0, 255],
+    [255, 0, 0],
+];
+
+/// テストパターンの設定 (解像度・FPS)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TestPatternConfig {
+    /// フレームの幅 (ピクセル)
+    pub width: i32,
+    /// フレームの高さ (ピクセル)
+    pub height: i32,
+    /// 生成するフレームレート
+    pub fps: f64,
+}
+
+impl Default for TestPatternConfig {
+    fn default() -> Self {
+        Self {
+            width: 640,
+            height: 480,
+            fps: 30.0,
+        }
+    }
+}
+
+impl TestPatternConfig {
+    /// `WIDTHxHEIGHT@FPS` 形式の文字列を解析 (`@FPS`は省略可能)
+    ///
+    /// # 戻り値
+    /// 成功時は設定、形式が不正な場合はエラーメッセージを含む`Err(String)`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (size, fps) = match text.split_once('@') {
+            Some((size, fps)) => (size, Some(fps)),
+            None => (text, None),
+        };
+
+        let (width, height) = size
+            .split_once(['x', 'X'])
+            .ok_or_else(|| format!("解像度の形式が不正です: {}", text))?;
+        let width: i32 = width
+            .trim()
+            .parse()
+            .map_err(|_| format!("幅が不正です: {}", width))?;
+        let height: i32 = height
+            .trim()
+            .parse()
+            .map_err(|_| format!("高さが不正です: {}", height))?;
+        let fps = match fps {
+            Some(fps) => fps
+                .trim()
+                .parse()
+                .map_err(|_| format!("FPSが不正です: {}", fps))?,
+            None => Self::default().fps,
+        };
+
+        if width <= 0 || height <= 0 || fps <= 0.0 {
+            return Err(format!("解像度とFPSは正の値を指定してください: {}", text));
+        }
+
+        Ok(Self { width, height, fps })
+    }
+}
+
+/// カメラ無しで動作確認するための合成映像ソース
+///
+/// カラーバーと横方向に流れるグラデーションを描画し、
+/// フレーム番号とタイムスタンプを焼き込む。
+/// 実カメラと同様に、設定したFPSに合わせて`read()`がブロックする。
+pub struct SyntheticSource {
+    /// 解像度とFPSの設定
+    config: TestPatternConfig,
+    /// 生成したフレーム数
+    frame_count: u64,
+    /// 設定したFPSに合わせて生成間隔を調整する
+    pacer: FramePacer,
+    /// 開いているかどうか
+    opened: bool,
+}
+
+impl SyntheticSource {
+    /// 合成映像ソースを作成
+    pub fn new(config: TestPatternConfig) -> Self {
+        Self {
+            config,
+            frame_count: 0,
+            pacer: FramePacer::new(config.fps),
+            opened: false,
+        }
+    }
+
+    /// カラーバーとグラデーションをBGRバイト列に描画
+    fn draw_pattern(&self, data: &mut [u8]) {
+        let width = self.config.width as usize;
+        let height = self.config.height as usize;
+        // 上部2/3はカラーバー、下部1/3は流れるグラデーション
+        let bars_height = height * 2 / 3;
+        let offset = self.frame_count as usize * 4;
+
+        for (y, row) in data.chunks_mut(width * 3).take(height).enumerate() {
+            for (x, pixel) in row.chunks_mut(3).enumerate() {
+                if y < bars_height {
+                    let bar = (x * COLOR_BARS.len() / width).min(COLOR_BARS.len() - 1);
+                    pixel.copy_from_slice(&COLOR_BARS[bar]);
+                } else {
+                    let level = (((x + offset) % width) * 255 / width) as u8;
+                    pixel.copy_from_slice(&[level, level, 255 - level]);
+                }
+            }
+        }
+    }
+
+    /// フレーム番号とタイムスタンプを焼き込む
+    fn draw_overlay(&self, frame: &mut Mat) -> opencv::Result<()> {
+        let scale = (self.config.height as f64 / 480.0).max(0.5);
+        let lines = [
+            format!("FRAME {:08}", self.frame_count),
+            Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+        ];
+
+        for (i, line) in lines.iter().enumerate() {
+            let origin = Point::new(
+                (16.0 * scale) as i32,
+                self.config.height - ((20.0 + 36.0 * (lines.len() - 1 - i) as f64) * scale) as i32,
+            );
+            // 黒の縁取り + 白文字で背景に関係なく読めるようにする
+            imgproc::put_text(
+                frame,
+                line,
+                origin,
+                imgproc::FONT_HERSHEY_SIMPLEX,
+                scale,
+                Scalar::new(0.0, 0.0, 0.0, 0.0),
+                (4.0 * scale) as i32,
+                imgproc::LINE_AA,
+                false,
+            )?;
+            imgproc::put_text(
+                frame,
+                line,
+                origin,
+                imgproc::FONT_HERSHEY_SIMPLEX,
+                scale,
+                Scalar::new(255.0, 255.0, 255.0, 0.0),
+                (2.0 * scale).max(1.0) as i32,
+                imgproc::LINE_AA,
+                false,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// 1フレーム分の画像を生成
+    fn render(&self) -> opencv::Result<Mat> {
+        let mut frame = Mat::new_rows_cols_with_default(
+            self.config.height,
+            self.config.width,
+            core::CV_8UC3,
+            Scalar::all(0.0),
+        )?;
+        self.draw_pattern(frame.data_bytes_mut()?);
+        self.draw_overlay(&mut frame)?;
+        Ok(frame)
+    }
+}
+
+impl FrameSource for SyntheticSource {
+    fn open(&mut self) -> Result<(), CameraError> {
+        if self.config.width <= 0 || self.config.height <= 0 || self.config.fps <= 0.0 {
+            return Err(CameraError::InvalidConfig(
+                "テストパターンの解像度またはFPSが不正です".to_string(),
+            ));
+        }
+        self.frame_count = 0;
+        self.pacer.reset();
+        self.opened = true;
+        log::info!(
+            "テストパターンを開始しました ({}x{} @ {}fps)",
+            self.config.width,
+            self.config.height,
+            self.config.fps
+        );
+        Ok(())
+    }
+
+    fn read(&mut self, frame: &mut Mat) -> Result<bool, CameraError> {
+        if !self.opened {
+            return Ok(false);
+        }
+
+        // 実カメラと同じフレーム間隔を再現
+        self.pacer.wait();
+        let rendered = self.render().map_err(|e| CameraError::FrameRead {
+            device: self.name(),
+            reason: format!("テストパターンの生成に失敗しました ({})", e),
+        })?;
+        *frame = rendered;
+        self.frame_count += 1;
+        Ok(true)
+    }
+
+    fn properties(&self) -> SourceProperties {
+        SourceProperties {
+            width: self.config.width,
+            height: self.config.height,
+            fps: self.config.fps,
+            fourcc: None,
+        }
+    }
+
+    /// 合成映像はどの解像度・FPSでも生成できる (ピクセルフォーマットは無視)
+    fn apply_mode(&mut self, mode: &VideoMode) -> Result<SourceProperties, CameraError> {
+        if mode.width <= 0 || mode.height <= 0 {
+            return Err(CameraError::InvalidConfig(format!(
+                "解像度が不正です: {}",
+                mode.label()
+            )));
+        }
+        self.config.width = mode.width;
+        self.config.height = mode.height;
+        if let Some(fps) = mode.fps.filter(|fps| *fps > 0.0) {
+            self.config.fps = fps;
+            self.pacer = FramePacer::new(fps);
+        }
+        Ok(self.properties())
+    }
+
+    fn close(&mut self) {
+        self.opened = false;
+    }
+
+    fn name(&self) -> String {
+        "テストパターン".to_string()
+    }
+}
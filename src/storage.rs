@@ -0,0 +1,51 @@
+// 標準ライブラリのインポート
+use std::fs;
+use std::path::Path;
+
+use crate::error::CameraError;
+
+/// 容量表示の単位 (1024倍ごと)
+const BYTE_UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+/// 容量表示の単位の倍率
+const BYTE_UNIT_STEP: f64 = 1024.0;
+
+/// 保存先ディレクトリを作成 (存在しない場合のみ)
+///
+/// # 戻り値
+/// 成功時は`Ok(())`、作成できなかった場合は`Err(CameraError)`
+pub fn ensure_dir(dir: &Path) -> Result<(), CameraError> {
+    if dir.is_dir() {
+        return Ok(());
+    }
+    fs::create_dir_all(dir).map_err(|e| CameraError::io(dir, e))
+}
+
+/// 保存先のディスクの空き容量 (バイト)
+///
+/// 保存先ディレクトリがまだ作成されていない場合は、存在する親ディレクトリ
+/// (相対パスの場合はカレントディレクトリ) で調べる。
+///
+/// # 戻り値
+/// 取得できた場合は`Some(空き容量)`、取得できない場合は`None`
+pub fn available_space(dir: &Path) -> Option<u64> {
+    let existing = dir
+        .ancestors()
+        .find(|path| path.is_dir())
+        .unwrap_or(Path::new("."));
+    fs2::available_space(existing).ok()
+}
+
+/// バイト数を表示用の文字列に変換 (例: `12.3 GB`)
+pub fn format_bytes(bytes: u64) -> String {
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= BYTE_UNIT_STEP && unit + 1 < BYTE_UNITS.len() {
+        value /= BYTE_UNIT_STEP;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, BYTE_UNITS[unit])
+    } else {
+        format!("{:.1} {}", value, BYTE_UNITS[unit])
+    }
+}
@@ -0,0 +1,733 @@
+// 標準ライブラリのインポート
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+// サードパーティクレートのインポート
+use opencv::{
+    core::{Mat, Size},
+    imgcodecs, imgproc,
+    prelude::*,
+    videoio::VideoWriter,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::capture::{IntervalTap, TimedFrame};
+use crate::codec::{VideoContainer, VideoFormat};
+use crate::error::CameraError;
+use crate::filename;
+use crate::metadata::PhotoMetadata;
+use crate::photo::{self, PhotoOptions};
+use crate::recorder::{self, RecordingSummary};
+use crate::source::FourCc;
+use crate::storage;
+
+/// 撮影間隔のデフォルト値 (秒)
+pub const DEFAULT_TIMELAPSE_INTERVAL_SECS: u32 = 10;
+/// 撮影間隔の上限 (秒)
+pub const MAX_TIMELAPSE_INTERVAL_SECS: u32 = 3600;
+/// 撮影枚数のデフォルト値
+pub const DEFAULT_TIMELAPSE_COUNT: u32 = 300;
+/// 撮影時間のデフォルト値 (分)
+pub const DEFAULT_TIMELAPSE_DURATION_MINS: u32 = 60;
+/// 撮影時間の上限 (分、7日間)
+pub const MAX_TIMELAPSE_DURATION_MINS: u32 = 7 * 24 * 60;
+/// 撮影枚数の上限 (連番の桁数に収まるように)
+pub const MAX_TIMELAPSE_FRAMES: u32 = 99_999;
+/// 動画の再生FPSのデフォルト値
+pub const DEFAULT_PLAYBACK_FPS: u32 = 30;
+/// 動画の再生FPSの上限
+pub const MAX_PLAYBACK_FPS: u32 = 60;
+/// 連番の桁数 (ゼロ埋め)
+const FRAME_INDEX_DIGITS: usize = 5;
+/// 途中経過を記録するファイル名 (フレームと同じフォルダに置く)
+const STATE_FILE_NAME: &str = "timelapse.toml";
+/// 書き込み途中の途中経過ファイルの拡張子 (書き込み後に置き換える)
+const STATE_TEMP_EXTENSION: &str = "toml.tmp";
+/// 書き込みスレッドのキュー長 (撮影間隔は書き込みより十分長いため小さくてよい)
+const TIMELAPSE_QUEUE_CAPACITY: usize = 4;
+/// 1分の秒数
+const SECS_PER_MINUTE: u32 = 60;
+
+/// タイムラプスの終了条件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimelapseLimit {
+    Count,    // 指定した枚数を撮影したら終了
+    Duration, // 指定した時間が経ったら終了
+}
+
+impl TimelapseLimit {
+    /// UIに表示する順序
+    pub const ALL: [TimelapseLimit; 2] = [TimelapseLimit::Count, TimelapseLimit::Duration];
+
+    /// UIに表示するラベル
+    pub fn label(self) -> &'static str {
+        match self {
+            TimelapseLimit::Count => "枚数",
+            TimelapseLimit::Duration => "時間",
+        }
+    }
+}
+
+/// タイムラプスの設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimelapseOptions {
+    /// 撮影間隔 (秒)
+    #[serde(rename = "timelapse_interval_secs")]
+    pub interval_secs: u32,
+    /// 終了条件 (枚数か時間か)
+    #[serde(rename = "timelapse_limit")]
+    pub limit: TimelapseLimit,
+    /// 撮影枚数 (終了条件が枚数の場合)
+    #[serde(rename = "timelapse_count")]
+    pub count: u32,
+    /// 撮影時間 (分、終了条件が時間の場合)
+    #[serde(rename = "timelapse_duration_mins")]
+    pub duration_mins: u32,
+    /// 撮影したフレームを写真として残すかどうか
+    #[serde(rename = "timelapse_save_photos")]
+    pub save_photos: bool,
+    /// 撮影したフレームから動画 (MP4) を作成するかどうか
+    #[serde(rename = "timelapse_make_video")]
+    pub make_video: bool,
+    /// 動画の再生FPS
+    #[serde(rename = "timelapse_playback_fps")]
+    pub playback_fps: u32,
+}
+
+impl Default for TimelapseOptions {
+    fn default() -> Self {
+        Self {
+            interval_secs: DEFAULT_TIMELAPSE_INTERVAL_SECS,
+            limit: TimelapseLimit::Count,
+            count: DEFAULT_TIMELAPSE_COUNT,
+            duration_mins: DEFAULT_TIMELAPSE_DURATION_MINS,
+            save_photos: true,
+            make_video: true,
+            playback_fps: DEFAULT_PLAYBACK_FPS,
+        }
+    }
+}
+
+impl TimelapseOptions {
+    /// 範囲外の値を上下限に収める (写真も動画も残さない設定の場合は写真を残す)
+    pub fn clamp(&mut self) {
+        self.interval_secs = self.interval_secs.clamp(1, MAX_TIMELAPSE_INTERVAL_SECS);
+        self.count = self.count.clamp(1, MAX_TIMELAPSE_FRAMES);
+        self.duration_mins = self.duration_mins.clamp(1, MAX_TIMELAPSE_DURATION_MINS);
+        self.playback_fps = self.playback_fps.clamp(1, MAX_PLAYBACK_FPS);
+        if !self.save_photos && !self.make_video {
+            self.save_photos = true;
+        }
+    }
+
+    /// 撮影間隔
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(u64::from(self.interval_secs))
+    }
+
+    /// 撮影する枚数 (終了条件が時間の場合は、開始時の1枚を含めて時間内に撮影できる枚数)
+    pub fn total_frames(&self) -> u32 {
+        match self.limit {
+            TimelapseLimit::Count => self.count,
+            TimelapseLimit::Duration => (self.duration_mins * SECS_PER_MINUTE)
+                .div_ceil(self.interval_secs.max(1))
+                .clamp(1, MAX_TIMELAPSE_FRAMES),
+        }
+    }
+
+    /// 作成される動画の長さ
+    pub fn video_length(&self) -> Duration {
+        Duration::from_secs_f64(
+            f64::from(self.total_frames()) / f64::from(self.playback_fps.max(1)),
+        )
+    }
+}
+
+/// タイムラプスの途中経過 (アプリを終了しても再開できるように、フレームごとに保存する)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimelapseState {
+    /// ファイル名の共通部分 (フレームを置くフォルダ名・動画のファイル名にも使う)
+    pub stem: String,
+    /// 撮影する枚数
+    pub total: u32,
+    /// 保存を終えた枚数
+    pub captured: u32,
+    /// 動画のコーデック (FOURCC)
+    pub video_codec: String,
+    /// 動画のコンテナ形式 (この項目が無い途中経過はMP4)
+    #[serde(default)]
+    pub video_container: VideoContainer,
+    /// 撮影間隔・動画の設定
+    pub options: TimelapseOptions,
+    /// フレームの保存形式と画質設定
+    pub photo: PhotoOptions,
+}
+
+impl TimelapseState {
+    /// 新しいタイムラプスの途中経過を作成
+    pub fn new(
+        stem: String,
+        options: TimelapseOptions,
+        photo: PhotoOptions,
+        video: VideoFormat,
+    ) -> Self {
+        Self {
+            stem,
+            total: options.total_frames(),
+            captured: 0,
+            video_codec: video.codec.to_string(),
+            video_container: video.container,
+            options,
+            photo,
+        }
+    }
+
+    /// フォルダに保存された途中経過を読み込む
+    ///
+    /// 途中経過の更新前に終了した場合に備えて、保存済みのフレームを数え直す。
+    ///
+    /// # 戻り値
+    /// 成功時は途中経過、読み込めない場合は`Err(CameraError)`
+    pub fn load(dir: &Path) -> Result<Self, CameraError> {
+        let path = dir.join(STATE_FILE_NAME);
+        let text = fs::read_to_string(&path).map_err(|e| CameraError::io(&path, e))?;
+        let mut state: Self = toml::from_str(&text).map_err(|e| {
+            CameraError::InvalidConfig(format!(
+                "タイムラプスの途中経過を読み込めません: {} ({})",
+                path.display(),
+                e
+            ))
+        })?;
+        state.options.clamp();
+        state.photo.clamp();
+        state.total = state.total.min(MAX_TIMELAPSE_FRAMES);
+        while state.captured < state.total && state.frame_path(dir, state.captured + 1).exists() {
+            state.captured += 1;
+        }
+        Ok(state)
+    }
+
+    /// 途中経過をフォルダに保存
+    ///
+    /// 書き込み途中で終了してもファイルが壊れないように、
+    /// 一時ファイルに書き込んでから置き換える。
+    fn save(&self, dir: &Path) -> Result<(), CameraError> {
+        let path = dir.join(STATE_FILE_NAME);
+        let text = toml::to_string_pretty(self).map_err(|e| {
+            CameraError::InvalidConfig(format!("タイムラプスの途中経過を変換できません: {}", e))
+        })?;
+        let temp = path.with_extension(STATE_TEMP_EXTENSION);
+        fs::write(&temp, text).map_err(|e| CameraError::io(&temp, e))?;
+        fs::rename(&temp, &path).map_err(|e| CameraError::io(&path, e))
+    }
+
+    /// 残りの枚数
+    pub fn remaining(&self) -> u32 {
+        self.total.saturating_sub(self.captured)
+    }
+
+    /// 指定した番号 (1始まり) のフレームのパス
+    fn frame_path(&self, dir: &Path, index: u32) -> PathBuf {
+        dir.join(format!(
+            "{}_{:0width$}.{}",
+            self.stem,
+            index,
+            self.photo.format.extension(),
+            width = FRAME_INDEX_DIGITS
+        ))
+    }
+}
+
+/// 前回の起動で終わらなかったタイムラプス
+#[derive(Debug, Clone)]
+pub struct UnfinishedTimelapse {
+    /// フレームと途中経過を置いたフォルダ
+    pub dir: PathBuf,
+    /// 途中経過
+    pub state: TimelapseState,
+}
+
+/// 保存先から終わっていないタイムラプスを探す
+///
+/// 保存先直下のフォルダのうち、途中経過ファイルがあるものを返す (読み込めないものは無視する)。
+pub fn find_unfinished(output_dir: &Path) -> Vec<UnfinishedTimelapse> {
+    let Ok(entries) = fs::read_dir(output_dir) else {
+        return Vec::new();
+    };
+    let mut unfinished: Vec<UnfinishedTimelapse> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|dir| dir.join(STATE_FILE_NAME).is_file())
+        .filter_map(|dir| match TimelapseState::load(&dir) {
+            Ok(state) => Some(UnfinishedTimelapse { dir, state }),
+            Err(e) => {
+                log::warn!("{}", e);
+                None
+            }
+        })
+        .collect();
+    unfinished.sort_by(|a, b| a.dir.cmp(&b.dir));
+    unfinished
+}
+
+/// タイムラプスの進捗
+#[derive(Debug, Clone, Copy)]
+pub struct TimelapseProgress {
+    /// 保存を終えた枚数
+    pub captured: u32,
+    /// 撮影する枚数
+    pub total: u32,
+    /// 動画に書き込んだ枚数 (動画の作成中のみSome)
+    pub compiled: Option<u32>,
+    /// 撮影が終わるまでの残り時間の目安
+    pub remaining: Duration,
+}
+
+/// タイムラプスの結果
+#[derive(Debug, Default)]
+pub struct TimelapseSummary {
+    /// フレームと途中経過を置いたフォルダ
+    pub dir: PathBuf,
+    /// 保存を終えた枚数
+    pub captured: u32,
+    /// 作成した動画 (作成しない設定の場合や失敗した場合はNone)
+    pub video: Option<PathBuf>,
+    /// 最後まで終わったかどうか (falseの場合は途中経過が残っており再開できる)
+    pub finished: bool,
+    /// 保存・動画の作成に失敗した場合のエラー
+    pub errors: Vec<CameraError>,
+}
+
+/// 書き込みスレッドと共有する進捗
+#[derive(Default)]
+struct SharedProgress {
+    /// 保存を終えた枚数
+    captured: AtomicU32,
+    /// 動画に書き込んだ枚数
+    compiled: AtomicU32,
+    /// 動画の作成中かどうか
+    compiling: AtomicBool,
+    /// 撮影を打ち切って仕上げる (動画を作成する) よう要求されたかどうか
+    finish_requested: AtomicBool,
+}
+
+/// タイムラプスセッション: 専用スレッドでフレームを保存し、最後に動画を作成する
+///
+/// フレームは`{保存先}/{stem}/{stem}_00001.jpg`からの連番で保存し、1枚ごとに途中経過を記録する。
+/// 動画は撮影が終わってから保存したフレームを読み込んで作成するため、
+/// 撮影中にアプリが終了しても、それまでのフレームは失われず続きから再開できる。
+pub struct TimelapseSession {
+    /// 書き込みスレッドのハンドル (終了時に結果を返す)
+    handle: Option<JoinHandle<TimelapseSummary>>,
+    /// 書き込みスレッドと共有する進捗
+    progress: Arc<SharedProgress>,
+    /// 撮影する枚数
+    total: u32,
+    /// 撮影間隔
+    interval: Duration,
+}
+
+impl TimelapseSession {
+    /// 書き込みスレッドを起動 (新規・再開のどちらも途中経過から始める)
+    ///
+    /// # 引数
+    /// * `output_dir` - 保存先ディレクトリ (フレームはこの下の`stem`のフォルダに置く)
+    /// * `state` - 途中経過 (新規の場合は保存済みの枚数が0)
+    /// * `metadata` - 埋め込むメタデータ (撮影日時は各フレームの取得時刻で置き換える)
+    ///
+    /// # 戻り値
+    /// 成功時はタイムラプスセッションと、キャプチャスレッドに渡すフレームの選択器。
+    /// フォルダ・途中経過を作成できない場合や、新規なのにフォルダに撮影途中の
+    /// タイムラプスがある場合は`Err(CameraError)`
+    pub fn start(
+        output_dir: &Path,
+        state: TimelapseState,
+        metadata: Option<PhotoMetadata>,
+    ) -> Result<(Self, IntervalTap), CameraError> {
+        let dir = output_dir.join(&state.stem);
+        // 新規のタイムラプスで、撮影済みのフレームがある別のタイムラプスを上書きしない
+        if state.captured == 0 {
+            if let Ok(existing) = TimelapseState::load(&dir) {
+                if existing.captured > 0 {
+                    return Err(CameraError::InvalidConfig(format!(
+                        "撮影途中のタイムラプスがあるフォルダには保存できません: {}",
+                        dir.display()
+                    )));
+                }
+            }
+        }
+        storage::ensure_dir(&dir)?;
+        state.save(&dir)?;
+
+        let (sender, receiver) = mpsc::sync_channel(TIMELAPSE_QUEUE_CAPACITY);
+        let progress = Arc::new(SharedProgress::default());
+        progress.captured.store(state.captured, Ordering::Relaxed);
+        let tap = IntervalTap::new(sender, state.options.interval(), state.remaining());
+        let total = state.total;
+        let interval = state.options.interval();
+
+        let handle = {
+            let output_dir = output_dir.to_path_buf();
+            let progress = progress.clone();
+            thread::spawn(move || {
+                timelapse_loop(receiver, &output_dir, dir, state, metadata, &progress)
+            })
+        };
+
+        Ok((
+            Self {
+                handle: Some(handle),
+                progress,
+                total,
+                interval,
+            },
+            tap,
+        ))
+    }
+
+    /// 撮影を打ち切って仕上げるよう要求 (選択器を破棄すると残りを保存して動画を作成する)
+    pub fn request_finish(&self) {
+        self.progress
+            .finish_requested
+            .store(true, Ordering::Relaxed);
+    }
+
+    /// 進捗
+    pub fn progress(&self) -> TimelapseProgress {
+        let captured = self.progress.captured.load(Ordering::Relaxed);
+        let compiled = self
+            .progress
+            .compiling
+            .load(Ordering::Relaxed)
+            .then(|| self.progress.compiled.load(Ordering::Relaxed));
+        TimelapseProgress {
+            captured,
+            total: self.total,
+            compiled,
+            remaining: self.interval * self.total.saturating_sub(captured),
+        }
+    }
+
+    /// 書き込みスレッドが終了したかどうか
+    pub fn is_finished(&self) -> bool {
+        self.handle
+            .as_ref()
+            .is_none_or(|handle| handle.is_finished())
+    }
+
+    /// 書き込みスレッドの終了を待ち、結果を返す
+    ///
+    /// 選択器 (`IntervalTap`) が破棄されてから呼ぶこと。
+    /// `request_finish`を呼んでいない場合、撮影途中であれば動画は作成せずに途中経過を残す。
+    pub fn finish(mut self) -> Option<TimelapseSummary> {
+        self.handle.take().and_then(|handle| handle.join().ok())
+    }
+}
+
+/// 書き込みスレッドの本体
+///
+/// 送信口が破棄されるまでフレームを保存し、最後まで撮影したか仕上げを要求された場合は
+/// 動画を作成して途中経過を削除する。それ以外の場合は途中経過を残して終了する。
+fn timelapse_loop(
+    receiver: Receiver<TimedFrame>,
+    output_dir: &Path,
+    dir: PathBuf,
+    mut state: TimelapseState,
+    metadata: Option<PhotoMetadata>,
+    progress: &SharedProgress,
+) -> TimelapseSummary {
+    let mut summary = TimelapseSummary {
+        dir: dir.clone(),
+        ..TimelapseSummary::default()
+    };
+
+    for timed in receiver {
+        let index = state.captured + 1;
+        let path = state.frame_path(&dir, index);
+        let metadata = metadata
+            .clone()
+            .map(|metadata| metadata.taken_at_instant(timed.captured_at));
+
+        if let Err(e) = photo::save_photo(&path, &timed.frame, &state.photo, metadata.as_ref()) {
+            // 容量不足等で保存できない場合は中断する (途中経過は残るため再開できる)
+            summary.errors.push(e);
+            break;
+        }
+        state.captured = index;
+        progress.captured.store(index, Ordering::Relaxed);
+        // 途中経過を保存できなくても、再開時に保存済みのフレームを数え直す
+        if let Err(e) = state.save(&dir) {
+            log::warn!("{}", e);
+        }
+    }
+    summary.captured = state.captured;
+
+    let complete = state.captured >= state.total;
+    if !complete && !progress.finish_requested.load(Ordering::Relaxed) {
+        log::info!(
+            "タイムラプスを中断しました: {:?} ({}/{}枚)",
+            dir,
+            state.captured,
+            state.total
+        );
+        return summary;
+    }
+
+    if state.options.make_video && state.captured > 0 {
+        progress.compiling.store(true, Ordering::Relaxed);
+        let extension = state.video_container.extension();
+        let path = filename::unique_path(output_dir, &state.stem, extension);
+        match compile_video(&dir, &state, &path, &progress.compiled) {
+            Ok(()) => {
+                log::info!("タイムラプスの動画を作成しました: {:?}", path);
+                summary.video = Some(path);
+            }
+            Err(e) => {
+                // フレームと途中経過は残し、後から作成し直せるようにする
+                summary.errors.push(e);
+                return summary;
+            }
+        }
+    }
+
+    remove_working_files(&dir, &state);
+    summary.finished = true;
+    summary
+}
+
+/// 保存したフレームを順に読み込み、再生FPSの動画に書き込む
+///
+/// 撮影途中で解像度が変わった場合は、最初のフレームの大きさに合わせる。
+///
+/// # 戻り値
+/// 成功時は`Ok(())`、動画を開けない・書き込めない場合は`Err(CameraError)`
+fn compile_video(
+    dir: &Path,
+    state: &TimelapseState,
+    path: &Path,
+    compiled: &AtomicU32,
+) -> Result<(), CameraError> {
+    let conversion = |e: opencv::Error| CameraError::Conversion(e.to_string());
+    let codec = FourCc::parse(&state.video_codec).unwrap_or(FourCc::MJPG);
+    let fps = f64::from(state.options.playback_fps);
+    let mut output: Option<(VideoWriter, Size)> = None;
+    let mut summary = RecordingSummary::default();
+
+    for index in 1..=state.captured {
+        let frame_path = state.frame_path(dir, index);
+        let frame = imgcodecs::imread(&frame_path.to_string_lossy(), imgcodecs::IMREAD_COLOR)
+            .map_err(conversion)?;
+        if frame.empty() {
+            log::warn!("読み込めないフレームを飛ばします: {:?}", frame_path);
+            continue;
+        }
+
+        let frame_size = frame.size().map_err(conversion)?;
+        let (writer, size) = match output.as_mut() {
+            Some(opened) => opened,
+            None => output.insert((
+                recorder::open_writer_with_fallback(path, codec, fps, frame_size)?.0,
+                frame_size,
+            )),
+        };
+
+        if frame_size == *size {
+            recorder::write_frame(writer, &frame, &mut summary);
+        } else {
+            let mut resized = Mat::default();
+            imgproc::resize(&frame, &mut resized, *size, 0.0, 0.0, imgproc::INTER_AREA)
+                .map_err(conversion)?;
+            recorder::write_frame(writer, &resized, &mut summary);
+        }
+        compiled.store(index, Ordering::Relaxed);
+    }
+
+    // VideoWriterを解放してファイルをクローズ
+    if output.take().is_none() {
+        return Err(CameraError::Conversion(
+            "動画にできるフレームがありません".to_string(),
+        ));
+    }
+    if summary.frames_failed > 0 {
+        return Err(CameraError::FrameWrite {
+            path: path.to_path_buf(),
+            count: summary.frames_failed,
+        });
+    }
+    Ok(())
+}
+
+/// 仕上げ後に不要になったファイルを削除
+///
+/// 途中経過は常に削除する。写真を残さない設定の場合はフレームも削除し、
+/// フォルダが空になれば削除する (利用者が置いた他のファイルは消さない)。
+fn remove_working_files(dir: &Path, state: &TimelapseState) {
+    let mut targets = vec![dir.join(STATE_FILE_NAME)];
+    if !state.options.save_photos {
+        targets.extend((1..=state.captured).map(|index| state.frame_path(dir, index)));
+    }
+    for path in targets {
+        if let Err(e) = fs::remove_file(&path) {
+            if e.kind() != ErrorKind::NotFound {
+                log::warn!("ファイルを削除できませんでした: {:?} ({})", path, e);
+            }
+        }
+    }
+    if !state.options.save_photos {
+        // 空でない場合は失敗するだけなので無視する
+        let _ = fs::remove_dir(dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // 標準ライブラリのインポート
+    use std::error::Error;
+
+    // サードパーティクレートのインポート
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// 枚数で終わるタイムラプスの途中経過
+    fn state(stem: &str, total: u32) -> TimelapseState {
+        let options = TimelapseOptions {
+            count: total,
+            ..TimelapseOptions::default()
+        };
+        TimelapseState::new(
+            stem.to_string(),
+            options,
+            PhotoOptions::default(),
+            VideoFormat::SAFE,
+        )
+    }
+
+    /// 保存先の下にフレームを置くフォルダを作り、途中経過と`frames`枚の (空の) フレームを置く
+    fn write_state(
+        output: &TempDir,
+        state: &TimelapseState,
+        frames: u32,
+    ) -> Result<PathBuf, Box<dyn Error>> {
+        let dir = output.path().join(&state.stem);
+        fs::create_dir_all(&dir)?;
+        state.save(&dir)?;
+        for index in 1..=frames {
+            fs::write(state.frame_path(&dir, index), b"")?;
+        }
+        Ok(dir)
+    }
+
+    #[test]
+    fn total_frames_by_count_and_duration() {
+        let by_count = TimelapseOptions {
+            limit: TimelapseLimit::Count,
+            count: 120,
+            ..TimelapseOptions::default()
+        };
+        assert_eq!(by_count.total_frames(), 120);
+        assert_eq!(by_count.video_length(), Duration::from_secs(4));
+
+        // 60分を7秒間隔: 3600 / 7 = 514.3 → 開始時の1枚を含めて515枚
+        let by_duration = TimelapseOptions {
+            limit: TimelapseLimit::Duration,
+            interval_secs: 7,
+            duration_mins: 60,
+            playback_fps: 5,
+            ..TimelapseOptions::default()
+        };
+        assert_eq!(by_duration.total_frames(), 515);
+        assert_eq!(by_duration.video_length(), Duration::from_secs(103));
+
+        // 上限を超える枚数は切り詰める
+        let too_many = TimelapseOptions {
+            limit: TimelapseLimit::Duration,
+            interval_secs: 1,
+            duration_mins: MAX_TIMELAPSE_DURATION_MINS,
+            ..TimelapseOptions::default()
+        };
+        assert_eq!(too_many.total_frames(), MAX_TIMELAPSE_FRAMES);
+    }
+
+    #[test]
+    fn clamp_limits_values_and_keeps_some_output() {
+        let mut options = TimelapseOptions {
+            interval_secs: 0,
+            count: MAX_TIMELAPSE_FRAMES + 1,
+            duration_mins: 0,
+            playback_fps: MAX_PLAYBACK_FPS + 1,
+            save_photos: false,
+            make_video: false,
+            ..TimelapseOptions::default()
+        };
+        options.clamp();
+        assert_eq!(options.interval_secs, 1);
+        assert_eq!(options.count, MAX_TIMELAPSE_FRAMES);
+        assert_eq!(options.duration_mins, 1);
+        assert_eq!(options.playback_fps, MAX_PLAYBACK_FPS);
+        assert!(options.save_photos);
+    }
+
+    #[test]
+    fn load_recounts_frames_saved_after_state() -> Result<(), Box<dyn Error>> {
+        let output = tempfile::tempdir()?;
+        let mut saved = state("timelapse_a", 5);
+        saved.captured = 1;
+        // 途中経過の更新前に終了した: 3枚目まで保存済み
+        let dir = write_state(&output, &saved, 3)?;
+
+        let loaded = TimelapseState::load(&dir)?;
+        assert_eq!(loaded.captured, 3);
+        assert_eq!(loaded.remaining(), 2);
+        assert_eq!(loaded.stem, saved.stem);
+        Ok(())
+    }
+
+    #[test]
+    fn load_does_not_count_past_total() -> Result<(), Box<dyn Error>> {
+        let output = tempfile::tempdir()?;
+        let dir = write_state(&output, &state("timelapse_b", 2), 4)?;
+        assert_eq!(TimelapseState::load(&dir)?.captured, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn find_unfinished_skips_finished_and_corrupt_folders() -> Result<(), Box<dyn Error>> {
+        let output = tempfile::tempdir()?;
+        let unfinished = write_state(&output, &state("timelapse_1", 10), 2)?;
+
+        // 仕上げ済み: 途中経過が削除され、フレームだけが残っている
+        let finished = state("timelapse_2", 2);
+        let finished_dir = write_state(&output, &finished, 2)?;
+        remove_working_files(&finished_dir, &finished);
+
+        // 途中経過が壊れている
+        let corrupt = output.path().join("timelapse_3");
+        fs::create_dir(&corrupt)?;
+        fs::write(corrupt.join(STATE_FILE_NAME), "captured = \"many\"")?;
+
+        // 保存先直下の写真はフォルダではない
+        fs::write(output.path().join("photo.jpg"), b"")?;
+
+        let found = find_unfinished(output.path());
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].dir, unfinished);
+        assert_eq!(found[0].state.captured, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn find_unfinished_in_missing_dir_is_empty() -> Result<(), Box<dyn Error>> {
+        let output = tempfile::tempdir()?;
+        assert!(find_unfinished(&output.path().join("missing")).is_empty());
+        Ok(())
+    }
+}
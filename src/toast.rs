@@ -0,0 +1,149 @@
+// 標準ライブラリのインポート
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// サードパーティクレートのインポート
+use eframe::egui;
+
+/// 情報・成功の通知を表示する時間
+const INFO_DURATION: Duration = Duration::from_secs(4);
+/// 警告・エラーの通知を表示する時間 (読み逃さないように長めに表示)
+const ERROR_DURATION: Duration = Duration::from_secs(10);
+/// 同時に表示する通知の最大数 (超えた場合は古いものから消す)
+const MAX_TOASTS: usize = 5;
+/// 画面右端からの余白 (ピクセル)
+const TOAST_MARGIN: f32 = 12.0;
+/// 画面下端からの余白 (ステータスバーに重ならないように確保, ピクセル)
+const TOAST_BOTTOM_MARGIN: f32 = 40.0;
+/// 通知の最大幅 (長いパスは折り返す)
+const TOAST_MAX_WIDTH: f32 = 360.0;
+
+/// 通知の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastLevel {
+    Info,    // 情報
+    Success, // 保存・録画開始等の成功
+    Warning, // 警告 (処理は継続)
+    Error,   // エラー (処理に失敗)
+}
+
+impl ToastLevel {
+    /// 通知の先頭に表示するアイコン
+    fn icon(self) -> &'static str {
+        match self {
+            ToastLevel::Info => "ℹ",
+            ToastLevel::Success => "✔",
+            ToastLevel::Warning => "⚠",
+            ToastLevel::Error => "✖",
+        }
+    }
+
+    /// アイコンの色
+    fn color(self) -> egui::Color32 {
+        match self {
+            ToastLevel::Info => egui::Color32::LIGHT_BLUE,
+            ToastLevel::Success => egui::Color32::GREEN,
+            ToastLevel::Warning => egui::Color32::YELLOW,
+            ToastLevel::Error => egui::Color32::RED,
+        }
+    }
+
+    /// 表示する時間
+    fn duration(self) -> Duration {
+        match self {
+            ToastLevel::Info | ToastLevel::Success => INFO_DURATION,
+            ToastLevel::Warning | ToastLevel::Error => ERROR_DURATION,
+        }
+    }
+}
+
+/// 表示中の通知
+struct Toast {
+    /// 通知の種類
+    level: ToastLevel,
+    /// 表示するメッセージ
+    message: String,
+    /// 表示を開始した時刻
+    shown_at: Instant,
+}
+
+/// 画面右下に一定時間表示する通知 (トースト) の一覧
+///
+/// 写真の保存先や録画の開始・停止、エラーを、コンソールを見ないGUI利用者にも伝える。
+#[derive(Default)]
+pub struct Toasts {
+    /// 表示中の通知 (古い順)
+    toasts: VecDeque<Toast>,
+}
+
+impl Toasts {
+    /// 通知を追加
+    pub fn push(&mut self, level: ToastLevel, message: impl Into<String>) {
+        if self.toasts.len() >= MAX_TOASTS {
+            self.toasts.pop_front();
+        }
+        self.toasts.push_back(Toast {
+            level,
+            message: message.into(),
+            shown_at: Instant::now(),
+        });
+    }
+
+    /// 情報の通知を追加
+    pub fn info(&mut self, message: impl Into<String>) {
+        self.push(ToastLevel::Info, message);
+    }
+
+    /// 成功の通知を追加
+    pub fn success(&mut self, message: impl Into<String>) {
+        self.push(ToastLevel::Success, message);
+    }
+
+    /// 警告の通知を追加
+    pub fn warning(&mut self, message: impl Into<String>) {
+        self.push(ToastLevel::Warning, message);
+    }
+
+    /// エラーの通知を追加
+    pub fn error(&mut self, message: impl Into<String>) {
+        self.push(ToastLevel::Error, message);
+    }
+
+    /// 期限切れの通知を消し、残りを画面右下に重ねて描画
+    ///
+    /// 各通知の✖ボタンで期限前に閉じることができる。
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let now = Instant::now();
+        self.toasts
+            .retain(|toast| now.duration_since(toast.shown_at) < toast.level.duration());
+        if self.toasts.is_empty() {
+            return;
+        }
+
+        let mut dismissed = None;
+        egui::Area::new(egui::Id::new("toasts"))
+            .anchor(
+                egui::Align2::RIGHT_BOTTOM,
+                egui::vec2(-TOAST_MARGIN, -TOAST_BOTTOM_MARGIN),
+            )
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                for (index, toast) in self.toasts.iter().enumerate() {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.set_max_width(TOAST_MAX_WIDTH);
+                        ui.horizontal(|ui| {
+                            ui.colored_label(toast.level.color(), toast.level.icon());
+                            ui.add(egui::Label::new(&toast.message).wrap());
+                            if ui.small_button("✖").clicked() {
+                                dismissed = Some(index);
+                            }
+                        });
+                    });
+                }
+            });
+
+        if let Some(index) = dismissed {
+            self.toasts.remove(index);
+        }
+    }
+}